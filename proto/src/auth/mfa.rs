@@ -0,0 +1,372 @@
+// garden-core/src/auth/mfa.rs
+//
+// Step-up (multi-factor) authentication. A signed `AuthToken` is normally
+// all-or-nothing, but sensitive paths (`groups/<id>/metadata`, `devices/<id>`)
+// deserve a second factor. Issuing and verifying a challenge here produces a
+// `Timestamp` the caller stamps into `AuthToken::mfa_verified_until`; see
+// `AuthToken::can_access_path` for how that gates `Capability::MfaRequired`.
+use std::collections::HashMap;
+
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::clock::HybridLogicalClock;
+use crate::types::Timestamp;
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// How long a confirmed step-up stays valid once verified.
+pub const DEFAULT_STEP_UP_VALIDITY_MS: u64 = 15 * 60 * 1000;
+const TOTP_STEP_SECONDS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum AuthenticatorType {
+    Totp,
+    Sms,
+    WebAuthn,
+    RecoveryCode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaChallenge {
+    pub challenge_id: String,
+    pub authenticator: AuthenticatorType,
+    pub expires_at: Timestamp,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MfaError {
+    #[error("MFA challenge not found or already consumed")]
+    UnknownChallenge,
+    #[error("MFA challenge expired")]
+    Expired,
+    #[error("verification code did not match")]
+    CodeMismatch,
+    #[error("no verifier is registered for {0:?}")]
+    NoVerifier(AuthenticatorType),
+}
+
+/// The outcome of a successful `MfaAuthenticator::verify_mfa` call: enough
+/// for the caller to stamp `AuthToken::mfa_verified_at`/`mfa_verified_until`
+/// and record which factor satisfied the challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepUp {
+    pub factor: AuthenticatorType,
+    pub verified_at: Timestamp,
+    pub valid_until: Timestamp,
+}
+
+/// A pluggable check for a single response against a previously enrolled
+/// `secret`, used for factor types the library can't verify on its own
+/// (SMS delivery, WebAuthn assertions) - the app layer registers one per
+/// `AuthenticatorType` via `MfaAuthenticator::register_verifier`.
+pub trait FactorVerifier: Send + Sync {
+    fn verify(&self, secret: &[u8], response: &str, now: Timestamp) -> bool;
+}
+
+/// Verifies an SMS/recovery-style one-time code by comparing its SHA-256
+/// hash to the enrolled `secret`, without ever storing the code itself.
+pub struct HashedCodeVerifier;
+
+impl FactorVerifier for HashedCodeVerifier {
+    fn verify(&self, secret: &[u8], response: &str, _now: Timestamp) -> bool {
+        constant_time_eq(&Sha256::digest(response.as_bytes()), secret)
+    }
+}
+
+struct PendingChallenge {
+    authenticator: AuthenticatorType,
+    expires_at: Timestamp,
+    /// TOTP shared secret, a SHA-256 hash of the one-time SMS/recovery
+    /// code, or whatever enrollment data the registered `FactorVerifier`
+    /// for `authenticator` expects.
+    secret: Vec<u8>,
+}
+
+/// Tracks outstanding MFA challenges for one `AuthToken` holder across the
+/// two-step `request_mfa` → `verify_mfa` flow. TOTP is always verified
+/// in-process (RFC 6238); other factor types are checked via whatever
+/// `FactorVerifier` the app layer has registered for them, defaulting to a
+/// hashed one-time-code comparison for `Sms`/`RecoveryCode`.
+pub struct MfaAuthenticator {
+    pending: HashMap<String, PendingChallenge>,
+    verifiers: HashMap<AuthenticatorType, Box<dyn FactorVerifier>>,
+}
+
+impl Default for MfaAuthenticator {
+    fn default() -> Self {
+        let mut verifiers: HashMap<AuthenticatorType, Box<dyn FactorVerifier>> = HashMap::new();
+        verifiers.insert(AuthenticatorType::Sms, Box::new(HashedCodeVerifier));
+        verifiers.insert(AuthenticatorType::RecoveryCode, Box::new(HashedCodeVerifier));
+        Self { pending: HashMap::new(), verifiers }
+    }
+}
+
+impl MfaAuthenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register (or replace) the `FactorVerifier` used for `authenticator`.
+    /// The app layer calls this to wire in a real SMS gateway or WebAuthn
+    /// relying-party check; `Totp` can't be overridden this way since it's
+    /// always verified in-process.
+    pub fn register_verifier(&mut self, authenticator: AuthenticatorType, verifier: Box<dyn FactorVerifier>) {
+        self.verifiers.insert(authenticator, verifier);
+    }
+
+    /// Issue a challenge. For TOTP, `secret` is the shared secret the user
+    /// already has enrolled; for SMS/recovery codes, `secret` is the
+    /// SHA-256 hash of the one-time code delivered out of band.
+    pub fn request_mfa(
+        &mut self,
+        authenticator: AuthenticatorType,
+        secret: Vec<u8>,
+        now: Timestamp,
+        validity_ms: u64,
+    ) -> MfaChallenge {
+        let mut id_bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut id_bytes);
+        let challenge_id = hex::encode(id_bytes);
+
+        let (physical_now, _) = HybridLogicalClock::unpack(now);
+        let expires_at = HybridLogicalClock::at_physical_time(physical_now + validity_ms);
+
+        self.pending.insert(
+            challenge_id.clone(),
+            PendingChallenge {
+                authenticator: authenticator.clone(),
+                expires_at,
+                secret,
+            },
+        );
+
+        MfaChallenge {
+            challenge_id,
+            authenticator,
+            expires_at,
+        }
+    }
+
+    /// Verify a response to a previously issued challenge. On success the
+    /// challenge is consumed (one-time use) and a `StepUp` describing which
+    /// factor satisfied it and how long the step-up stays fresh is returned,
+    /// ready to stamp into `AuthToken::mfa_verified_at`/`mfa_verified_until`.
+    pub fn verify_mfa(
+        &mut self,
+        challenge_id: &str,
+        code: &str,
+        now: Timestamp,
+    ) -> Result<StepUp, MfaError> {
+        let challenge = self
+            .pending
+            .get(challenge_id)
+            .ok_or(MfaError::UnknownChallenge)?;
+
+        if challenge.expires_at <= now {
+            self.pending.remove(challenge_id);
+            return Err(MfaError::Expired);
+        }
+
+        let matches = match challenge.authenticator {
+            AuthenticatorType::Totp => Ok(verify_totp(&challenge.secret, code, now)),
+            ref other => self
+                .verifiers
+                .get(other)
+                .map(|verifier| verifier.verify(&challenge.secret, code, now))
+                .ok_or_else(|| MfaError::NoVerifier(other.clone())),
+        };
+        let factor = challenge.authenticator.clone();
+
+        self.pending.remove(challenge_id);
+
+        match matches? {
+            true => {
+                let (physical_now, _) = HybridLogicalClock::unpack(now);
+                Ok(StepUp {
+                    factor,
+                    verified_at: now,
+                    valid_until: HybridLogicalClock::at_physical_time(
+                        physical_now + DEFAULT_STEP_UP_VALIDITY_MS,
+                    ),
+                })
+            }
+            false => Err(MfaError::CodeMismatch),
+        }
+    }
+}
+
+/// Per-capability freshness requirements for a step-up: how recently an
+/// `AuthToken` must have completed MFA for access to a `MfaRequired` path to
+/// be granted, layered on top of whatever `has_capability` already allows.
+/// Paths with no matching entry fall back to `mfa::DEFAULT_STEP_UP_VALIDITY_MS`.
+#[derive(Default)]
+pub struct MfaPolicy {
+    windows: HashMap<String, u64>,
+}
+
+impl MfaPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require a step-up completed within `window_ms` for any path under
+    /// `prefix` - e.g. a short window for `groups/*/metadata` writes and a
+    /// longer one for ordinary `MfaRequired` reads.
+    pub fn require_fresh(&mut self, prefix: &str, window_ms: u64) {
+        self.windows.insert(prefix.to_string(), window_ms);
+    }
+
+    /// The freshness window that applies to `path`, if any. When more than
+    /// one registered prefix matches, the longest (most specific) one wins.
+    pub fn window_for(&self, path: &str) -> Option<u64> {
+        self.windows
+            .iter()
+            .filter(|(prefix, _)| prefix.as_str() == "*" || path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, window_ms)| *window_ms)
+    }
+}
+
+fn totp_code(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = ((hash[offset] as u32 & 0x7f) << 24)
+        | ((hash[offset + 1] as u32) << 16)
+        | ((hash[offset + 2] as u32) << 8)
+        | (hash[offset + 3] as u32);
+
+    binary % 1_000_000
+}
+
+/// RFC 6238 TOTP verification: HMAC-SHA1 over `floor(now/30)`, dynamic
+/// truncation to a 6-digit code, accepting the adjacent time step either
+/// side to absorb clock skew between client and server.
+fn verify_totp(secret: &[u8], code: &str, now: Timestamp) -> bool {
+    let (physical_now, _) = HybridLogicalClock::unpack(now);
+    let counter = (physical_now / 1000) / TOTP_STEP_SECONDS;
+
+    for drift in [-1i64, 0, 1] {
+        let step = match counter as i64 + drift {
+            s if s >= 0 => s as u64,
+            _ => continue,
+        };
+        if format!("{:06}", totp_code(secret, step)) == code {
+            return true;
+        }
+    }
+    false
+}
+
+/// Byte-for-byte comparison that doesn't short-circuit on the first
+/// mismatch, so a failed SMS/recovery-code check can't be used as a timing
+/// oracle to find the right code one byte at a time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totp_round_trips_within_drift_window() {
+        let secret = b"totp-shared-secret".to_vec();
+        let mut mfa = MfaAuthenticator::new();
+        let now = HybridLogicalClock::now();
+        let challenge = mfa.request_mfa(AuthenticatorType::Totp, secret.clone(), now, 300_000);
+
+        let (physical_now, _) = HybridLogicalClock::unpack(now);
+        let counter = (physical_now / 1000) / TOTP_STEP_SECONDS;
+        let code = format!("{:06}", totp_code(&secret, counter));
+
+        let step_up = mfa.verify_mfa(&challenge.challenge_id, &code, now).unwrap();
+        assert_eq!(step_up.factor, AuthenticatorType::Totp);
+        assert!(step_up.valid_until > now);
+    }
+
+    #[test]
+    fn wrong_totp_code_is_rejected_and_consumes_the_challenge() {
+        let secret = b"totp-shared-secret".to_vec();
+        let mut mfa = MfaAuthenticator::new();
+        let now = HybridLogicalClock::now();
+        let challenge = mfa.request_mfa(AuthenticatorType::Totp, secret, now, 300_000);
+
+        assert_eq!(mfa.verify_mfa(&challenge.challenge_id, "000000", now), Err(MfaError::CodeMismatch));
+        // Challenge was consumed by the failed attempt.
+        assert_eq!(
+            mfa.verify_mfa(&challenge.challenge_id, "000000", now),
+            Err(MfaError::UnknownChallenge)
+        );
+    }
+
+    #[test]
+    fn recovery_code_matches_its_hash() {
+        let code = "recover-me-123";
+        let secret = Sha256::digest(code.as_bytes()).to_vec();
+        let mut mfa = MfaAuthenticator::new();
+        let now = HybridLogicalClock::now();
+        let challenge = mfa.request_mfa(AuthenticatorType::RecoveryCode, secret, now, 300_000);
+
+        assert!(mfa.verify_mfa(&challenge.challenge_id, code, now).is_ok());
+    }
+
+    #[test]
+    fn webauthn_fails_closed_without_a_registered_verifier() {
+        let mut mfa = MfaAuthenticator::new();
+        let now = HybridLogicalClock::now();
+        let challenge = mfa.request_mfa(AuthenticatorType::WebAuthn, vec![], now, 300_000);
+
+        assert_eq!(
+            mfa.verify_mfa(&challenge.challenge_id, "assertion", now),
+            Err(MfaError::NoVerifier(AuthenticatorType::WebAuthn))
+        );
+    }
+
+    #[test]
+    fn a_registered_verifier_is_used_for_webauthn() {
+        struct AcceptsValidAssertion;
+        impl FactorVerifier for AcceptsValidAssertion {
+            fn verify(&self, _secret: &[u8], response: &str, _now: Timestamp) -> bool {
+                response == "valid-assertion"
+            }
+        }
+
+        let mut mfa = MfaAuthenticator::new();
+        mfa.register_verifier(AuthenticatorType::WebAuthn, Box::new(AcceptsValidAssertion));
+        let now = HybridLogicalClock::now();
+        let challenge = mfa.request_mfa(AuthenticatorType::WebAuthn, vec![], now, 300_000);
+
+        let step_up = mfa.verify_mfa(&challenge.challenge_id, "valid-assertion", now).unwrap();
+        assert_eq!(step_up.factor, AuthenticatorType::WebAuthn);
+    }
+
+    #[test]
+    fn policy_picks_the_most_specific_matching_window() {
+        let mut policy = MfaPolicy::new();
+        policy.require_fresh("*", 900_000);
+        policy.require_fresh("groups", 300_000);
+        policy.require_fresh("groups/g1/metadata", 60_000);
+
+        assert_eq!(policy.window_for("groups/g1/metadata"), Some(60_000));
+        assert_eq!(policy.window_for("groups/g2/metadata"), Some(300_000));
+        assert_eq!(policy.window_for("messages/inbox"), Some(900_000));
+    }
+
+    #[test]
+    fn policy_has_no_window_for_an_unmatched_path() {
+        let policy = MfaPolicy::new();
+        assert_eq!(policy.window_for("anything"), None);
+    }
+}
@@ -0,0 +1,1044 @@
+// garden-core/src/auth/mod.rs
+pub mod mfa;
+
+use crate::auth::mfa::{AuthenticatorType, MfaPolicy};
+use crate::clock::HybridLogicalClock;
+use crate::identity::device_list::DeviceList;
+use crate::identity::Capability;
+use serde::{Serialize, Deserialize};
+use crate::types::Timestamp;
+use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Binds a delegated `AuthToken` to the exact parent token it was carved out
+/// of. `issuer_signature_hash` is a hash of the parent's own signature (not
+/// just its data), so the proof can't be reattached to a different token
+/// signed by the same key. `issuer_token` is embedded so `verify_chain` can
+/// walk all the way to a trusted root offline, without a side lookup for
+/// each ancestor. `authorization` is the parent's own signature over
+/// `(child_public_key, capabilities, expires_at)` - the actual grant - so
+/// that merely observing a validly-signed parent token is never enough to
+/// mint a child for it; only whoever holds the parent's private key can
+/// produce a signature that validates here. See `verify_chain`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationProof {
+    pub issuer_signature_hash: Vec<u8>,
+    pub child_public_key: Vec<u8>,
+    pub issuer_token: Box<AuthToken>,
+    pub authorization: Vec<u8>,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DelegationError {
+    #[error("parent token has no signature to delegate from")]
+    ParentNotSigned,
+    #[error("capability {0:?} is not implied by any capability of the parent token")]
+    NotAttenuated(Capability),
+    #[error("child token expires after its parent")]
+    ExpiresAfterParent,
+    #[error("a token in the chain has an invalid signature")]
+    InvalidSignature,
+    #[error("a proof's issuer_signature_hash does not match its embedded issuer_token")]
+    TamperedProof,
+    #[error("chain does not terminate at one of the trusted roots")]
+    UntrustedRoot,
+    #[error("a token in the chain has expired")]
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthToken {
+    /// Unique per-token id, independent of `device_id` (one device can hold
+    /// many tokens over its lifetime). Covered by the token signature, so
+    /// it can't be swapped after issuance; this is what `TokenStore`/
+    /// `verify_with_store` key revocation on, instead of having to revoke
+    /// every token a device ever held just to take back one of them.
+    pub id: String,
+    pub user_id: String,
+    pub device_id: String,
+    pub capabilities: Vec<Capability>,
+    pub signature: Option<Vec<u8>>,  // Signature is now optional (to be added later)
+    pub expires_at: Timestamp,
+    /// Set by a successful `mfa::MfaAuthenticator::verify_mfa` step-up; covered
+    /// by the token signature so it can't be forged or extended in transit.
+    #[serde(default)]
+    pub mfa_verified_until: Option<Timestamp>,
+    /// When the step-up that produced `mfa_verified_until` was completed -
+    /// lets `can_access_path_with_policy` measure freshness against a
+    /// per-capability window rather than only the fixed validity baked in
+    /// at verification time. Also covered by the token signature.
+    #[serde(default)]
+    pub mfa_verified_at: Option<Timestamp>,
+    /// Which factor satisfied the most recent step-up, so a caller (or
+    /// audit log) can tell a TOTP-gated action from a WebAuthn-gated one
+    /// without re-deriving it. Also covered by the token signature.
+    #[serde(default)]
+    pub mfa_factor: Option<AuthenticatorType>,
+    /// Present when this token was minted by `delegate` rather than issued
+    /// directly; proves a chain of custody back to whoever holds a trusted
+    /// root key. See `verify_chain`.
+    #[serde(default)]
+    pub proof: Option<DelegationProof>,
+    /// Proof that `device_id`'s key (the key that produced `signature`) was
+    /// itself authorized by the user's long-lived master key - see
+    /// `bootstrap_cross_signing`/`sign_device`. `None` for a token that
+    /// predates cross-signing or whose issuer doesn't use it; `verify`
+    /// simply skips the extra check in that case.
+    #[serde(default)]
+    pub device_key_certificate: Option<DeviceCertificate>,
+}
+
+/// Proof that `device_public_key` was authorized, for use as `device_id`,
+/// by whoever holds the master key behind `master_public_key`. Signed over
+/// `(device_id, device_public_key)` so it can't be replayed for a different
+/// device id or rebound to a different key. The master key that produces
+/// these never signs an `AuthToken` itself and never touches the network -
+/// only device subkeys do - so a stolen device key can authenticate as that
+/// one device but can't mint a certificate for a new one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeviceCertificate {
+    pub device_id: String,
+    pub device_public_key: Vec<u8>,
+    pub master_public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl DeviceCertificate {
+    fn signing_bytes(device_id: &str, device_public_key: &[u8]) -> Vec<u8> {
+        bincode::serialize(&(device_id, device_public_key))
+            .expect("device certificate signing tuple always serializes")
+    }
+
+    /// Have `master_key` certify that `device_id` owns `device_public_key`.
+    pub fn sign_device(
+        master_key: &SigningKey,
+        device_id: &str,
+        device_public_key: Vec<u8>,
+    ) -> Self {
+        let bytes = Self::signing_bytes(device_id, &device_public_key);
+        let signature = master_key.sign(&bytes).to_bytes().to_vec();
+        Self {
+            device_id: device_id.to_string(),
+            device_public_key,
+            master_public_key: master_key.verifying_key().to_bytes().to_vec(),
+            signature,
+        }
+    }
+
+    /// Verify this certificate against its own embedded master public key -
+    /// callers that need to pin a specific master key should additionally
+    /// compare `master_public_key` against the identity they expect.
+    pub fn verify(&self) -> bool {
+        let Ok(master_key_bytes): Result<[u8; 32], _> = self.master_public_key.as_slice().try_into() else {
+            return false;
+        };
+        let Ok(master_key) = VerifyingKey::from_bytes(&master_key_bytes) else {
+            return false;
+        };
+        let Ok(signature_bytes): Result<[u8; 64], _> = self.signature.as_slice().try_into() else {
+            return false;
+        };
+        let signature = Signature::from_bytes(&signature_bytes);
+        let bytes = Self::signing_bytes(&self.device_id, &self.device_public_key);
+        master_key.verify(&bytes, &signature).is_ok()
+    }
+}
+
+/// The master key and first device key produced by `bootstrap_cross_signing`.
+/// The master key should be stashed somewhere that never touches the
+/// network (a local keychain, a paper backup); only `first_device_key`
+/// (and any later `sign_device`-enrolled device key) ever signs a token.
+pub struct CrossSigningRoot {
+    pub master_key: SigningKey,
+    pub first_device_key: SigningKey,
+    pub first_device_certificate: DeviceCertificate,
+}
+
+/// Generate a fresh master key and self-sign `first_device_id` as its first
+/// device - the root of a user's cross-signing hierarchy. Enroll additional
+/// devices afterward with `DeviceCertificate::sign_device(&master_key, ...)`.
+pub fn bootstrap_cross_signing(first_device_id: &str) -> CrossSigningRoot {
+    let mut master_seed = [0u8; 32];
+    OsRng.fill_bytes(&mut master_seed);
+    let master_key = SigningKey::from_bytes(&master_seed);
+
+    let mut device_seed = [0u8; 32];
+    OsRng.fill_bytes(&mut device_seed);
+    let first_device_key = SigningKey::from_bytes(&device_seed);
+
+    let first_device_certificate = DeviceCertificate::sign_device(
+        &master_key,
+        first_device_id,
+        first_device_key.verifying_key().to_bytes().to_vec(),
+    );
+
+    CrossSigningRoot { master_key, first_device_key, first_device_certificate }
+}
+
+impl AuthToken {
+    pub fn is_valid(&self, now: Timestamp) -> bool {
+        self.expires_at > now
+    }
+
+    /// Reject access to `path` if it falls under a `Capability::MfaRequired`
+    /// prefix the token holds and the token hasn't stepped up recently enough.
+    /// Paths with no matching `MfaRequired` marker are unaffected - this only
+    /// layers the step-up gate on top of whatever `has_capability` already allows.
+    pub fn can_access_path(&self, path: &str, now: Timestamp) -> bool {
+        let requires_mfa = self.capabilities.iter().any(|cap| {
+            matches!(cap, Capability::MfaRequired(prefix) if path.starts_with(prefix.as_str()))
+        });
+
+        if !requires_mfa {
+            return true;
+        }
+
+        self.mfa_verified_until.is_some_and(|until| until > now)
+    }
+
+    /// As `can_access_path`, but the freshness required for a `MfaRequired`
+    /// path comes from `policy` rather than the fixed step-up validity baked
+    /// in at verification time - lets sensitive capabilities (`ManageGroup`,
+    /// writing group metadata) demand a tighter window than ordinary
+    /// `MfaRequired` reads. Falls back to `mfa::DEFAULT_STEP_UP_VALIDITY_MS`
+    /// for paths `policy` has no explicit window for.
+    pub fn can_access_path_with_policy(&self, path: &str, now: Timestamp, policy: &MfaPolicy) -> bool {
+        let requires_mfa = self.capabilities.iter().any(|cap| {
+            matches!(cap, Capability::MfaRequired(prefix) if path.starts_with(prefix.as_str()))
+        });
+
+        if !requires_mfa {
+            return true;
+        }
+
+        let Some(verified_at) = self.mfa_verified_at else {
+            return false;
+        };
+
+        let window_ms = policy.window_for(path).unwrap_or(crate::auth::mfa::DEFAULT_STEP_UP_VALIDITY_MS);
+        let (physical_now, _) = HybridLogicalClock::unpack(now);
+        let (physical_verified, _) = HybridLogicalClock::unpack(verified_at);
+        physical_now >= physical_verified && physical_now - physical_verified <= window_ms
+    }
+
+    /// As `can_access_path_with_policy`, but additionally requires `device_id`
+    /// to be an active member of `device_list` whenever `path` falls under
+    /// `devices/<user_id>/...` - a revoked device can't keep using a cached,
+    /// otherwise still-valid token to act on device-management paths. Takes
+    /// `policy` rather than relying on the fixed-validity `can_access_path`,
+    /// so it composes with the same per-prefix step-up freshness
+    /// `enforce_step_up` already enforces everywhere else.
+    pub fn can_access_device_path(&self, path: &str, now: Timestamp, policy: &MfaPolicy, device_list: &DeviceList) -> bool {
+        if !self.can_access_path_with_policy(path, now, policy) {
+            return false;
+        }
+
+        let device_prefix = format!("devices/{}/", self.user_id);
+        if path.starts_with(&device_prefix) {
+            return device_list.is_active_device(&self.device_id);
+        }
+
+        true
+    }
+
+    pub fn has_capability(&self, required: &Capability) -> bool {
+        // Direct match first
+        if self.capabilities.contains(required) {
+            return true;
+        }
+        
+        // Check for wildcard capabilities
+        match required {
+            Capability::ReadMessages(target) => {
+                // Check if user has wildcard read access
+                self.capabilities.iter().any(|cap| {
+                    if let Capability::ReadMessages(pattern) = cap {
+                        pattern == "*" || pattern == target
+                    } else {
+                        false
+                    }
+                })
+            },
+            Capability::WriteMessages(target) => {
+                // Check if user has wildcard write access
+                self.capabilities.iter().any(|cap| {
+                    if let Capability::WriteMessages(pattern) = cap {
+                        pattern == "*" || pattern == target
+                    } else {
+                        false
+                    }
+                })
+            },
+            Capability::ManageGroup(target) => {
+                // Check if user has wildcard group management
+                self.capabilities.iter().any(|cap| {
+                    if let Capability::ManageGroup(pattern) = cap {
+                        pattern == "*" || pattern == target
+                    } else {
+                        false
+                    }
+                })
+            },
+            Capability::ManageDevice(target) => {
+                // Check if user has wildcard device management
+                self.capabilities.iter().any(|cap| {
+                    if let Capability::ManageDevice(pattern) = cap {
+                        pattern == "*" || pattern == target
+                    } else {
+                        false
+                    }
+                })
+            },
+            // For capabilities without parameters, we already checked with contains
+            _ => false,
+        }
+    }
+
+    // Sign the AuthToken with a private key
+    pub fn sign(&mut self, signing_key: &SigningKey) {
+        // Create a temporary copy without the signature for serialization
+        let mut token_for_signing = self.clone();
+        token_for_signing.signature = None;
+        
+        // Serialize the AuthToken, excluding the signature
+        let token_data = bincode::serialize(&token_for_signing).expect("Failed to serialize AuthToken");
+        
+        // Create the signature
+        let signature = signing_key.sign(&token_data);
+
+        // Store the signature
+        self.signature = Some(signature.to_bytes().to_vec());
+    }
+
+    // Verify the AuthToken's signature with the public key
+    pub fn verify(&self, verifying_key: &VerifyingKey) -> bool {
+        if let Some(signature_bytes) = &self.signature {
+            // Create a clone of the token without the signature for consistent serialization
+            let mut token_for_verification = self.clone();
+            token_for_verification.signature = None;
+            
+            // Serialize the AuthToken excluding the signature
+            let token_data = bincode::serialize(&token_for_verification).expect("Failed to serialize AuthToken");
+            
+            // Convert the signature back from bytes
+            let signature_array: [u8; 64] = match signature_bytes.as_slice().try_into() {
+                Ok(array) => array,
+                Err(_) => return false,
+            };
+            
+            // Create signature from bytes - this returns a Signature directly, not a Result
+            let signature = Signature::from_bytes(&signature_array);
+            
+            // Verify the signature using the public key
+            verifying_key.verify(&token_data, &signature).is_ok()
+        } else {
+            false
+        }
+    }
+
+    /// Check both that `required` is granted and that the delegation chain
+    /// behind this token is genuine - every signature in it verifies, every
+    /// link is attenuated from its parent, nothing in it has expired, and it
+    /// is rooted in one of `trusted_roots`. `has_capability` alone only
+    /// looks at this token's own `capabilities` list, which is only
+    /// trustworthy once its chain has actually been checked - this is the
+    /// one-call version for a caller that hasn't already done so.
+    pub fn has_verified_capability(
+        &self,
+        required: &Capability,
+        trusted_roots: &[VerifyingKey],
+        now: Timestamp,
+    ) -> Result<bool, DelegationError> {
+        self.verify_chain(trusted_roots, now)?;
+        Ok(self.has_capability(required))
+    }
+
+    /// As `verify`, but additionally rejects a token whose `id` has been
+    /// revoked in `store` - the only way to take a single capability grant
+    /// back before `expires_at`, since nothing else about an otherwise
+    /// correctly-signed, unexpired token is falsifiable. See
+    /// `crate::token_store::TokenStore`.
+    pub fn verify_with_store(
+        &self,
+        verifying_key: &VerifyingKey,
+        store: &dyn crate::token_store::TokenStore,
+        now: Timestamp,
+    ) -> Result<bool, crate::token_store::TokenStoreError> {
+        if !self.is_valid(now) || !self.verify(verifying_key) {
+            return Ok(false);
+        }
+        Ok(!store.is_revoked(&self.id)?)
+    }
+
+    /// As `verify`, but additionally requires a `device_key_certificate`
+    /// binding `device_key` to this token's `device_id` and produced by
+    /// `trusted_master_key` specifically - proof the device key was
+    /// actually enrolled, by the expected identity, via
+    /// `bootstrap_cross_signing`/`sign_device`, not just that it produced a
+    /// signature that happens to verify. `DeviceCertificate::verify` alone
+    /// only checks a certificate against whatever master key is embedded in
+    /// it, which anyone can self-generate; pinning the caller's own
+    /// `trusted_master_key` here is what actually ties the certificate to a
+    /// specific user rather than to an arbitrary, attacker-controlled one. A
+    /// stolen device key still passes plain `verify`, but can't satisfy this
+    /// for any `device_id` other than the one it was already certified for,
+    /// since minting a new certificate requires the master key it never had.
+    pub fn verify_cross_signed(&self, device_key: &VerifyingKey, trusted_master_key: &VerifyingKey) -> bool {
+        if !self.verify(device_key) {
+            return false;
+        }
+        let Some(cert) = &self.device_key_certificate else { return false };
+        cert.device_id == self.device_id
+            && cert.device_public_key == device_key.to_bytes().to_vec()
+            && cert.master_public_key == trusted_master_key.to_bytes().to_vec()
+            && cert.verify()
+    }
+
+    fn delegation_authorization_bytes(child_public_key: &[u8], capabilities: &[Capability], expires_at: Timestamp) -> Vec<u8> {
+        bincode::serialize(&(child_public_key, capabilities, expires_at))
+            .expect("delegation authorization tuple always serializes")
+    }
+
+    /// Mint a child token carved out of `self`, authorized by `parent_key`
+    /// and signed by `child_key`. `parent_key` must be the key that actually
+    /// signed `self` - checked here, not just assumed - so delegating
+    /// requires possessing the parent's private key, not merely a copy of
+    /// its (public) signed token. `subset` must be attenuated - every
+    /// capability in it must be implied by some capability `self` holds
+    /// (see `Capability::implies`) - and `expires_at` must not outlive the
+    /// parent. The resulting token carries a `DelegationProof` - including
+    /// `parent_key`'s own signature over `child_key`'s public half and the
+    /// granted subset/expiry - back to `self`, so `verify_chain` can check
+    /// its provenance without any other party's cooperation.
+    pub fn delegate(
+        &self,
+        parent_key: &SigningKey,
+        child_key: &SigningKey,
+        subset: Vec<Capability>,
+        expires_at: Timestamp,
+    ) -> Result<AuthToken, DelegationError> {
+        let parent_signature = self.signature.as_ref().ok_or(DelegationError::ParentNotSigned)?;
+
+        if !self.verify(&parent_key.verifying_key()) {
+            return Err(DelegationError::InvalidSignature);
+        }
+
+        if expires_at > self.expires_at {
+            return Err(DelegationError::ExpiresAfterParent);
+        }
+
+        for cap in &subset {
+            let attenuated = self.capabilities.iter().any(|parent_cap| parent_cap.implies(cap));
+            if !attenuated {
+                return Err(DelegationError::NotAttenuated(cap.clone()));
+            }
+        }
+
+        let child_public_key = child_key.verifying_key().to_bytes().to_vec();
+        let authorization_bytes = Self::delegation_authorization_bytes(&child_public_key, &subset, expires_at);
+        let authorization = parent_key.sign(&authorization_bytes).to_bytes().to_vec();
+
+        let proof = DelegationProof {
+            issuer_signature_hash: Sha256::digest(parent_signature).to_vec(),
+            child_public_key,
+            issuer_token: Box::new(self.clone()),
+            authorization,
+        };
+
+        let mut child = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: self.user_id.clone(),
+            device_id: uuid::Uuid::new_v4().to_string(),
+            capabilities: subset,
+            signature: None,
+            expires_at,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: Some(proof),
+            device_key_certificate: None,
+        };
+        child.sign(child_key);
+        Ok(child)
+    }
+
+    /// Walk this token's `proof` chain back to a trusted root, checking at
+    /// every hop that: this token is signed by the proof's stated
+    /// `child_public_key`, the parent actually authorized that exact key for
+    /// that exact capability subset and expiry (`authorization`, verified
+    /// against the parent's *own* validated signing key - recovered by
+    /// recursing into the parent's chain, never taken from a field the
+    /// presenter controls), the proof hasn't been reattached to a different
+    /// parent, the child doesn't outlive its parent, nothing in the chain
+    /// has expired, and every capability the child carries is attenuated
+    /// from one the parent held. A token with no proof is only accepted as
+    /// a root if its own signature verifies against one of `trusted_roots`.
+    pub fn verify_chain(&self, trusted_roots: &[VerifyingKey], now: Timestamp) -> Result<(), DelegationError> {
+        self.verify_chain_inner(trusted_roots, now).map(|_| ())
+    }
+
+    /// As `verify_chain`, but returns the `VerifyingKey` this specific token
+    /// was actually signed with - so a child one level down can check that
+    /// its own `authorization` was signed by *that* key, rather than by
+    /// whatever key a forged `DelegationProof` merely claims is the issuer.
+    fn verify_chain_inner(&self, trusted_roots: &[VerifyingKey], now: Timestamp) -> Result<VerifyingKey, DelegationError> {
+        if !self.is_valid(now) {
+            return Err(DelegationError::Expired);
+        }
+
+        let Some(proof) = &self.proof else {
+            return trusted_roots
+                .iter()
+                .find(|root| self.verify(root))
+                .copied()
+                .ok_or(DelegationError::UntrustedRoot);
+        };
+
+        let child_key_bytes: [u8; 32] = proof
+            .child_public_key
+            .as_slice()
+            .try_into()
+            .map_err(|_| DelegationError::InvalidSignature)?;
+        let child_key = VerifyingKey::from_bytes(&child_key_bytes)
+            .map_err(|_| DelegationError::InvalidSignature)?;
+
+        if !self.verify(&child_key) {
+            return Err(DelegationError::InvalidSignature);
+        }
+
+        let issuer_signature = proof
+            .issuer_token
+            .signature
+            .as_ref()
+            .ok_or(DelegationError::TamperedProof)?;
+        if Sha256::digest(issuer_signature).to_vec() != proof.issuer_signature_hash {
+            return Err(DelegationError::TamperedProof);
+        }
+
+        // The only trustworthy source for "the parent's real signing key" is
+        // recursively validating the parent's own chain - never a field this
+        // proof supplies about itself.
+        let parent_key = proof.issuer_token.verify_chain_inner(trusted_roots, now)?;
+
+        let authorization_bytes = Self::delegation_authorization_bytes(&proof.child_public_key, &self.capabilities, self.expires_at);
+        let authorization_bytes_array: [u8; 64] = proof
+            .authorization
+            .as_slice()
+            .try_into()
+            .map_err(|_| DelegationError::InvalidSignature)?;
+        let authorization_signature = Signature::from_bytes(&authorization_bytes_array);
+        if parent_key.verify(&authorization_bytes, &authorization_signature).is_err() {
+            return Err(DelegationError::InvalidSignature);
+        }
+
+        if self.expires_at > proof.issuer_token.expires_at {
+            return Err(DelegationError::ExpiresAfterParent);
+        }
+
+        for cap in &self.capabilities {
+            let attenuated = proof.issuer_token.capabilities.iter().any(|parent_cap| parent_cap.implies(cap));
+            if !attenuated {
+                return Err(DelegationError::NotAttenuated(cap.clone()));
+            }
+        }
+
+        Ok(child_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Capability;
+    use rand::RngCore;
+
+    fn test_signing_key() -> SigningKey {
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        SigningKey::from_bytes(&bytes)
+    }
+
+    fn root_token(signing_key: &SigningKey, capabilities: Vec<Capability>, expires_at: Timestamp) -> AuthToken {
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "root-user".to_string(),
+            device_id: "root-device".to_string(),
+            capabilities,
+            signature: None,
+            expires_at,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        };
+        token.sign(signing_key);
+        token
+    }
+
+    #[test]
+    fn a_delegated_chain_verifies_against_its_root() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::ManageGroup("*".to_string())], 1000);
+
+        let intermediate_key = test_signing_key();
+        let intermediate = root
+            .delegate(&root_key, &intermediate_key, vec![Capability::ManageGroup("garden-1".to_string())], 500)
+            .unwrap();
+
+        let end_user_key = test_signing_key();
+        let end_user = intermediate
+            .delegate(&intermediate_key, &end_user_key, vec![Capability::ManageGroup("garden-1".to_string())], 100)
+            .unwrap();
+
+        assert_eq!(end_user.verify_chain(&[root_key.verifying_key()], 0), Ok(()));
+    }
+
+    #[test]
+    fn a_chain_not_rooted_in_a_trusted_key_is_rejected() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::CreateInvites], 1000);
+
+        let other_key = test_signing_key();
+        let child = root.delegate(&root_key, &other_key, vec![Capability::CreateInvites], 500).unwrap();
+
+        let untrusted_key = test_signing_key();
+        assert_eq!(child.verify_chain(&[untrusted_key.verifying_key()], 0), Err(DelegationError::UntrustedRoot));
+    }
+
+    #[test]
+    fn delegating_without_the_parents_own_signing_key_is_rejected() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::ManageGroup("*".to_string())], 1000);
+
+        // Attacker never held `root_key` - they only ever observed `root`,
+        // a validly-signed, fully public token - and tries to mint a child
+        // from it anyway, signing both "parent" and child side with keys
+        // they generated themselves.
+        let attacker_key = test_signing_key();
+        let attacker_child_key = test_signing_key();
+        let result = root.delegate(
+            &attacker_key,
+            &attacker_child_key,
+            vec![Capability::ManageGroup("victim-garden".to_string())],
+            500,
+        );
+
+        assert_eq!(result.unwrap_err(), DelegationError::InvalidSignature);
+    }
+
+    #[test]
+    fn a_proof_claiming_a_key_that_never_signed_the_parent_is_rejected() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::ManageGroup("*".to_string())], 1000);
+
+        let attacker_key = test_signing_key();
+        let attacker_child_key = test_signing_key();
+        let mut child = root
+            .delegate(&root_key, &attacker_child_key, vec![Capability::ManageGroup("garden-1".to_string())], 500)
+            .unwrap();
+
+        // Forge the proof's authorization as if `attacker_key`, not
+        // `root_key`, had produced it - `child_public_key` still matches
+        // what actually signed `child`, so only the authorization check
+        // catches this.
+        let forged_authorization_bytes = AuthToken::delegation_authorization_bytes(
+            &attacker_child_key.verifying_key().to_bytes().to_vec(),
+            &child.capabilities,
+            child.expires_at,
+        );
+        child.proof.as_mut().unwrap().authorization =
+            attacker_key.sign(&forged_authorization_bytes).to_bytes().to_vec();
+
+        assert_eq!(child.verify_chain(&[root_key.verifying_key()], 0), Err(DelegationError::InvalidSignature));
+    }
+
+    #[test]
+    fn delegating_a_broader_capability_than_the_parent_holds_is_rejected() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::WriteMessages("groups/g1".to_string())], 1000);
+
+        let child_key = test_signing_key();
+        let result = root.delegate(&root_key, &child_key, vec![Capability::WriteMessages("*".to_string())], 500);
+
+        assert_eq!(result.unwrap_err(), DelegationError::NotAttenuated(Capability::WriteMessages("*".to_string())));
+    }
+
+    #[test]
+    fn delegating_an_expiry_past_the_parent_is_rejected() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::CreateInvites], 500);
+
+        let child_key = test_signing_key();
+        let result = root.delegate(&root_key, &child_key, vec![Capability::CreateInvites], 600);
+
+        assert_eq!(result.unwrap_err(), DelegationError::ExpiresAfterParent);
+    }
+
+    #[test]
+    fn reattaching_a_proof_to_a_tampered_issuer_token_is_rejected() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::CreateInvites], 1000);
+
+        let child_key = test_signing_key();
+        let mut child = root.delegate(&root_key, &child_key, vec![Capability::CreateInvites], 500).unwrap();
+
+        // Swap in a different (still validly-signed) issuer token without
+        // updating the signature hash binding the proof to the original.
+        let mut tampered_root = root.clone();
+        tampered_root.capabilities.push(Capability::AdminAccess);
+        tampered_root.sign(&root_key);
+        child.proof.as_mut().unwrap().issuer_token = Box::new(tampered_root);
+
+        assert_eq!(child.verify_chain(&[root_key.verifying_key()], 0), Err(DelegationError::TamperedProof));
+    }
+
+    #[test]
+    fn has_verified_capability_checks_the_chain_before_trusting_the_capability_list() {
+        let root_key = test_signing_key();
+        let root = root_token(&root_key, vec![Capability::WriteMessages("*".to_string())], 1000);
+
+        let device_key = test_signing_key();
+        let device = root
+            .delegate(&root_key, &device_key, vec![Capability::WriteMessages("inbox".to_string())], 500)
+            .unwrap();
+
+        assert_eq!(
+            device.has_verified_capability(&Capability::WriteMessages("inbox".to_string()), &[root_key.verifying_key()], 0),
+            Ok(true),
+        );
+        assert_eq!(
+            device.has_verified_capability(&Capability::WriteMessages("other".to_string()), &[root_key.verifying_key()], 0),
+            Ok(false),
+        );
+
+        let untrusted_key = test_signing_key();
+        assert_eq!(
+            device.has_verified_capability(&Capability::WriteMessages("inbox".to_string()), &[untrusted_key.verifying_key()], 0),
+            Err(DelegationError::UntrustedRoot),
+        );
+    }
+
+    #[test]
+    fn a_token_signed_by_a_certified_device_key_passes_cross_signed_verification() {
+        let root = bootstrap_cross_signing("device-a");
+
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-a".to_string(),
+            capabilities: vec![Capability::CreateInvites],
+            signature: None,
+            expires_at: 1000,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: Some(root.first_device_certificate.clone()),
+        };
+        token.sign(&root.first_device_key);
+
+        assert!(token.verify_cross_signed(&root.first_device_key.verifying_key(), &root.master_key.verifying_key()));
+    }
+
+    #[test]
+    fn sign_device_enrolls_an_additional_device_under_the_same_master_key() {
+        let root = bootstrap_cross_signing("device-a");
+
+        let second_device_key = test_signing_key();
+        let second_device_certificate = DeviceCertificate::sign_device(
+            &root.master_key,
+            "device-b",
+            second_device_key.verifying_key().to_bytes().to_vec(),
+        );
+
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-b".to_string(),
+            capabilities: vec![Capability::CreateInvites],
+            signature: None,
+            expires_at: 1000,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: Some(second_device_certificate),
+        };
+        token.sign(&second_device_key);
+
+        assert!(token.verify_cross_signed(&second_device_key.verifying_key(), &root.master_key.verifying_key()));
+    }
+
+    #[test]
+    fn a_token_signed_by_an_uncertified_device_key_fails_cross_signed_verification() {
+        let root = bootstrap_cross_signing("device-a");
+        let rogue_device_key = test_signing_key();
+
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-rogue".to_string(),
+            capabilities: vec![Capability::CreateInvites],
+            signature: None,
+            expires_at: 1000,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        };
+        token.sign(&rogue_device_key);
+
+        // Plain `verify` only checks the signature, which is genuine...
+        assert!(token.verify(&rogue_device_key.verifying_key()));
+        // ...but there's no certificate tying this device key to the
+        // master key behind `root`, so cross-signed verification rejects it.
+        assert!(!token.verify_cross_signed(&rogue_device_key.verifying_key(), &root.master_key.verifying_key()));
+    }
+
+    #[test]
+    fn a_certificate_reused_for_a_different_device_key_fails_verification() {
+        let root = bootstrap_cross_signing("device-a");
+        let attacker_key = test_signing_key();
+
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-a".to_string(),
+            capabilities: vec![Capability::CreateInvites],
+            signature: None,
+            expires_at: 1000,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            // Certificate was issued for `first_device_key`, not `attacker_key`.
+            device_key_certificate: Some(root.first_device_certificate.clone()),
+        };
+        token.sign(&attacker_key);
+
+        assert!(!token.verify_cross_signed(&attacker_key.verifying_key(), &root.master_key.verifying_key()));
+    }
+
+    #[test]
+    fn a_self_issued_certificate_fails_verification_against_the_real_master_key() {
+        // An attacker can always run their own `bootstrap_cross_signing` and
+        // build a token naming any user_id/device_id they like, self-signed
+        // and self-certified end to end - `DeviceCertificate::verify` alone
+        // can't catch this, since the certificate is internally consistent.
+        // Only pinning the caller's actually-expected master key catches it.
+        let legitimate_root = bootstrap_cross_signing("device-a");
+        let forged_root = bootstrap_cross_signing("device-a");
+
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-a".to_string(),
+            capabilities: vec![Capability::AdminAccess],
+            signature: None,
+            expires_at: 1000,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: Some(forged_root.first_device_certificate.clone()),
+        };
+        token.sign(&forged_root.first_device_key);
+
+        // The certificate verifies fine in isolation...
+        assert!(forged_root.first_device_certificate.verify());
+        // ...but fails once checked against the real "user-a" master key.
+        assert!(!token.verify_cross_signed(
+            &forged_root.first_device_key.verifying_key(),
+            &legitimate_root.master_key.verifying_key(),
+        ));
+    }
+
+    #[test]
+    fn verify_with_store_rejects_a_revoked_token_id_but_accepts_it_before_revocation() {
+        use crate::token_store::{InMemoryTokenStore, TokenStore};
+
+        let signing_key = test_signing_key();
+        let mut token = AuthToken {
+            id: "token-1".to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-a".to_string(),
+            capabilities: vec![Capability::CreateInvites],
+            signature: None,
+            expires_at: 1000,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        };
+        token.sign(&signing_key);
+
+        let store = InMemoryTokenStore::new();
+        store.insert(&token).unwrap();
+
+        assert_eq!(token.verify_with_store(&signing_key.verifying_key(), &store, 0), Ok(true));
+
+        store.revoke(&token.id).unwrap();
+        assert_eq!(token.verify_with_store(&signing_key.verifying_key(), &store, 0), Ok(false));
+    }
+
+    #[test]
+    fn capability_implies_checks_path_attenuation() {
+        assert!(Capability::ReadMessages("*".to_string()).implies(&Capability::ReadMessages("groups/g1".to_string())));
+        assert!(Capability::WriteMessages("groups".to_string()).implies(&Capability::WriteMessages("groups/g1".to_string())));
+        assert!(!Capability::WriteMessages("groups/g1".to_string()).implies(&Capability::WriteMessages("groups/g2".to_string())));
+        assert!(!Capability::ManageGroup("g1".to_string()).implies(&Capability::ManageDevice("g1".to_string())));
+    }
+
+    #[test]
+    fn a_capability_specific_policy_window_can_be_tighter_than_the_default() {
+        use crate::auth::mfa::MfaPolicy;
+
+        let mut policy = MfaPolicy::new();
+        policy.require_fresh("groups", 1_000);
+
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-a".to_string(),
+            capabilities: vec![Capability::MfaRequired("groups".to_string())],
+            signature: None,
+            expires_at: u64::MAX,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        };
+
+        token.mfa_verified_at = Some(HybridLogicalClock::at_physical_time(0));
+        token.mfa_factor = Some(AuthenticatorType::Totp);
+        assert!(token.can_access_path_with_policy(
+            "groups/g1/metadata",
+            HybridLogicalClock::at_physical_time(500),
+            &policy,
+        ));
+        assert!(!token.can_access_path_with_policy(
+            "groups/g1/metadata",
+            HybridLogicalClock::at_physical_time(1_500),
+            &policy,
+        ));
+    }
+
+    #[test]
+    fn a_path_outside_the_policy_falls_back_to_the_default_step_up_window() {
+        use crate::auth::mfa::{MfaPolicy, DEFAULT_STEP_UP_VALIDITY_MS};
+
+        let policy = MfaPolicy::new();
+        let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: "device-a".to_string(),
+            capabilities: vec![Capability::MfaRequired("messages".to_string())],
+            signature: None,
+            expires_at: u64::MAX,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        };
+
+        token.mfa_verified_at = Some(HybridLogicalClock::at_physical_time(0));
+        assert!(token.can_access_path_with_policy(
+            "messages/inbox",
+            HybridLogicalClock::at_physical_time(DEFAULT_STEP_UP_VALIDITY_MS - 1),
+            &policy,
+        ));
+        assert!(!token.can_access_path_with_policy(
+            "messages/inbox",
+            HybridLogicalClock::at_physical_time(DEFAULT_STEP_UP_VALIDITY_MS + 1),
+            &policy,
+        ));
+    }
+
+    fn device_path_token(device_id: &str) -> AuthToken {
+        AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-a".to_string(),
+            device_id: device_id.to_string(),
+            capabilities: vec![Capability::ManageGroup("*".to_string())],
+            signature: None,
+            expires_at: u64::MAX,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        }
+    }
+
+    #[test]
+    fn an_active_device_can_access_its_own_device_path() {
+        use crate::auth::mfa::MfaPolicy;
+        use crate::identity::device_list::DeviceList;
+        use crate::identity::Device;
+
+        let mut device_list = DeviceList::new("user-a");
+        let signing_key = test_signing_key();
+        device_list.rotate(
+            vec![Device {
+                device_id: "device-a".to_string(),
+                public_key: vec![1, 2, 3],
+                signature: Vec::new(),
+                capabilities: Vec::new(),
+            }],
+            vec![],
+            &signing_key,
+        );
+
+        let token = device_path_token("device-a");
+        let policy = MfaPolicy::new();
+        assert!(token.can_access_device_path("devices/user-a/device-a", 0, &policy, &device_list));
+    }
+
+    #[test]
+    fn a_revoked_device_is_denied_its_own_device_path() {
+        use crate::auth::mfa::MfaPolicy;
+        use crate::identity::device_list::DeviceList;
+        use crate::identity::Device;
+
+        let mut device_list = DeviceList::new("user-a");
+        let signing_key = test_signing_key();
+        device_list.rotate(
+            vec![Device {
+                device_id: "device-a".to_string(),
+                public_key: vec![1, 2, 3],
+                signature: Vec::new(),
+                capabilities: Vec::new(),
+            }],
+            vec![],
+            &signing_key,
+        );
+        // Revoke the device the token claims to be acting as.
+        device_list.rotate(vec![], vec!["device-a".to_string()], &signing_key);
+
+        let token = device_path_token("device-a");
+        let policy = MfaPolicy::new();
+        assert!(!token.can_access_device_path("devices/user-a/device-a", 0, &policy, &device_list));
+    }
+
+    #[test]
+    fn a_path_outside_the_device_prefix_is_unaffected_by_device_list_membership() {
+        use crate::auth::mfa::MfaPolicy;
+        use crate::identity::device_list::DeviceList;
+
+        // No devices at all recorded for this user - would fail an
+        // is_active_device check, but the path below isn't a device path.
+        let device_list = DeviceList::new("user-a");
+        let token = device_path_token("device-a");
+        let policy = MfaPolicy::new();
+        assert!(token.can_access_device_path("groups/g1/metadata", 0, &policy, &device_list));
+    }
+}
+
@@ -0,0 +1,382 @@
+// garden-core/src/capability_ledger.rs
+//
+// Bayou-style replicated capability grants (as used in Aerogramme's sync
+// layer), adapted to `Capability` grants/revokes: a weakly-consistent,
+// append-only log of `GardenEntry::CapabilityOp` entries that converges to
+// identical state on every device regardless of the order ops are received
+// in. Ops are applied tentatively in arrival order and re-sorted into
+// `(timestamp, device_id)` order once replayed, rather than truly
+// speculative execution - `effective_for` always recomputes from the full
+// sorted log (or a checkpoint plus the ops above its watermark), so two
+// replicas holding the same set of ops always agree. `AccessControlService`
+// (the application's authorization layer) should consult
+// `CapabilityLedger::has_capability` for a subject rather than trusting only
+// the capabilities embedded in a single `AuthToken`.
+use std::collections::{HashMap, HashSet};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::entries::GardenEntry;
+use crate::identity::{Capability, Identity};
+use crate::types::Timestamp;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CapabilityAction {
+    Grant,
+    Revoke,
+}
+
+/// One entry in the replicated capability log - see
+/// `GardenEntry::CapabilityOp`, which this mirrors for in-memory use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityOp {
+    pub subject: String,
+    pub capability: Capability,
+    pub action: CapabilityAction,
+    pub timestamp: Timestamp,
+    pub device_id: String,
+}
+
+/// A signed fold of the log up to (and including) `watermark`: the
+/// effective capability set for every subject that appears in the log as of
+/// that point, so replay doesn't have to start from the beginning of an
+/// ever-growing log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub watermark: Timestamp,
+    pub effective: HashMap<String, HashSet<Capability>>,
+}
+
+impl Checkpoint {
+    /// `effective`'s `HashMap`/`HashSet`s iterate in an arbitrary,
+    /// per-process order, so signing `self` directly would make
+    /// `verify` fail for a checkpoint that round-tripped through another
+    /// process even though nothing changed. Sorting into a canonical
+    /// `Vec` form before serializing makes the signed bytes depend only on
+    /// the checkpoint's actual contents.
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut subjects: Vec<(&String, Vec<&Capability>)> = self.effective.iter()
+            .map(|(subject, capabilities)| {
+                let mut capabilities: Vec<&Capability> = capabilities.iter().collect();
+                capabilities.sort();
+                (subject, capabilities)
+            })
+            .collect();
+        subjects.sort_by(|a, b| a.0.cmp(b.0));
+
+        bincode::serialize(&(self.watermark, &subjects)).expect("checkpoint always serializes")
+    }
+
+    fn sign(&self, signing_key: &SigningKey) -> Vec<u8> {
+        signing_key.sign(&self.signing_bytes()).to_bytes().to_vec()
+    }
+
+    /// Verify this checkpoint was signed by `signed_by`'s long-term identity
+    /// key - callers are responsible for resolving `signed_by` to the right
+    /// `Identity` (e.g. via their device registry) before calling this.
+    pub fn verify(&self, signed_by: &Identity, signature: &[u8]) -> bool {
+        let Ok(public_key_bytes): Result<[u8; 32], _> = signed_by.public_key.clone().try_into() else {
+            return false;
+        };
+        let Ok(verifying_key) = VerifyingKey::from_bytes(&public_key_bytes) else {
+            return false;
+        };
+        let Ok(signature_bytes): Result<[u8; 64], _> = signature.try_into() else {
+            return false;
+        };
+        let signature = Signature::from_bytes(&signature_bytes);
+        verifying_key.verify(&self.signing_bytes(), &signature).is_ok()
+    }
+}
+
+/// Replays a `CapabilityOp` log (optionally rooted at a `Checkpoint`) into
+/// the effective capability set for every subject. Ops are accepted in any
+/// arrival order via `record`; `effective_for` always re-sorts the relevant
+/// ops by `(timestamp, device_id)` before folding, so two replicas that have
+/// seen the same ops converge on the same state even if they observed them
+/// in different orders.
+#[derive(Default)]
+pub struct CapabilityLedger {
+    checkpoint: Option<Checkpoint>,
+    ops: Vec<CapabilityOp>,
+}
+
+impl CapabilityLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_checkpoint(checkpoint: Checkpoint) -> Self {
+        Self { checkpoint: Some(checkpoint), ops: Vec::new() }
+    }
+
+    /// Append an op to the tentative log, in whatever order it was received.
+    pub fn record(&mut self, op: CapabilityOp) {
+        self.ops.push(op);
+    }
+
+    /// Fold a replicated `GardenEntry::CapabilityOp` or
+    /// `GardenEntry::CapabilityCheckpoint` into the ledger. Other variants
+    /// are ignored.
+    pub fn apply_entry(&mut self, entry: &GardenEntry) {
+        match entry {
+            GardenEntry::CapabilityOp { subject, capability, action, device_id, timestamp, .. } => {
+                self.record(CapabilityOp {
+                    subject: subject.clone(),
+                    capability: capability.clone(),
+                    action: action.clone(),
+                    timestamp: *timestamp,
+                    device_id: device_id.clone(),
+                });
+            }
+            GardenEntry::CapabilityCheckpoint { checkpoint, .. } => {
+                // A checkpoint only ever raises the watermark - an older one
+                // arriving after a newer one must not roll state back.
+                let should_adopt = self.checkpoint.as_ref()
+                    .map(|current| checkpoint.watermark > current.watermark)
+                    .unwrap_or(true);
+                if should_adopt {
+                    self.ops.retain(|op| op.timestamp > checkpoint.watermark);
+                    self.checkpoint = Some(checkpoint.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn fold(&self, subject: &str, upper_bound: Timestamp) -> HashSet<Capability> {
+        let watermark = self.checkpoint.as_ref().map(|c| c.watermark).unwrap_or(0);
+        let mut state: HashSet<Capability> = self.checkpoint.as_ref()
+            .and_then(|c| c.effective.get(subject))
+            .cloned()
+            .unwrap_or_default();
+
+        let mut relevant: Vec<&CapabilityOp> = self.ops.iter()
+            .filter(|op| op.subject == subject && op.timestamp > watermark && op.timestamp <= upper_bound)
+            .collect();
+        relevant.sort_by(|a, b| (a.timestamp, &a.device_id).cmp(&(b.timestamp, &b.device_id)));
+
+        // Batch ops that land on the same timestamp so a simultaneous
+        // Grant+Revoke of the same capability resolves to Revoke regardless
+        // of which one sorts first by device id.
+        let mut i = 0;
+        while i < relevant.len() {
+            let mut j = i;
+            while j < relevant.len() && relevant[j].timestamp == relevant[i].timestamp {
+                j += 1;
+            }
+            let batch = &relevant[i..j];
+            let mut granted: HashSet<&Capability> = HashSet::new();
+            let mut revoked: HashSet<&Capability> = HashSet::new();
+            for op in batch {
+                match op.action {
+                    CapabilityAction::Grant => { granted.insert(&op.capability); }
+                    CapabilityAction::Revoke => { revoked.insert(&op.capability); }
+                }
+            }
+            for capability in &granted {
+                if !revoked.contains(*capability) {
+                    state.insert((*capability).clone());
+                }
+            }
+            for capability in &revoked {
+                state.remove(*capability);
+            }
+            i = j;
+        }
+
+        state
+    }
+
+    /// The deterministic effective capability set for `subject`.
+    pub fn effective_for(&self, subject: &str) -> HashSet<Capability> {
+        self.fold(subject, Timestamp::MAX)
+    }
+
+    /// Whether `subject`'s effective set includes `capability`, including
+    /// via a wildcard (`Capability::implies`).
+    pub fn has_capability(&self, subject: &str, capability: &Capability) -> bool {
+        self.effective_for(subject).iter().any(|held| held.implies(capability))
+    }
+
+    /// Fold every op up to and including `watermark` into a new signed
+    /// checkpoint covering every subject seen so far, drop those ops, and
+    /// adopt the checkpoint as the ledger's new root. Returns the
+    /// checkpoint so the caller can publish it as a
+    /// `GardenEntry::CapabilityCheckpoint`.
+    pub fn checkpoint_at(&mut self, watermark: Timestamp, signing_key: &SigningKey) -> (Checkpoint, Vec<u8>) {
+        let mut subjects: HashSet<String> = self.ops.iter()
+            .filter(|op| op.timestamp <= watermark)
+            .map(|op| op.subject.clone())
+            .collect();
+        if let Some(existing) = &self.checkpoint {
+            subjects.extend(existing.effective.keys().cloned());
+        }
+
+        let effective = subjects.into_iter()
+            .map(|subject| {
+                let state = self.fold(&subject, watermark);
+                (subject, state)
+            })
+            .collect();
+
+        let checkpoint = Checkpoint { watermark, effective };
+        let signature = checkpoint.sign(signing_key);
+
+        self.ops.retain(|op| op.timestamp > watermark);
+        self.checkpoint = Some(checkpoint.clone());
+
+        (checkpoint, signature)
+    }
+
+    pub fn watermark(&self) -> Option<Timestamp> {
+        self.checkpoint.as_ref().map(|c| c.watermark)
+    }
+
+    pub fn pending_op_count(&self) -> usize {
+        self.ops.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SubspaceId;
+
+    fn op(subject: &str, capability: Capability, action: CapabilityAction, timestamp: Timestamp, device_id: &str) -> CapabilityOp {
+        CapabilityOp { subject: subject.to_string(), capability, action, timestamp, device_id: device_id.to_string() }
+    }
+
+    fn capability_op_entry(op: &CapabilityOp) -> GardenEntry {
+        GardenEntry::CapabilityOp {
+            subject: op.subject.clone(),
+            capability: op.capability.clone(),
+            action: op.action.clone(),
+            subspace_id: SubspaceId("sub-1".to_string()),
+            device_id: op.device_id.clone(),
+            timestamp: op.timestamp,
+        }
+    }
+
+    #[test]
+    fn a_granted_capability_is_effective() {
+        let mut ledger = CapabilityLedger::new();
+        ledger.record(op("user-a", Capability::CreateInvites, CapabilityAction::Grant, 100, "device-1"));
+
+        assert!(ledger.has_capability("user-a", &Capability::CreateInvites));
+        assert!(!ledger.has_capability("user-b", &Capability::CreateInvites));
+    }
+
+    #[test]
+    fn a_later_revoke_overrides_an_earlier_grant() {
+        let mut ledger = CapabilityLedger::new();
+        ledger.record(op("user-a", Capability::CreateInvites, CapabilityAction::Grant, 100, "device-1"));
+        ledger.record(op("user-a", Capability::CreateInvites, CapabilityAction::Revoke, 200, "device-2"));
+
+        assert!(!ledger.has_capability("user-a", &Capability::CreateInvites));
+    }
+
+    #[test]
+    fn ops_converge_to_the_same_state_regardless_of_arrival_order() {
+        let ops = vec![
+            op("user-a", Capability::CreateInvites, CapabilityAction::Grant, 100, "device-1"),
+            op("user-a", Capability::CreateInvites, CapabilityAction::Revoke, 200, "device-2"),
+            op("user-a", Capability::AdminAccess, CapabilityAction::Grant, 150, "device-1"),
+        ];
+
+        let mut received_in_order = CapabilityLedger::new();
+        for o in ops.clone() {
+            received_in_order.record(o);
+        }
+
+        let mut received_reversed = CapabilityLedger::new();
+        for o in ops.into_iter().rev() {
+            received_reversed.record(o);
+        }
+
+        assert_eq!(
+            received_in_order.effective_for("user-a"),
+            received_reversed.effective_for("user-a"),
+        );
+        assert!(received_in_order.has_capability("user-a", &Capability::AdminAccess));
+        assert!(!received_in_order.has_capability("user-a", &Capability::CreateInvites));
+    }
+
+    #[test]
+    fn a_concurrent_grant_and_revoke_at_the_same_timestamp_resolves_to_revoke() {
+        let mut ledger = CapabilityLedger::new();
+        // Same timestamp, different devices - order of `record` must not matter.
+        ledger.record(op("user-a", Capability::AdminAccess, CapabilityAction::Revoke, 100, "device-z"));
+        ledger.record(op("user-a", Capability::AdminAccess, CapabilityAction::Grant, 100, "device-a"));
+
+        assert!(!ledger.has_capability("user-a", &Capability::AdminAccess));
+    }
+
+    #[test]
+    fn checkpointing_bounds_replay_and_preserves_effective_state() {
+        let (_, signing_key) = Identity::generate_identity();
+        let mut ledger = CapabilityLedger::new();
+        ledger.record(op("user-a", Capability::CreateInvites, CapabilityAction::Grant, 100, "device-1"));
+        ledger.record(op("user-a", Capability::AdminAccess, CapabilityAction::Grant, 150, "device-1"));
+
+        let (checkpoint, _signature) = ledger.checkpoint_at(150, &signing_key);
+        assert_eq!(checkpoint.watermark, 150);
+        assert_eq!(ledger.pending_op_count(), 0);
+        assert!(ledger.has_capability("user-a", &Capability::CreateInvites));
+        assert!(ledger.has_capability("user-a", &Capability::AdminAccess));
+
+        ledger.record(op("user-a", Capability::AdminAccess, CapabilityAction::Revoke, 200, "device-2"));
+        assert!(ledger.has_capability("user-a", &Capability::CreateInvites));
+        assert!(!ledger.has_capability("user-a", &Capability::AdminAccess));
+    }
+
+    #[test]
+    fn a_checkpoint_signature_verifies_against_the_signing_identity() {
+        let (identity, signing_key) = Identity::generate_identity();
+        let (other_identity, _) = Identity::generate_identity();
+        let mut ledger = CapabilityLedger::new();
+        ledger.record(op("user-a", Capability::CreateInvites, CapabilityAction::Grant, 100, "device-1"));
+
+        let (checkpoint, signature) = ledger.checkpoint_at(100, &signing_key);
+
+        assert!(checkpoint.verify(&identity, &signature));
+        assert!(!checkpoint.verify(&other_identity, &signature));
+    }
+
+    #[test]
+    fn replaying_entries_produces_the_same_ledger_as_recording_ops_directly() {
+        let mut from_ops = CapabilityLedger::new();
+        from_ops.record(op("user-a", Capability::CreateInvites, CapabilityAction::Grant, 100, "device-1"));
+
+        let mut from_entries = CapabilityLedger::new();
+        from_entries.apply_entry(&capability_op_entry(&op(
+            "user-a",
+            Capability::CreateInvites,
+            CapabilityAction::Grant,
+            100,
+            "device-1",
+        )));
+
+        assert_eq!(from_ops.effective_for("user-a"), from_entries.effective_for("user-a"));
+    }
+
+    #[test]
+    fn an_older_checkpoint_arriving_late_does_not_roll_back_the_watermark() {
+        let (_, signing_key) = Identity::generate_identity();
+        let mut ledger = CapabilityLedger::new();
+        ledger.record(op("user-a", Capability::CreateInvites, CapabilityAction::Grant, 100, "device-1"));
+        let (newer, newer_sig) = ledger.checkpoint_at(200, &signing_key);
+
+        let stale_checkpoint = Checkpoint { watermark: 50, effective: HashMap::new() };
+        ledger.apply_entry(&GardenEntry::CapabilityCheckpoint {
+            subspace_id: SubspaceId("sub-1".to_string()),
+            checkpoint: stale_checkpoint,
+            signed_by: "device-1".to_string(),
+            signature: newer_sig,
+            timestamp: 50,
+        });
+
+        assert_eq!(ledger.watermark(), Some(newer.watermark));
+    }
+}
@@ -0,0 +1,236 @@
+// garden-core/src/commands.rs
+//
+// Runtime slash-command router: indexes published `GardenEntry::SlashCommand`
+// entries, recognizes `/`-prefixed content in `DirectMessage`/`GroupMessage`
+// entries, and runs a chain of hooks (permission checks, cooldowns, arg
+// validation) before handing the invocation off to webhook dispatch. Callers
+// are expected to have already decrypted `encrypted_content` into plaintext
+// before calling `parse_invocation` - the router itself never touches keys.
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::entries::GardenEntry;
+use crate::types::Timestamp;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandVisibility {
+    Public,
+    /// Only visible within the garden it was registered for.
+    Garden(String),
+    /// Only visible outside any garden (direct messages).
+    Private,
+}
+
+impl CommandVisibility {
+    fn allows(&self, group_id: Option<&str>) -> bool {
+        match self {
+            CommandVisibility::Public => true,
+            CommandVisibility::Garden(id) => group_id == Some(id.as_str()),
+            CommandVisibility::Private => group_id.is_none(),
+        }
+    }
+}
+
+/// One invocation of a slash command, already parsed out of message content.
+#[derive(Debug, Clone)]
+pub struct CommandContext {
+    pub command: String,
+    pub args: Vec<String>,
+    pub sender_id: String,
+    pub group_id: Option<String>,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookResult {
+    Continue,
+    /// Short-circuits dispatch; the string is surfaced back to the caller
+    /// as the reason (e.g. "on cooldown", "missing capability").
+    Block(String),
+}
+
+/// A pre-execution check run against every invocation before dispatch, in
+/// registration order, stopping at the first `Block`.
+pub type Hook = Box<dyn Fn(&CommandContext) -> HookResult + Send + Sync>;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RouterError {
+    #[error("no command registered: {0}")]
+    UnknownCommand(String),
+    #[error("command not visible in this context")]
+    NotVisible,
+    #[error("blocked: {0}")]
+    Blocked(String),
+}
+
+struct RegisteredCommand {
+    handler_url: String,
+    visibility: CommandVisibility,
+}
+
+/// Indexes registered slash commands and runs invocations through the hook
+/// chain before handing the resolved webhook URL back to the caller's
+/// dispatcher (see the Tauri backend's `data::commands::WebhookClient` for
+/// an example of what consumes that URL).
+#[derive(Default)]
+pub struct CommandRouter {
+    commands: HashMap<String, RegisteredCommand>,
+    hooks: Vec<Hook>,
+}
+
+impl CommandRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index a `GardenEntry::SlashCommand`, ignoring any other entry variant.
+    pub fn register(&mut self, entry: &GardenEntry) {
+        if let GardenEntry::SlashCommand {
+            command,
+            handler_url,
+            visibility,
+            group_id,
+            ..
+        } = entry
+        {
+            let visibility = match visibility.as_str() {
+                "public" => CommandVisibility::Public,
+                "garden" => CommandVisibility::Garden(group_id.clone().unwrap_or_default()),
+                _ => CommandVisibility::Private,
+            };
+
+            self.commands.insert(
+                command.clone(),
+                RegisteredCommand {
+                    handler_url: handler_url.clone(),
+                    visibility,
+                },
+            );
+        }
+    }
+
+    /// Add a hook to the end of the pre-execution chain.
+    pub fn add_hook(&mut self, hook: Hook) {
+        self.hooks.push(hook);
+    }
+
+    /// Recognize a `/command arg1 arg2` invocation inside a `DirectMessage`
+    /// or `GroupMessage` entry's already-decrypted plaintext content. Any
+    /// other entry variant, or content not starting with `/`, yields `None`.
+    pub fn parse_invocation(entry: &GardenEntry, plaintext_content: &str) -> Option<CommandContext> {
+        let (sender_id, group_id, timestamp) = match entry {
+            GardenEntry::DirectMessage { sender_id, timestamp, .. } => {
+                (sender_id.clone(), None, *timestamp)
+            }
+            GardenEntry::GroupMessage { sender_id, group_id, timestamp, .. } => {
+                (sender_id.clone(), Some(group_id.clone()), *timestamp)
+            }
+            _ => return None,
+        };
+
+        let rest = plaintext_content.strip_prefix('/')?;
+        let mut parts = rest.split_whitespace();
+        let command = parts.next()?.to_string();
+        let args = parts.map(str::to_string).collect();
+
+        Some(CommandContext {
+            command,
+            args,
+            sender_id,
+            group_id,
+            timestamp,
+        })
+    }
+
+    /// Run `ctx` through the hook chain and resolve it to a handler URL to
+    /// dispatch to, or the reason it was rejected.
+    pub fn route(&self, ctx: &CommandContext) -> Result<&str, RouterError> {
+        let registered = self
+            .commands
+            .get(&ctx.command)
+            .ok_or_else(|| RouterError::UnknownCommand(ctx.command.clone()))?;
+
+        if !registered.visibility.allows(ctx.group_id.as_deref()) {
+            return Err(RouterError::NotVisible);
+        }
+
+        for hook in &self.hooks {
+            if let HookResult::Block(reason) = hook(ctx) {
+                return Err(RouterError::Blocked(reason));
+            }
+        }
+
+        Ok(&registered.handler_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MessageType, SubspaceId};
+
+    fn registered_entry(command: &str, visibility: &str, group_id: Option<&str>) -> GardenEntry {
+        GardenEntry::SlashCommand {
+            command: command.to_string(),
+            description: None,
+            handler_url: "https://example.com/hook".to_string(),
+            visibility: visibility.to_string(),
+            creator_id: "creator-1".to_string(),
+            group_id: group_id.map(str::to_string),
+            timestamp: 0,
+            bot_token: None,
+        }
+    }
+
+    fn group_message(group_id: &str) -> GardenEntry {
+        GardenEntry::GroupMessage {
+            group_id: group_id.to_string(),
+            sender_id: "user-1".to_string(),
+            subspace_id: SubspaceId("sub-1".to_string()),
+            encrypted_content: vec![],
+            timestamp: 5,
+            message_type: MessageType::Text,
+            attachments: vec![],
+            epoch: 0,
+        }
+    }
+
+    #[test]
+    fn parses_and_routes_a_public_command() {
+        let mut router = CommandRouter::new();
+        router.register(&registered_entry("roll", "public", None));
+
+        let entry = group_message("garden-1");
+        let ctx = CommandRouter::parse_invocation(&entry, "/roll 2d6").unwrap();
+        assert_eq!(ctx.command, "roll");
+        assert_eq!(ctx.args, vec!["2d6".to_string()]);
+
+        assert_eq!(router.route(&ctx).unwrap(), "https://example.com/hook");
+    }
+
+    #[test]
+    fn garden_scoped_command_is_invisible_outside_its_garden() {
+        let mut router = CommandRouter::new();
+        router.register(&registered_entry("mod", "garden", Some("garden-1")));
+
+        let ctx = CommandRouter::parse_invocation(&group_message("garden-2"), "/mod ban").unwrap();
+        assert_eq!(router.route(&ctx), Err(RouterError::NotVisible));
+    }
+
+    #[test]
+    fn hook_can_block_dispatch() {
+        let mut router = CommandRouter::new();
+        router.register(&registered_entry("roll", "public", None));
+        router.add_hook(Box::new(|_ctx| HookResult::Block("on cooldown".to_string())));
+
+        let ctx = CommandRouter::parse_invocation(&group_message("garden-1"), "/roll").unwrap();
+        assert_eq!(router.route(&ctx), Err(RouterError::Blocked("on cooldown".to_string())));
+    }
+
+    #[test]
+    fn non_slash_content_does_not_parse() {
+        let entry = group_message("garden-1");
+        assert!(CommandRouter::parse_invocation(&entry, "just chatting").is_none());
+    }
+}
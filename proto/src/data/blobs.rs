@@ -0,0 +1,209 @@
+// garden-core/src/data/blobs.rs
+//
+// Content-addressed, chunked attachment storage. `AttachmentRef` carries a
+// manifest hash and a per-attachment symmetric key; the actual bytes live in
+// `sled` as individually encrypted, content-addressed chunks so large media
+// never has to round-trip through `serde_json` as a single blob. Every
+// attachment is encrypted under its own fresh key, so the same plaintext
+// chunk in two attachments is stored twice.
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::types::{AttachmentMetadata, AttachmentRef};
+
+/// Plaintext is split into fixed-size chunks, each encrypted and
+/// content-addressed on its own. `put_attachment` takes the whole plaintext
+/// and `get_attachment` returns it whole, so both hold the entire
+/// attachment in memory.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Error, Debug)]
+pub enum BlobError {
+    #[error("storage error: {0}")]
+    Storage(#[from] sled::Error),
+
+    #[error("serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("encryption/decryption failed")]
+    Crypto,
+
+    #[error("manifest not found for attachment hash {0}")]
+    ManifestNotFound(String),
+
+    #[error("chunk {index} missing from store (hash {hash})")]
+    ChunkMissing { index: usize, hash: String },
+
+    #[error("chunk {index} failed content-address verification: expected {expected}, got {actual}")]
+    ChunkCorrupted {
+        index: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Ordered list of chunk hashes plus the metadata needed to reassemble and
+/// label the attachment. Its own BLAKE3 hash becomes `AttachmentRef.hash`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    chunk_hashes: Vec<String>,
+    total_size: u64,
+    mime_type: String,
+    name: String,
+}
+
+/// Content-addressed attachment store backed by two `sled` trees: one for
+/// encrypted chunks keyed by their ciphertext hash, one for manifests.
+/// Identical plaintext chunks in different attachments are encrypted under
+/// different keys, so they get different hashes and are not deduplicated.
+pub struct BlobStore {
+    chunks: sled::Tree,
+    manifests: sled::Tree,
+}
+
+impl BlobStore {
+    pub fn new(db: &sled::Db) -> Result<Self, BlobError> {
+        Ok(Self {
+            chunks: db.open_tree("blob_chunks")?,
+            manifests: db.open_tree("blob_manifests")?,
+        })
+    }
+
+    /// Per-chunk nonces are derived from the chunk index rather than random,
+    /// since each attachment uses a freshly generated key and every index is
+    /// only ever encrypted once under that key.
+    fn nonce_for_chunk(chunk_index: u64) -> Nonce {
+        let mut bytes = [0u8; 12];
+        bytes[4..].copy_from_slice(&chunk_index.to_be_bytes());
+        *Nonce::from_slice(&bytes)
+    }
+
+    /// Encrypt, chunk, and content-address `plaintext`, returning the
+    /// `AttachmentRef` to embed in the `GardenEntry` carrying this attachment.
+    pub async fn put_attachment(
+        &self,
+        plaintext: &[u8],
+        name: &str,
+        mime_type: &str,
+    ) -> Result<AttachmentRef, BlobError> {
+        let mut key_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut key_bytes);
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key_bytes));
+
+        let mut chunk_hashes = Vec::new();
+        for (index, chunk) in plaintext.chunks(CHUNK_SIZE).enumerate() {
+            let nonce = Self::nonce_for_chunk(index as u64);
+            let ciphertext = cipher.encrypt(&nonce, chunk).map_err(|_| BlobError::Crypto)?;
+            let hash = blake3::hash(&ciphertext).to_hex().to_string();
+            // Dedup: identical ciphertext (same chunk, same key+index) overwrites
+            // the same key with an identical value - a no-op in practice.
+            self.chunks.insert(hash.as_bytes(), ciphertext)?;
+            chunk_hashes.push(hash);
+        }
+
+        let manifest = Manifest {
+            chunk_hashes,
+            total_size: plaintext.len() as u64,
+            mime_type: mime_type.to_string(),
+            name: name.to_string(),
+        };
+        let manifest_bytes = serde_json::to_vec(&manifest)?;
+        let manifest_hash = blake3::hash(&manifest_bytes).to_hex().to_string();
+        self.manifests.insert(manifest_hash.as_bytes(), manifest_bytes)?;
+
+        Ok(AttachmentRef {
+            hash: manifest_hash,
+            encryption_key: key_bytes.to_vec(),
+            metadata: AttachmentMetadata {
+                name: manifest.name,
+                mime_type: manifest.mime_type,
+                size: manifest.total_size,
+                thumbnail: None,
+            },
+        })
+    }
+
+    /// Fetch and decrypt an attachment's chunks in order, verifying each
+    /// chunk's content address before it's appended so corruption or
+    /// tampering is caught mid-stream rather than silently served.
+    pub async fn get_attachment(&self, attachment: &AttachmentRef) -> Result<Vec<u8>, BlobError> {
+        let manifest_bytes = self
+            .manifests
+            .get(attachment.hash.as_bytes())?
+            .ok_or_else(|| BlobError::ManifestNotFound(attachment.hash.clone()))?;
+        let manifest: Manifest = serde_json::from_slice(&manifest_bytes)?;
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&attachment.encryption_key));
+        let mut plaintext = Vec::with_capacity(manifest.total_size as usize);
+
+        for (index, expected_hash) in manifest.chunk_hashes.iter().enumerate() {
+            let ciphertext = self
+                .chunks
+                .get(expected_hash.as_bytes())?
+                .ok_or_else(|| BlobError::ChunkMissing {
+                    index,
+                    hash: expected_hash.clone(),
+                })?;
+
+            let actual_hash = blake3::hash(&ciphertext).to_hex().to_string();
+            if &actual_hash != expected_hash {
+                return Err(BlobError::ChunkCorrupted {
+                    index,
+                    expected: expected_hash.clone(),
+                    actual: actual_hash,
+                });
+            }
+
+            let nonce = Self::nonce_for_chunk(index as u64);
+            let chunk_plain = cipher
+                .decrypt(&nonce, ciphertext.as_ref())
+                .map_err(|_| BlobError::Crypto)?;
+            plaintext.extend_from_slice(&chunk_plain);
+        }
+
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> BlobStore {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        BlobStore::new(&db).unwrap()
+    }
+
+    #[tokio::test]
+    async fn round_trips_a_multi_chunk_attachment() {
+        let store = temp_store();
+        let plaintext = vec![7u8; CHUNK_SIZE * 2 + 123];
+
+        let attachment = store.put_attachment(&plaintext, "video.mp4", "video/mp4").await.unwrap();
+        assert_eq!(attachment.metadata.size, plaintext.len() as u64);
+
+        let decrypted = store.get_attachment(&attachment).await.unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[tokio::test]
+    async fn tampered_chunk_is_detected() {
+        let store = temp_store();
+        let attachment = store.put_attachment(b"hello attachment", "a.txt", "text/plain").await.unwrap();
+
+        // Corrupt every stored chunk in place.
+        for kv in store.chunks.iter() {
+            let (key, _) = kv.unwrap();
+            store.chunks.insert(key, b"corrupted".to_vec()).unwrap();
+        }
+
+        assert!(matches!(
+            store.get_attachment(&attachment).await,
+            Err(BlobError::ChunkCorrupted { .. })
+        ));
+    }
+}
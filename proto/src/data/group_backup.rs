@@ -0,0 +1,153 @@
+// garden-core/src/data/group_backup.rs
+//
+// Passphrase-encrypted export/import of a client's encrypted-group state
+// (see the mock group sessions in `p2p::GardenClient`), so membership in an
+// MLS-style group isn't lost on `shutdown()` or stranded on one device.
+// Argon2id stretches the passphrase into an XChaCha20-Poly1305 key; the
+// wider 192-bit XChaCha nonce means a nonce can be chosen at random on
+// every export without the birthday-bound concerns a 96-bit ChaCha20-Poly1305
+// nonce would have on a value that gets re-encrypted on every backup.
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+#[derive(Error, Debug)]
+pub enum GroupBackupError {
+    #[error("serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("passphrase key derivation failed")]
+    KeyDerivation,
+
+    #[error("decryption failed - wrong passphrase or corrupted bundle")]
+    Crypto,
+
+    #[error("bundle is too short to contain a salt and nonce")]
+    Truncated,
+}
+
+/// One group's exportable secret state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GroupKeyRecord {
+    pub group_id: String,
+    pub epoch: u64,
+    pub ratchet_key: Vec<u8>,
+    /// The group's aggregate DKG public key (see `crate::group_crypto`),
+    /// compressed to 32 bytes - needed alongside `ratchet_key` to restore a
+    /// usable encrypted-group session rather than just the raw secret.
+    pub group_public_key: Vec<u8>,
+    pub sender_user_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle {
+    groups: Vec<GroupKeyRecord>,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8; SALT_LEN]) -> Result<[u8; 32], GroupBackupError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|_| GroupBackupError::KeyDerivation)?;
+    Ok(key)
+}
+
+/// Encrypt `groups` into a self-contained bundle laid out as
+/// `salt || nonce || ciphertext`.
+pub fn export(groups: Vec<GroupKeyRecord>, passphrase: &str) -> Result<Vec<u8>, GroupBackupError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    let plaintext = serde_json::to_vec(&Bundle { groups })?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let ciphertext = cipher.encrypt(nonce, plaintext.as_ref()).map_err(|_| GroupBackupError::Crypto)?;
+
+    let mut bundle = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    bundle.extend_from_slice(&salt);
+    bundle.extend_from_slice(&nonce_bytes);
+    bundle.extend_from_slice(&ciphertext);
+    Ok(bundle)
+}
+
+/// Decrypt a bundle produced by `export`.
+pub fn import(bundle: &[u8], passphrase: &str) -> Result<Vec<GroupKeyRecord>, GroupBackupError> {
+    if bundle.len() < SALT_LEN + NONCE_LEN {
+        return Err(GroupBackupError::Truncated);
+    }
+    let salt: [u8; SALT_LEN] = bundle[0..SALT_LEN].try_into().expect("length checked above");
+    let nonce = XNonce::from_slice(&bundle[SALT_LEN..SALT_LEN + NONCE_LEN]);
+    let ciphertext = &bundle[SALT_LEN + NONCE_LEN..];
+
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let plaintext = cipher.decrypt(nonce, ciphertext).map_err(|_| GroupBackupError::Crypto)?;
+
+    let decoded: Bundle = serde_json::from_slice(&plaintext)?;
+    Ok(decoded.groups)
+}
+
+/// Merge `imported` into `existing`, keeping the higher epoch per group id
+/// so a stale backup can't roll a device back to a superseded key.
+pub fn merge_by_highest_epoch(existing: &mut Vec<GroupKeyRecord>, imported: Vec<GroupKeyRecord>) {
+    for record in imported {
+        match existing.iter_mut().find(|g| g.group_id == record.group_id) {
+            Some(current) if record.epoch > current.epoch => *current = record,
+            Some(_) => {}
+            None => existing.push(record),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(group_id: &str, epoch: u64) -> GroupKeyRecord {
+        GroupKeyRecord {
+            group_id: group_id.to_string(),
+            epoch,
+            ratchet_key: vec![epoch as u8; 32],
+            group_public_key: vec![epoch as u8; 32],
+            sender_user_ids: vec!["user-1".to_string()],
+        }
+    }
+
+    #[test]
+    fn a_bundle_round_trips_under_the_right_passphrase() {
+        let groups = vec![record("garden-1", 3), record("garden-2", 0)];
+        let bundle = export(groups.clone(), "correct horse battery staple").unwrap();
+
+        let imported = import(&bundle, "correct horse battery staple").unwrap();
+        assert_eq!(imported, groups);
+    }
+
+    #[test]
+    fn the_wrong_passphrase_fails_to_decrypt() {
+        let bundle = export(vec![record("garden-1", 0)], "right passphrase").unwrap();
+        assert!(matches!(import(&bundle, "wrong passphrase"), Err(GroupBackupError::Crypto)));
+    }
+
+    #[test]
+    fn merging_keeps_the_higher_epoch_and_rejects_stale_downgrades() {
+        let mut existing = vec![record("garden-1", 5)];
+
+        merge_by_highest_epoch(&mut existing, vec![record("garden-1", 2)]);
+        assert_eq!(existing[0].epoch, 5, "a stale backup must not roll epoch back");
+
+        merge_by_highest_epoch(&mut existing, vec![record("garden-1", 9), record("garden-2", 0)]);
+        assert_eq!(existing.len(), 2);
+        assert_eq!(existing[0].epoch, 9);
+    }
+}
@@ -0,0 +1,3 @@
+// garden-core/src/data/mod.rs
+pub mod blobs;
+pub mod group_backup;
@@ -1,4 +1,6 @@
 use serde::{Serialize, Deserialize};
+use crate::capability_ledger::{CapabilityAction, Checkpoint};
+use crate::identity::{Capability, Device};
 use crate::types::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +23,11 @@ pub enum GardenEntry {
         timestamp: Timestamp,
         message_type: MessageType,
         attachments: Vec<AttachmentRef>,
+        /// Which group epoch `encrypted_content` was sealed under. Lets a
+        /// receiver pick the matching ratchet key and reject messages from
+        /// an epoch it was removed before the group rekeyed past.
+        #[serde(default)]
+        epoch: u64,
     },
     FriendRequest {
         from: String,
@@ -79,4 +86,76 @@ pub enum GardenEntry {
         encrypted_key: Vec<u8>,
         timestamp: Timestamp,
     },
+    /// A signed, versioned device list for `user_id` (see
+    /// `crate::identity::device_list::DeviceList`). `version` is
+    /// monotonically increasing so a stale, still-validly-signed list can't
+    /// be replayed to roll back a revocation.
+    DeviceList {
+        user_id: String,
+        version: u64,
+        devices: Vec<Device>,
+        signature: Vec<u8>,
+        timestamp: Timestamp,
+    },
+    /// Immediate, expiry-independent revocation of one `(user_id, device_id)`
+    /// pair - published by a group owner with `Capability::ManageGroup` to
+    /// evict a compromised device without waiting for its outstanding
+    /// `AuthToken`s to time out. See `crate::revocation::RevocationRegistry`,
+    /// which folds these into a monotonic record: a later entry can never
+    /// un-revoke a device, only a fresh enrollment under a new `device_id`
+    /// restores access.
+    RevocationEntry {
+        user_id: String,
+        device_id: String,
+        subspace_id: SubspaceId,
+        revoked_at: Timestamp,
+        reason: String,
+        timestamp: Timestamp,
+    },
+    /// One tentatively-ordered operation in the replicated capability-grant
+    /// log (see `crate::capability_ledger::CapabilityLedger`). `device_id`
+    /// is the device that issued the grant/revoke, used as the tiebreak
+    /// when two ops land on the same `timestamp`.
+    CapabilityOp {
+        subject: String,
+        capability: Capability,
+        action: CapabilityAction,
+        subspace_id: SubspaceId,
+        device_id: String,
+        timestamp: Timestamp,
+    },
+    /// A signed fold of the capability log up to `checkpoint.watermark`,
+    /// published so replicas can discard superseded `CapabilityOp` entries
+    /// instead of replaying the log from its start forever.
+    CapabilityCheckpoint {
+        subspace_id: SubspaceId,
+        checkpoint: Checkpoint,
+        signed_by: String,
+        signature: Vec<u8>,
+        timestamp: Timestamp,
+    },
+}
+
+impl GardenEntry {
+    /// Every variant carries a `timestamp` field; this is the common
+    /// accessor for code (e.g. history backfill) that orders entries
+    /// without needing to match on the specific variant.
+    pub fn timestamp(&self) -> Timestamp {
+        match self {
+            GardenEntry::DirectMessage { timestamp, .. }
+            | GardenEntry::GroupMessage { timestamp, .. }
+            | GardenEntry::FriendRequest { timestamp, .. }
+            | GardenEntry::BlockedUser { timestamp, .. }
+            | GardenEntry::MutedUser { timestamp, .. }
+            | GardenEntry::Profile { timestamp, .. }
+            | GardenEntry::SlashCommand { timestamp, .. }
+            | GardenEntry::DeviceKey { timestamp, .. }
+            | GardenEntry::GroupMeta { timestamp, .. }
+            | GardenEntry::GroupMember { timestamp, .. }
+            | GardenEntry::DeviceList { timestamp, .. }
+            | GardenEntry::RevocationEntry { timestamp, .. }
+            | GardenEntry::CapabilityOp { timestamp, .. }
+            | GardenEntry::CapabilityCheckpoint { timestamp, .. } => *timestamp,
+        }
+    }
 }
\ No newline at end of file
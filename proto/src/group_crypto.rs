@@ -0,0 +1,333 @@
+// garden-core/src/group_crypto.rs
+//
+// Threshold group-key agreement: a Feldman verifiable-secret-sharing (VSS)
+// based ECDKG over Ristretto25519, replacing the old `MockGroupSession`'s
+// single random "secret" with real distributed key generation. Every
+// participant deals its own random degree-`(threshold - 1)` polynomial; the
+// group's secret is the sum of every dealer's constant term, and no single
+// dealer ever learns that sum - recovering it (or decrypting a message
+// under the aggregate public key) needs `threshold` participants to combine
+// their shares via Lagrange interpolation. A production GJKR-style DKG
+// would also run a complaint/disqualification round against dealers whose
+// shares fail verification; that round isn't implemented here, but a
+// failing share is still rejected via `GroupCryptoError::ShareVerificationFailed`.
+use std::collections::HashSet;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, Key, XNonce};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+const NONCE_LEN: usize = 24;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GroupCryptoError {
+    #[error("share failed Feldman VSS verification against the broadcast commitments")]
+    ShareVerificationFailed,
+
+    #[error("fewer than the threshold number of partial decryptions were supplied")]
+    BelowThreshold,
+
+    #[error("duplicate participant index among partial decryptions")]
+    DuplicateIndex,
+
+    #[error("decryption failed - wrong shares or a corrupted ciphertext")]
+    DecryptionFailed,
+
+    #[error("malformed point encoding")]
+    InvalidPoint,
+}
+
+type GcResult<T> = Result<T, GroupCryptoError>;
+
+fn index_to_scalar(index: u64) -> Scalar {
+    Scalar::from(index)
+}
+
+/// One dealer's private polynomial of degree `threshold - 1`, used once
+/// during DKG to derive every participant's share and then discarded.
+struct Polynomial {
+    coefficients: Vec<Scalar>,
+}
+
+impl Polynomial {
+    fn random(threshold: usize) -> Self {
+        let mut rng = OsRng;
+        let coefficients = (0..threshold).map(|_| Scalar::random(&mut rng)).collect();
+        Polynomial { coefficients }
+    }
+
+    fn evaluate(&self, x: Scalar) -> Scalar {
+        let mut result = Scalar::ZERO;
+        let mut power = Scalar::ONE;
+        for coeff in &self.coefficients {
+            result += coeff * power;
+            power *= x;
+        }
+        result
+    }
+
+    /// Public commitments `g^{a_0}, g^{a_1}, ...` to each coefficient,
+    /// broadcast so every other participant can verify the share it receives.
+    fn commitments(&self) -> Vec<RistrettoPoint> {
+        self.coefficients.iter().map(|c| RISTRETTO_BASEPOINT_POINT * c).collect()
+    }
+}
+
+/// Feldman VSS check: does `share` (a participant's evaluation of a dealt
+/// polynomial at `index`) match the dealer's broadcast `commitments`? Holds
+/// because `g^{p(x)} == sum_k commitments[k] * x^k` for the polynomial `p`
+/// the commitments were derived from.
+pub fn verify_share(share: &Scalar, index: u64, commitments: &[RistrettoPoint]) -> bool {
+    let x = index_to_scalar(index);
+    let mut expected = RistrettoPoint::identity();
+    let mut power = Scalar::ONE;
+    for commitment in commitments {
+        expected += commitment * power;
+        power *= x;
+    }
+    RISTRETTO_BASEPOINT_POINT * share == expected
+}
+
+/// One participant's output from a completed DKG round.
+#[derive(Debug, Clone, Copy)]
+pub struct ParticipantKeyShare {
+    pub index: u64,
+    pub secret_share: Scalar,
+    /// The aggregate commitments (summed across every dealer, per
+    /// coefficient degree) - lets any third party verify any participant's
+    /// total share with the same Feldman check, without needing each
+    /// dealer's individual commitments.
+    pub commitments: Vec<RistrettoPoint>,
+    pub group_public_key: RistrettoPoint,
+    pub threshold: usize,
+}
+
+/// Run a joint Feldman-VSS DKG among `participant_indices`, each dealing its
+/// own random degree-`(threshold - 1)` polynomial. Returns one
+/// `ParticipantKeyShare` per participant; the group's secret (the sum of
+/// every dealer's constant term) is never materialized anywhere.
+pub fn run_dkg(participant_indices: &[u64], threshold: usize) -> Vec<ParticipantKeyShare> {
+    let polynomials: Vec<Polynomial> = participant_indices.iter().map(|_| Polynomial::random(threshold)).collect();
+    let per_dealer_commitments: Vec<Vec<RistrettoPoint>> = polynomials.iter().map(Polynomial::commitments).collect();
+
+    let mut agg_commitments = vec![RistrettoPoint::identity(); threshold];
+    for commitments in &per_dealer_commitments {
+        for (agg, c) in agg_commitments.iter_mut().zip(commitments) {
+            *agg += c;
+        }
+    }
+    let group_public_key = agg_commitments[0];
+
+    participant_indices.iter().map(|&index| {
+        let x = index_to_scalar(index);
+        let secret_share: Scalar = polynomials.iter().zip(&per_dealer_commitments)
+            .map(|(polynomial, commitments)| {
+                let share = polynomial.evaluate(x);
+                debug_assert!(verify_share(&share, index, commitments), "a dealer's own share must verify against its own commitments");
+                share
+            })
+            .sum();
+
+        ParticipantKeyShare {
+            index,
+            secret_share,
+            commitments: agg_commitments.clone(),
+            group_public_key,
+            threshold,
+        }
+    }).collect()
+}
+
+/// An ElGamal/ECIES ciphertext against a DKG group's aggregate public key.
+/// No single `secret_share` can open this alone (unless `threshold == 1`) -
+/// opening it needs `threshold` participants' partial decryptions combined
+/// via `combine_partial_decryptions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupCiphertext {
+    ephemeral_point: [u8; 32],
+    nonce: [u8; NONCE_LEN],
+    payload: Vec<u8>,
+}
+
+fn derive_symmetric_key(shared_point: &RistrettoPoint) -> [u8; 32] {
+    let hk = Hkdf::<Sha256>::new(None, shared_point.compress().as_bytes());
+    let mut key = [0u8; 32];
+    hk.expand(b"garden-group-ecies", &mut key).expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// Encrypt `plaintext` to `group_public_key`. The resulting ciphertext can
+/// only be opened by combining `threshold` participants' partial decryptions.
+pub fn encrypt(group_public_key: &RistrettoPoint, plaintext: &[u8]) -> GroupCiphertext {
+    let mut rng = OsRng;
+    let ephemeral_scalar = Scalar::random(&mut rng);
+    let ephemeral_point = RISTRETTO_BASEPOINT_POINT * ephemeral_scalar;
+    let shared_point = group_public_key * ephemeral_scalar;
+    let key = derive_symmetric_key(&shared_point);
+
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let payload = cipher.encrypt(XNonce::from_slice(&nonce), plaintext)
+        .expect("XChaCha20-Poly1305 encryption does not fail");
+
+    GroupCiphertext {
+        ephemeral_point: ephemeral_point.compress().to_bytes(),
+        nonce,
+        payload,
+    }
+}
+
+/// One participant's contribution toward opening `ciphertext`: their secret
+/// share applied to the ciphertext's ephemeral point. Reveals nothing about
+/// `secret_share` on its own.
+pub fn partial_decrypt(ciphertext: &GroupCiphertext, secret_share: &Scalar) -> GcResult<RistrettoPoint> {
+    let ephemeral_point = CompressedRistretto(ciphertext.ephemeral_point)
+        .decompress()
+        .ok_or(GroupCryptoError::InvalidPoint)?;
+    Ok(ephemeral_point * secret_share)
+}
+
+fn lagrange_coefficient_at_zero(index: u64, participants: &[(u64, RistrettoPoint)]) -> Scalar {
+    let xi = index_to_scalar(index);
+    let mut coefficient = Scalar::ONE;
+    for &(other_index, _) in participants {
+        if other_index == index {
+            continue;
+        }
+        let xj = index_to_scalar(other_index);
+        coefficient *= xj * (xj - xi).invert();
+    }
+    coefficient
+}
+
+/// Combine at least `threshold` participants' partial decryptions (each
+/// tagged with the participant index it came from) to recover the shared
+/// ECDH point via Lagrange interpolation, then decrypt `ciphertext`'s payload.
+pub fn combine_partial_decryptions(
+    ciphertext: &GroupCiphertext,
+    partials: &[(u64, RistrettoPoint)],
+    threshold: usize,
+) -> GcResult<Vec<u8>> {
+    if partials.len() < threshold {
+        return Err(GroupCryptoError::BelowThreshold);
+    }
+
+    let mut seen = HashSet::new();
+    for &(index, _) in partials {
+        if !seen.insert(index) {
+            return Err(GroupCryptoError::DuplicateIndex);
+        }
+    }
+
+    let used = &partials[..threshold];
+    let shared_point: RistrettoPoint = used.iter()
+        .map(|&(index, point)| point * lagrange_coefficient_at_zero(index, used))
+        .sum();
+
+    let key = derive_symmetric_key(&shared_point);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let nonce = XNonce::from_slice(&ciphertext.nonce);
+    cipher.decrypt(nonce, ciphertext.payload.as_ref())
+        .map_err(|_| GroupCryptoError::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_dealt_share_verifies_against_its_dealers_commitments() {
+        let polynomial = Polynomial::random(3);
+        let commitments = polynomial.commitments();
+        let share = polynomial.evaluate(index_to_scalar(5));
+        assert!(verify_share(&share, 5, &commitments));
+    }
+
+    #[test]
+    fn a_tampered_share_fails_verification() {
+        let polynomial = Polynomial::random(3);
+        let commitments = polynomial.commitments();
+        let share = polynomial.evaluate(index_to_scalar(5)) + Scalar::ONE;
+        assert!(!verify_share(&share, 5, &commitments));
+    }
+
+    #[test]
+    fn every_participant_shares_the_same_aggregate_public_key() {
+        let shares = run_dkg(&[1, 2, 3], 2);
+        let pubkey = shares[0].group_public_key;
+        assert!(shares.iter().all(|s| s.group_public_key == pubkey));
+    }
+
+    #[test]
+    fn each_participants_share_verifies_against_the_aggregate_commitments() {
+        let shares = run_dkg(&[1, 2, 3], 2);
+        for share in &shares {
+            assert!(verify_share(&share.secret_share, share.index, &share.commitments));
+        }
+    }
+
+    #[test]
+    fn threshold_of_n_partial_decryptions_recover_the_plaintext() {
+        let shares = run_dkg(&[1, 2, 3], 2);
+        let ciphertext = encrypt(&shares[0].group_public_key, b"meet at the garden gate");
+
+        let partials: Vec<(u64, RistrettoPoint)> = shares.iter().take(2)
+            .map(|s| (s.index, partial_decrypt(&ciphertext, &s.secret_share).unwrap()))
+            .collect();
+
+        let plaintext = combine_partial_decryptions(&ciphertext, &partials, 2).unwrap();
+        assert_eq!(plaintext, b"meet at the garden gate");
+    }
+
+    #[test]
+    fn a_different_pair_of_shares_recovers_the_same_plaintext() {
+        let shares = run_dkg(&[1, 2, 3], 2);
+        let ciphertext = encrypt(&shares[0].group_public_key, b"lagrange interpolation works");
+
+        let partials: Vec<(u64, RistrettoPoint)> = [&shares[0], &shares[2]].iter()
+            .map(|s| (s.index, partial_decrypt(&ciphertext, &s.secret_share).unwrap()))
+            .collect();
+
+        let plaintext = combine_partial_decryptions(&ciphertext, &partials, 2).unwrap();
+        assert_eq!(plaintext, b"lagrange interpolation works");
+    }
+
+    #[test]
+    fn fewer_than_threshold_partial_decryptions_are_rejected() {
+        let shares = run_dkg(&[1, 2, 3], 2);
+        let ciphertext = encrypt(&shares[0].group_public_key, b"not enough shares");
+
+        let partials = vec![(shares[0].index, partial_decrypt(&ciphertext, &shares[0].secret_share).unwrap())];
+        assert_eq!(combine_partial_decryptions(&ciphertext, &partials, 2), Err(GroupCryptoError::BelowThreshold));
+    }
+
+    #[test]
+    fn duplicate_participant_indices_are_rejected() {
+        let shares = run_dkg(&[1, 2, 3], 2);
+        let ciphertext = encrypt(&shares[0].group_public_key, b"duplicate check");
+        let partial = partial_decrypt(&ciphertext, &shares[0].secret_share).unwrap();
+
+        let partials = vec![(shares[0].index, partial), (shares[0].index, partial)];
+        assert_eq!(combine_partial_decryptions(&ciphertext, &partials, 2), Err(GroupCryptoError::DuplicateIndex));
+    }
+
+    #[test]
+    fn a_single_party_threshold_one_group_decrypts_alone() {
+        let shares = run_dkg(&[1], 1);
+        let ciphertext = encrypt(&shares[0].group_public_key, b"solo decrypt");
+        let partial = partial_decrypt(&ciphertext, &shares[0].secret_share).unwrap();
+
+        let plaintext = combine_partial_decryptions(&ciphertext, &[(shares[0].index, partial)], 1).unwrap();
+        assert_eq!(plaintext, b"solo decrypt");
+    }
+}
@@ -0,0 +1,299 @@
+// garden-core/src/handshake.rs
+//
+// Session negotiation layered on top of p2panda-net's own transport (which
+// already handles real wire-level connection establishment - see
+// `GardenClient::connect_to_peer`'s "kept for API compatibility" comment).
+// This module is the application-level layer above that: two clients
+// exchange a `Hello` advertising protocol versions, compression codecs, and
+// AEAD ciphersuites, converge on a mutually-supported set (see `negotiate`), and
+// derive a shared `session_key` via X25519 ECDH + HKDF (mirroring
+// `identity::verify`'s SAS derivation). A `ResumeToken` signed over the
+// resulting `session_id` lets a client that was briefly disconnected
+// re-establish the same session without repeating negotiation - see
+// `GardenClient::resume_session`/`reconnect_with_backoff`.
+//
+// Only `CompressionCodec::None` and `Ciphersuite::XChaCha20Poly1305Sha256`
+// are implemented today (matching the one AEAD already used elsewhere in
+// this crate - see `crate::group_crypto`); both enums are
+// written to grow without changing the negotiation logic. Actually
+// compressing/encrypting each subsequent gossip frame through that cipher
+// is left as a follow-up to avoid duplicating p2panda-net's own transport
+// security - this module's job is letting both sides already agree on one.
+use std::time::Duration;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey};
+
+pub type ProtocolVersion = u32;
+
+/// Protocol versions this build can speak, newest first is not required -
+/// `negotiate` picks the highest common one regardless of order.
+pub const SUPPORTED_VERSIONS: &[ProtocolVersion] = &[1];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompressionCodec {
+    /// No compression - the only codec actually implemented today.
+    None,
+}
+
+/// Compression codecs this build supports, in preference order (most to
+/// least preferred when multiple are mutually supported).
+pub const SUPPORTED_COMPRESSION: &[CompressionCodec] = &[CompressionCodec::None];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ciphersuite {
+    /// XChaCha20-Poly1305 with an HKDF-SHA256 session key - the same AEAD
+    /// `crate::group_crypto` already uses.
+    XChaCha20Poly1305Sha256,
+}
+
+/// Ciphersuites this build supports, in preference order.
+pub const SUPPORTED_CIPHERS: &[Ciphersuite] = &[Ciphersuite::XChaCha20Poly1305Sha256];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HandshakeError {
+    #[error("no protocol version is supported by both sides")]
+    NoCommonVersion,
+    #[error("no compression codec is supported by both sides")]
+    NoCommonCompression,
+    #[error("no ciphersuite is supported by both sides")]
+    NoCommonCipher,
+    #[error("resume token signature does not verify")]
+    InvalidResumeToken,
+    #[error("resume token references a different session than the one on record")]
+    SessionMismatch,
+}
+
+/// What one side of a handshake sends the other to kick off (or respond
+/// to) negotiation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hello {
+    pub supported_versions: Vec<ProtocolVersion>,
+    pub supported_compression: Vec<CompressionCodec>,
+    pub supported_ciphers: Vec<Ciphersuite>,
+    pub ephemeral_public: [u8; 32],
+}
+
+/// The outcome of a completed handshake: the negotiated parameters plus the
+/// session key both sides derived for this `session_id`.
+#[derive(Debug, Clone)]
+pub struct NegotiatedSession {
+    pub session_id: String,
+    pub version: ProtocolVersion,
+    pub compression: CompressionCodec,
+    pub cipher: Ciphersuite,
+    pub session_key: [u8; 32],
+}
+
+/// The highest protocol version present in both `local` and `remote`, plus
+/// the first codec and the first cipher in `local`'s preference order that
+/// `remote` also lists. Both sides land on the same answer as long as they
+/// list their shared codecs and ciphers in the same order, as every build
+/// advertising `SUPPORTED_COMPRESSION`/`SUPPORTED_CIPHERS` does.
+fn negotiate(local: &Hello, remote: &Hello) -> Result<(ProtocolVersion, CompressionCodec, Ciphersuite), HandshakeError> {
+    let version = local.supported_versions.iter()
+        .filter(|v| remote.supported_versions.contains(v))
+        .max()
+        .copied()
+        .ok_or(HandshakeError::NoCommonVersion)?;
+
+    let compression = local.supported_compression.iter()
+        .find(|c| remote.supported_compression.contains(c))
+        .copied()
+        .ok_or(HandshakeError::NoCommonCompression)?;
+
+    let cipher = local.supported_ciphers.iter()
+        .find(|c| remote.supported_ciphers.contains(c))
+        .copied()
+        .ok_or(HandshakeError::NoCommonCipher)?;
+
+    Ok((version, compression, cipher))
+}
+
+fn hkdf_expand(ikm: &[u8], info: &[u8]) -> [u8; 32] {
+    let hk = Hkdf::<Sha256>::new(None, ikm);
+    let mut okm = [0u8; 32];
+    hk.expand(info, &mut okm).expect("HKDF output length is within RFC 5869 bounds");
+    okm
+}
+
+/// One in-progress handshake. Consumed by `complete` once the remote
+/// party's `Hello` has arrived.
+pub struct HandshakeSession {
+    local_hello: Hello,
+    ephemeral_secret: EphemeralSecret,
+}
+
+impl HandshakeSession {
+    /// Start a handshake, advertising every version/codec/cipher this build
+    /// supports. The returned `Hello` is what gets sent to the remote party.
+    pub fn begin() -> (Self, Hello) {
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let public = X25519PublicKey::from(&secret);
+
+        let hello = Hello {
+            supported_versions: SUPPORTED_VERSIONS.to_vec(),
+            supported_compression: SUPPORTED_COMPRESSION.to_vec(),
+            supported_ciphers: SUPPORTED_CIPHERS.to_vec(),
+            ephemeral_public: public.to_bytes(),
+        };
+
+        (Self { local_hello: hello.clone(), ephemeral_secret: secret }, hello)
+    }
+
+    /// Negotiate against `remote_hello` and derive the session key. Both
+    /// sides of a handshake call this with the other's `Hello` and land on
+    /// an identical `NegotiatedSession` (same `session_id`, same
+    /// `session_key`), independent of who called `begin` first.
+    pub fn complete(self, remote_hello: &Hello) -> Result<NegotiatedSession, HandshakeError> {
+        let (version, compression, cipher) = negotiate(&self.local_hello, remote_hello)?;
+
+        let remote_public = X25519PublicKey::from(remote_hello.ephemeral_public);
+        let shared_secret = self.ephemeral_secret.diffie_hellman(&remote_public);
+
+        let (first, second) = sorted_pair(&self.local_hello.ephemeral_public, &remote_hello.ephemeral_public);
+        let mut info = Vec::with_capacity(32 + first.len() + second.len() + 3);
+        info.extend_from_slice(b"GARDEN_HANDSHAKE_v1");
+        info.extend_from_slice(first);
+        info.extend_from_slice(second);
+        info.push(version as u8);
+        info.push(compression as u8);
+        info.push(cipher as u8);
+
+        let session_key = hkdf_expand(shared_secret.as_bytes(), &info);
+        let session_id = hex::encode(Sha256::digest(session_key));
+
+        Ok(NegotiatedSession { session_id, version, compression, cipher, session_key })
+    }
+}
+
+fn sorted_pair<'a>(a: &'a [u8; 32], b: &'a [u8; 32]) -> (&'a [u8], &'a [u8]) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+/// Proof that the holder of `signing_key` previously completed the
+/// handshake that produced `session_id`, so a peer can reinstate that
+/// session on reconnect without redoing negotiation. Signed rather than
+/// just asserted, since a resume request arrives over the same untrusted
+/// channel a fresh handshake would.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumeToken {
+    pub session_id: String,
+    pub user_id: String,
+    pub signature: Vec<u8>,
+}
+
+impl ResumeToken {
+    fn signing_bytes(session_id: &str, user_id: &str) -> Vec<u8> {
+        bincode::serialize(&(session_id, user_id))
+            .expect("resume token signing tuple always serializes")
+    }
+
+    pub fn create(session_id: &str, user_id: &str, signing_key: &SigningKey) -> Self {
+        let bytes = Self::signing_bytes(session_id, user_id);
+        let signature = signing_key.sign(&bytes).to_bytes().to_vec();
+        Self { session_id: session_id.to_string(), user_id: user_id.to_string(), signature }
+    }
+
+    pub fn verify(&self, verifying_key: &VerifyingKey) -> bool {
+        let Ok(signature_bytes): Result<[u8; 64], _> = self.signature.as_slice().try_into() else {
+            return false;
+        };
+        let signature = Signature::from_bytes(&signature_bytes);
+        let bytes = Self::signing_bytes(&self.session_id, &self.user_id);
+        verifying_key.verify(&bytes, &signature).is_ok()
+    }
+}
+
+/// Exponential backoff with a cap, for retrying a dropped connection
+/// instead of hammering a peer that's still unreachable. Each `next_delay`
+/// call both returns and advances the schedule.
+pub struct ReconnectBackoff {
+    base: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl ReconnectBackoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self { base, max, attempt: 0 }
+    }
+
+    /// The delay to wait before the next reconnect attempt: `base * 2^n`,
+    /// capped at `max`.
+    pub fn next_delay(&mut self) -> Duration {
+        let scaled = self.base.saturating_mul(1u32 << self.attempt.min(31));
+        self.attempt += 1;
+        scaled.min(self.max)
+    }
+
+    /// Number of delays handed out so far.
+    pub fn attempts(&self) -> u32 {
+        self.attempt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_sides_of_a_handshake_derive_the_same_session() {
+        let (alice, alice_hello) = HandshakeSession::begin();
+        let (bob, bob_hello) = HandshakeSession::begin();
+
+        let alice_session = alice.complete(&bob_hello).unwrap();
+        let bob_session = bob.complete(&alice_hello).unwrap();
+
+        assert_eq!(alice_session.session_id, bob_session.session_id);
+        assert_eq!(alice_session.session_key, bob_session.session_key);
+        assert_eq!(alice_session.version, 1);
+        assert_eq!(alice_session.compression, CompressionCodec::None);
+        assert_eq!(alice_session.cipher, Ciphersuite::XChaCha20Poly1305Sha256);
+    }
+
+    #[test]
+    fn negotiation_fails_closed_when_there_is_no_common_version() {
+        let (alice, _) = HandshakeSession::begin();
+        let mismatched_hello = Hello {
+            supported_versions: vec![99],
+            supported_compression: SUPPORTED_COMPRESSION.to_vec(),
+            supported_ciphers: SUPPORTED_CIPHERS.to_vec(),
+            ephemeral_public: [7u8; 32],
+        };
+
+        assert_eq!(alice.complete(&mismatched_hello), Err(HandshakeError::NoCommonVersion));
+    }
+
+    #[test]
+    fn a_resume_token_verifies_only_for_its_signer_and_only_with_the_session_id_it_was_signed_over() {
+        let signing_key = SigningKey::from_bytes(&[1u8; 32]);
+        let token = ResumeToken::create("session-1", "user-a", &signing_key);
+
+        assert!(token.verify(&signing_key.verifying_key()));
+
+        let other_key = SigningKey::from_bytes(&[2u8; 32]);
+        assert!(!token.verify(&other_key.verifying_key()));
+
+        let mut tampered = token.clone();
+        tampered.session_id = "session-2".to_string();
+        assert!(!tampered.verify(&signing_key.verifying_key()));
+    }
+
+    #[test]
+    fn backoff_doubles_each_attempt_until_it_hits_the_cap() {
+        let mut backoff = ReconnectBackoff::new(Duration::from_millis(100), Duration::from_millis(1000));
+
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(200));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(400));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(800));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(1000));
+        assert_eq!(backoff.attempts(), 5);
+    }
+}
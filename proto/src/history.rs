@@ -0,0 +1,154 @@
+// garden-core/src/history.rs
+//
+// CHATHISTORY-style backfill: `GardenClient::query_history` lets a client
+// that subscribed late, or was offline for a while, catch up on entries for
+// a `Topic` instead of only ever seeing what arrives after it joined - see
+// `test_group_chat_scenario`'s member1/member2, who otherwise have no way to
+// load prior context after `join_encrypted_group`.
+//
+// `HistoryLog` only holds what this client has itself already observed
+// (recorded wherever entries land, e.g. `GardenClient::dispatch_entry`).
+// Backfilling a gap this client never saw - because it was offline, or
+// joined after the fact - means asking connected peers for that range; this
+// mock network doesn't have a real peer-to-peer sync transport yet, so
+// `query_history` only ever serves from the local log for now.
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use thiserror::Error;
+
+use crate::entries::GardenEntry;
+use crate::p2p::Topic;
+use crate::types::Timestamp;
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("history log lock was poisoned")]
+    Poisoned,
+}
+
+/// Which slice of a topic's history to fetch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistorySelector {
+    Before(Timestamp),
+    After(Timestamp),
+    Between(Timestamp, Timestamp),
+    Latest,
+}
+
+/// Outcome of a history query. Kept distinct from a bare `Vec<GardenEntry>`
+/// so "nothing happened in that range" (`Empty`) and "you don't have
+/// `ReadMessages` on this topic" (`Unauthorized`) aren't both just an empty
+/// list to the caller.
+#[derive(Debug, Clone)]
+pub enum HistoryResult {
+    Messages(Vec<GardenEntry>),
+    Empty,
+    Unauthorized,
+}
+
+/// Per-topic log of entries this client has observed, oldest first.
+#[derive(Default)]
+pub struct HistoryLog {
+    topics: RwLock<HashMap<Topic, Vec<GardenEntry>>>,
+}
+
+impl HistoryLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an entry as having been seen on `topic`.
+    pub fn record(&self, topic: &Topic, entry: GardenEntry) -> Result<(), HistoryError> {
+        let mut topics = self.topics.write().map_err(|_| HistoryError::Poisoned)?;
+        topics.entry(topic.clone()).or_default().push(entry);
+        Ok(())
+    }
+
+    /// Select entries out of `topic`'s locally-known log matching
+    /// `selector`, oldest first, capped to the most recent `limit` matches.
+    pub fn query(
+        &self,
+        topic: &Topic,
+        selector: &HistorySelector,
+        limit: usize,
+    ) -> Result<Vec<GardenEntry>, HistoryError> {
+        let topics = self.topics.read().map_err(|_| HistoryError::Poisoned)?;
+        let Some(log) = topics.get(topic) else {
+            return Ok(Vec::new());
+        };
+
+        let mut matched: Vec<GardenEntry> = log
+            .iter()
+            .filter(|entry| match selector {
+                HistorySelector::Before(ts) => entry.timestamp() < *ts,
+                HistorySelector::After(ts) => entry.timestamp() > *ts,
+                HistorySelector::Between(from, to) => {
+                    entry.timestamp() >= *from && entry.timestamp() <= *to
+                }
+                HistorySelector::Latest => true,
+            })
+            .cloned()
+            .collect();
+
+        if matched.len() > limit {
+            matched = matched.split_off(matched.len() - limit);
+        }
+        Ok(matched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_at(timestamp: Timestamp) -> GardenEntry {
+        GardenEntry::GroupMessage {
+            group_id: "garden-1".to_string(),
+            sender_id: "user-1".to_string(),
+            subspace_id: crate::types::SubspaceId("sub-1".to_string()),
+            encrypted_content: vec![],
+            timestamp,
+            message_type: crate::types::MessageType::Text,
+            attachments: vec![],
+            epoch: 0,
+        }
+    }
+
+    #[test]
+    fn an_unknown_topic_returns_no_entries() {
+        let log = HistoryLog::new();
+        let result = log.query(&Topic::new("garden/none"), &HistorySelector::Latest, 10).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn before_and_after_selectors_split_on_the_boundary() {
+        let log = HistoryLog::new();
+        let topic = Topic::new("garden/test");
+        for ts in [10, 20, 30] {
+            log.record(&topic, entry_at(ts)).unwrap();
+        }
+
+        let before = log.query(&topic, &HistorySelector::Before(20), 10).unwrap();
+        assert_eq!(before.iter().map(|e| e.timestamp()).collect::<Vec<_>>(), vec![10]);
+
+        let after = log.query(&topic, &HistorySelector::After(20), 10).unwrap();
+        assert_eq!(after.iter().map(|e| e.timestamp()).collect::<Vec<_>>(), vec![30]);
+
+        let between = log.query(&topic, &HistorySelector::Between(10, 20), 10).unwrap();
+        assert_eq!(between.iter().map(|e| e.timestamp()).collect::<Vec<_>>(), vec![10, 20]);
+    }
+
+    #[test]
+    fn limit_keeps_only_the_most_recent_matches() {
+        let log = HistoryLog::new();
+        let topic = Topic::new("garden/test");
+        for ts in [10, 20, 30] {
+            log.record(&topic, entry_at(ts)).unwrap();
+        }
+
+        let latest = log.query(&topic, &HistorySelector::Latest, 2).unwrap();
+        assert_eq!(latest.iter().map(|e| e.timestamp()).collect::<Vec<_>>(), vec![20, 30]);
+    }
+}
@@ -0,0 +1,199 @@
+// garden-core/src/identity/device_list.rs
+//
+// The authenticated set of devices allowed to act on a user's identity,
+// published as a `GardenEntry::DeviceList`. Peers reject messages from
+// devices absent from (or revoked in) the current version. This mirrors the
+// Tauri backend's `SignedDeviceList` but operates on `garden_core::identity`
+// types so the core protocol crate doesn't depend on the application layer.
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use thiserror::Error;
+
+use crate::entries::GardenEntry;
+use crate::identity::{Device, Identity};
+use crate::types::Timestamp;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DeviceListError {
+    #[error("entry is not a DeviceList")]
+    WrongEntryType,
+    #[error("device list version {incoming} does not supersede current version {current}")]
+    StaleVersion { incoming: u64, current: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceList {
+    pub user_id: String,
+    /// Monotonically increasing so a replayed, older signed list is rejected
+    /// even though its signature is still valid.
+    pub version: u64,
+    pub devices: Vec<Device>,
+    pub signature: Vec<u8>,
+}
+
+impl DeviceList {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            version: 0,
+            devices: Vec::new(),
+            signature: Vec::new(),
+        }
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        bincode::serialize(&(&self.user_id, self.version, &self.devices))
+            .expect("device list signing tuple always serializes")
+    }
+
+    fn sign(&mut self, identity_key: &SigningKey) {
+        let bytes = self.signing_bytes();
+        self.signature = identity_key.sign(&bytes).to_bytes().to_vec();
+    }
+
+    /// Verify the list was signed by `identity`'s long-term identity key.
+    pub fn verify(&self, identity: &Identity) -> bool {
+        let Ok(public_key_bytes): Result<[u8; 32], _> = identity.public_key.clone().try_into() else {
+            return false;
+        };
+        let Ok(verifying_key) = VerifyingKey::from_bytes(&public_key_bytes) else {
+            return false;
+        };
+        let Ok(signature_bytes): Result<[u8; 64], _> = self.signature.as_slice().try_into() else {
+            return false;
+        };
+        let signature = Signature::from_bytes(&signature_bytes);
+        verifying_key.verify(&self.signing_bytes(), &signature).is_ok()
+    }
+
+    /// Add and/or remove devices in a single version bump, re-signing the
+    /// result. Bundling both in one call keeps a compromised-device
+    /// revocation and its replacement enrollment atomic from an observer's
+    /// point of view - there's no intermediate, partially-applied version.
+    pub fn rotate(&mut self, add: Vec<Device>, remove: Vec<String>, signing_key: &SigningKey) {
+        self.devices.retain(|d| !remove.contains(&d.device_id));
+        self.devices.extend(add);
+        self.version += 1;
+        self.sign(signing_key);
+    }
+
+    /// Whether `device_id` is currently allowed to act on this identity.
+    pub fn is_active_device(&self, device_id: &str) -> bool {
+        self.devices.iter().any(|d| d.device_id == device_id)
+    }
+
+    /// Reject a candidate version that isn't newer than what we already
+    /// have, so a stale (e.g. pre-revocation) signed list can't be replayed.
+    pub fn supersedes(&self, incoming_version: u64) -> bool {
+        incoming_version > self.version
+    }
+
+    pub fn to_entry(&self, timestamp: Timestamp) -> GardenEntry {
+        GardenEntry::DeviceList {
+            user_id: self.user_id.clone(),
+            version: self.version,
+            devices: self.devices.clone(),
+            signature: self.signature.clone(),
+            timestamp,
+        }
+    }
+
+    /// Rebuild a `DeviceList` from its replicated entry, rejecting it up
+    /// front if it doesn't supersede `current` - callers should check this
+    /// before doing anything else with an incoming list.
+    pub fn from_entry(entry: &GardenEntry, current: &DeviceList) -> Result<Self, DeviceListError> {
+        match entry {
+            GardenEntry::DeviceList {
+                user_id,
+                version,
+                devices,
+                signature,
+                ..
+            } => {
+                if current.user_id == *user_id && !current.supersedes(*version) {
+                    return Err(DeviceListError::StaleVersion {
+                        incoming: *version,
+                        current: current.version,
+                    });
+                }
+                Ok(Self {
+                    user_id: user_id.clone(),
+                    version: *version,
+                    devices: devices.clone(),
+                    signature: signature.clone(),
+                })
+            }
+            _ => Err(DeviceListError::WrongEntryType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::HybridLogicalClock;
+
+    fn identity_and_key() -> (Identity, SigningKey) {
+        Identity::generate_identity()
+    }
+
+    fn device(id: &str) -> Device {
+        Device {
+            device_id: id.to_string(),
+            public_key: vec![1, 2, 3],
+            signature: Vec::new(),
+            capabilities: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rotate_bumps_version_and_stays_verifiable() {
+        let (identity, signing_key) = identity_and_key();
+        let mut list = DeviceList::new(&identity.user_id);
+
+        list.rotate(vec![device("device-a")], vec![], &signing_key);
+        assert_eq!(list.version, 1);
+        assert!(list.verify(&identity));
+        assert!(list.is_active_device("device-a"));
+
+        list.rotate(vec![device("device-b")], vec!["device-a".to_string()], &signing_key);
+        assert_eq!(list.version, 2);
+        assert!(list.verify(&identity));
+        assert!(!list.is_active_device("device-a"));
+        assert!(list.is_active_device("device-b"));
+    }
+
+    #[test]
+    fn stale_version_is_rejected() {
+        let (identity, signing_key) = identity_and_key();
+        let mut list = DeviceList::new(&identity.user_id);
+        list.rotate(vec![device("device-a")], vec![], &signing_key);
+
+        assert!(!list.supersedes(list.version));
+        assert!(!list.supersedes(list.version - 1));
+        assert!(list.supersedes(list.version + 1));
+    }
+
+    #[test]
+    fn from_entry_rejects_a_rollback() {
+        let (identity, signing_key) = identity_and_key();
+        let mut current = DeviceList::new(&identity.user_id);
+        current.rotate(vec![device("device-a")], vec![], &signing_key);
+        current.rotate(vec![device("device-b")], vec![], &signing_key);
+
+        let mut stale = DeviceList::new(&identity.user_id);
+        stale.rotate(vec![device("device-a")], vec![], &signing_key);
+        let stale_entry = stale.to_entry(HybridLogicalClock::now());
+
+        assert!(DeviceList::from_entry(&stale_entry, &current).is_err());
+    }
+
+    #[test]
+    fn verify_fails_for_the_wrong_identity() {
+        let (identity, signing_key) = identity_and_key();
+        let (other_identity, _) = identity_and_key();
+        let mut list = DeviceList::new(&identity.user_id);
+        list.rotate(vec![device("device-a")], vec![], &signing_key);
+
+        assert!(!list.verify(&other_identity));
+    }
+}
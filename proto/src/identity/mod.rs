@@ -0,0 +1,86 @@
+// garden-core/src/identity/mod.rs
+pub mod device_list;
+pub mod verify;
+
+use serde::{Serialize, Deserialize};
+use crate::clock::HybridLogicalClock;
+use crate::types::{Timestamp};
+use ed25519_dalek::SigningKey;
+use rand::{rngs::OsRng, RngCore};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    pub user_id: String,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+    pub device_id: String,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub capabilities: Vec<Capability>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Capability {
+    ReadMessages(String),
+    WriteMessages(String),
+    ManageGroup(String),
+    ManageDevice(String),
+    CreateInvites,
+    AdminAccess,
+    /// Marks `path_prefix` as requiring a fresh MFA step-up before a token
+    /// carrying this capability may act on it, regardless of what other
+    /// capabilities the token holds.
+    MfaRequired(String),
+}
+
+impl Capability {
+    /// Whether holding `self` justifies also holding `child` - i.e. `child`
+    /// is the same or a narrower grant. Used to check that a delegated
+    /// capability is a genuine attenuation of the one it was carved out of,
+    /// rather than a delegate fabricating broader authority than it was
+    /// handed. `"*"` matches any path; otherwise the child's path must equal
+    /// or fall under the parent's path (`"groups/g"` falls under
+    /// `"groups"`, not the other way around).
+    pub fn implies(&self, child: &Capability) -> bool {
+        fn path_implies(parent: &str, child: &str) -> bool {
+            parent == "*" || child == parent || child.starts_with(&format!("{}/", parent))
+        }
+
+        match (self, child) {
+            (Capability::ReadMessages(p), Capability::ReadMessages(c)) => path_implies(p, c),
+            (Capability::WriteMessages(p), Capability::WriteMessages(c)) => path_implies(p, c),
+            (Capability::ManageGroup(p), Capability::ManageGroup(c)) => path_implies(p, c),
+            (Capability::ManageDevice(p), Capability::ManageDevice(c)) => path_implies(p, c),
+            (Capability::MfaRequired(p), Capability::MfaRequired(c)) => path_implies(p, c),
+            (Capability::CreateInvites, Capability::CreateInvites) => true,
+            (Capability::AdminAccess, Capability::AdminAccess) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Identity {
+    // Generate a new Identity with a key pair
+    pub fn generate_identity() -> (Self, SigningKey) {
+        // Generate random bytes for the key
+        let mut secret_key_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut secret_key_bytes);
+        
+        // Create SigningKey from random bytes
+        let signing_key = SigningKey::from_bytes(&secret_key_bytes);
+        let verifying_key = signing_key.verifying_key();
+        
+        let identity = Identity {
+            user_id: uuid::Uuid::new_v4().to_string(),
+            public_key: verifying_key.to_bytes().to_vec(),
+            signature: Vec::new(), // We'll sign the identity later if necessary
+            created_at: HybridLogicalClock::now(),
+        };
+        (identity, signing_key)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,296 @@
+// garden-core/src/identity/verify.rs
+//
+// Matrix-style short authentication string (SAS) verification. Two parties
+// exchange ephemeral X25519 keys over the existing P2P channel, derive a
+// shared secret, and render it as an emoji/decimal string that can be
+// compared out of band (in person, over a voice call, ...). A match, backed
+// by an HMAC over the long-term keys being attested, is the only way a
+// `DeviceKey` or a friend's `Identity` public key becomes trusted - closing
+// the MITM window where a malicious relay substitutes keys during first contact.
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey};
+
+use crate::clock::HybridLogicalClock;
+use crate::types::Timestamp;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Fixed 64-entry emoji table; a SAS chunk is a 6-bit index into this table.
+pub const EMOJI_TABLE: [&str; 64] = [
+    "🐶", "🐱", "🦁", "🐴", "🦄", "🐷", "🐘", "🐰", "🐼", "🐓", "🐧", "🐢", "🐟", "🐙", "🦋", "🐌",
+    "🐞", "🐜", "🕷️", "🦂", "🌸", "🌳", "🌵", "🍄", "🌍", "🌙", "☀️", "☁️", "🔥", "🍌", "🍎", "🍇",
+    "🍓", "🌽", "🍕", "🎂", "❤️", "😀", "🎩", "👓", "🔔", "🎈", "🎉", "🎸", "🔑", "📷", "📞", "⏰",
+    "🎁", "💡", "📕", "✏️", "📎", "✂️", "🔒", "🔧", "🔨", "⚓", "🚀", "🚲", "🚗", "✈️", "🏠", "⭐",
+];
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    #[error("remote commitment does not match the revealed ephemeral key")]
+    CommitmentMismatch,
+    #[error("MAC over the attested keys did not verify")]
+    MacMismatch,
+    #[error("verification session is in the wrong state for this operation")]
+    InvalidState,
+}
+
+/// Human-comparable rendering of the derived shared secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sas {
+    pub emoji: [&'static str; 7],
+    pub decimal: (u16, u16, u16),
+}
+
+/// Record of a successfully verified long-term key, produced once both sides'
+/// MACs check out. Downstream code (friend acceptance, `DeviceKey` trust in
+/// `ProfileField::DeviceList`) should gate on the presence of one of these.
+#[derive(Debug, Clone)]
+pub struct TrustRecord {
+    pub subject_public_key: Vec<u8>,
+    pub transaction_id: String,
+    pub verified_at: Timestamp,
+}
+
+enum State {
+    AwaitingRemoteKey,
+    KeysExchanged { shared_secret: Vec<u8> },
+    Confirmed,
+}
+
+/// One in-progress SAS verification between the local identity and a remote
+/// long-term public key (a friend's `Identity` or a `DeviceKey`).
+pub struct VerificationSession {
+    pub transaction_id: String,
+    local_long_term_key: Vec<u8>,
+    remote_long_term_key: Vec<u8>,
+    ephemeral_secret: Option<EphemeralSecret>,
+    local_ephemeral_public: X25519PublicKey,
+    commitment: [u8; 32],
+    state: State,
+}
+
+impl VerificationSession {
+    /// Start a verification session, generating the ephemeral keypair, a
+    /// random transaction id, and a commitment to our ephemeral public key.
+    /// The returned tuple (ephemeral public key, transaction id, commitment)
+    /// is what gets sent to the remote party over the P2P channel.
+    pub fn begin_verification(
+        local_long_term_key: Vec<u8>,
+        remote_long_term_key: Vec<u8>,
+    ) -> (Self, X25519PublicKey, String, [u8; 32]) {
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let public = X25519PublicKey::from(&secret);
+
+        let mut txn_bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut txn_bytes);
+        let transaction_id = hex::encode(txn_bytes);
+        let commitment = Self::commit(&public, &transaction_id);
+
+        let session = Self {
+            transaction_id: transaction_id.clone(),
+            local_long_term_key,
+            remote_long_term_key,
+            ephemeral_secret: Some(secret),
+            local_ephemeral_public: public,
+            commitment,
+            state: State::AwaitingRemoteKey,
+        };
+
+        (session, public, transaction_id, commitment)
+    }
+
+    fn commit(ephemeral_public: &X25519PublicKey, transaction_id: &str) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(ephemeral_public.as_bytes());
+        hasher.update(transaction_id.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Accept the remote party's ephemeral key and commitment, run the ECDH +
+    /// HKDF, and return the SAS for the user to compare out of band.
+    pub fn accept_remote_key(
+        &mut self,
+        remote_ephemeral_public: X25519PublicKey,
+        remote_commitment: [u8; 32],
+    ) -> Result<Sas, VerificationError> {
+        if Self::commit(&remote_ephemeral_public, &self.transaction_id) != remote_commitment {
+            return Err(VerificationError::CommitmentMismatch);
+        }
+
+        let secret = self.ephemeral_secret.take().ok_or(VerificationError::InvalidState)?;
+        let shared_secret = secret.diffie_hellman(&remote_ephemeral_public);
+
+        let info = Self::info_string(
+            &self.local_long_term_key,
+            &self.remote_long_term_key,
+            &self.local_ephemeral_public,
+            &remote_ephemeral_public,
+            &self.transaction_id,
+        );
+        let okm = Self::expand(shared_secret.as_bytes(), &info, 64);
+
+        self.state = State::KeysExchanged {
+            shared_secret: shared_secret.as_bytes().to_vec(),
+        };
+
+        Ok(Self::derive_sas(&okm))
+    }
+
+    fn info_string(
+        local_lt: &[u8],
+        remote_lt: &[u8],
+        local_eph: &X25519PublicKey,
+        remote_eph: &X25519PublicKey,
+        txn: &str,
+    ) -> Vec<u8> {
+        // Both sides must feed HKDF identical bytes, so the two
+        // (long-term key, ephemeral key) pairs go in a fixed order rather
+        // than local-first.
+        let local = (local_lt, local_eph.as_bytes().as_slice());
+        let remote = (remote_lt, remote_eph.as_bytes().as_slice());
+        let (first, second) = if local <= remote { (local, remote) } else { (remote, local) };
+
+        let mut info = Vec::with_capacity(32 + local_lt.len() + remote_lt.len());
+        info.extend_from_slice(b"GARDEN_SAS_v1");
+        info.extend_from_slice(first.0);
+        info.extend_from_slice(second.0);
+        info.extend_from_slice(first.1);
+        info.extend_from_slice(second.1);
+        info.extend_from_slice(txn.as_bytes());
+        info
+    }
+
+    fn expand(ikm: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+        let hk = Hkdf::<Sha256>::new(None, ikm);
+        let mut okm = vec![0u8; len];
+        hk.expand(info, &mut okm).expect("HKDF output length is within RFC 5869 bounds");
+        okm
+    }
+
+    /// Pack the first 81 bits of the HKDF output into the emoji table and
+    /// decimal rendering of the Matrix SAS spec.
+    fn derive_sas(okm: &[u8]) -> Sas {
+        let combined: u128 = okm[0..11].iter().fold(0u128, |acc, &b| (acc << 8) | b as u128);
+        const TOTAL_BITS: u32 = 11 * 8;
+
+        let read = |start: u32, len: u32| -> u128 {
+            let shift = TOTAL_BITS - start - len;
+            (combined >> shift) & ((1u128 << len) - 1)
+        };
+
+        let mut emoji = [""; 7];
+        for (i, slot) in emoji.iter_mut().enumerate() {
+            *slot = EMOJI_TABLE[read(i as u32 * 6, 6) as usize];
+        }
+
+        let decimal = (
+            read(42, 13) as u16 + 1000,
+            read(55, 13) as u16 + 1000,
+            read(68, 13) as u16 + 1000,
+        );
+
+        Sas { emoji, decimal }
+    }
+
+    /// Once the user has confirmed the SAS matches out of band, compute our
+    /// MAC over the long-term keys being attested. Send this to the remote
+    /// party; they compare it against their own computation via `verify_peer_mac`.
+    pub fn confirm_match(&mut self) -> Result<[u8; 32], VerificationError> {
+        let shared_secret = match &self.state {
+            State::KeysExchanged { shared_secret } => shared_secret.clone(),
+            _ => return Err(VerificationError::InvalidState),
+        };
+
+        let mac_key = Self::expand(&shared_secret, b"GARDEN_SAS_MAC_v1", 32);
+        let mut mac = HmacSha256::new_from_slice(&mac_key).expect("HMAC accepts any key length");
+        mac.update(&self.local_long_term_key);
+        mac.update(&self.remote_long_term_key);
+        mac.update(self.transaction_id.as_bytes());
+
+        Ok(mac.finalize().into_bytes().into())
+    }
+
+    /// Verify the MAC the remote party sent after confirming the SAS on
+    /// their end. Only on success does the remote long-term key become trusted.
+    pub fn verify_peer_mac(&mut self, peer_mac: &[u8; 32]) -> Result<TrustRecord, VerificationError> {
+        let shared_secret = match &self.state {
+            State::KeysExchanged { shared_secret } => shared_secret.clone(),
+            _ => return Err(VerificationError::InvalidState),
+        };
+
+        let mac_key = Self::expand(&shared_secret, b"GARDEN_SAS_MAC_v1", 32);
+        let mut mac = HmacSha256::new_from_slice(&mac_key).expect("HMAC accepts any key length");
+        // Peer computed over (their local key, their remote key) which from
+        // our perspective is (our remote key, our local key) - mirror that order.
+        mac.update(&self.remote_long_term_key);
+        mac.update(&self.local_long_term_key);
+        mac.update(self.transaction_id.as_bytes());
+
+        mac.verify_slice(peer_mac).map_err(|_| VerificationError::MacMismatch)?;
+
+        self.state = State::Confirmed;
+
+        Ok(TrustRecord {
+            subject_public_key: self.remote_long_term_key.clone(),
+            transaction_id: self.transaction_id.clone(),
+            verified_at: HybridLogicalClock::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_parties_derive_the_same_sas_and_trust_each_other() {
+        let alice_key = b"alice-long-term-key".to_vec();
+        let bob_key = b"bob-long-term-key".to_vec();
+
+        let (mut alice, alice_eph, txn, alice_commitment) =
+            VerificationSession::begin_verification(alice_key.clone(), bob_key.clone());
+        let (mut bob, bob_eph, bob_txn, _bob_commitment) =
+            VerificationSession::begin_verification(bob_key.clone(), alice_key.clone());
+        // Both sides agree on the same transaction id out of band (e.g. it's
+        // chosen by the initiator and echoed back); force them equal here.
+        // The commitment covers the transaction id, so bob's is recomputed.
+        bob.transaction_id = txn.clone();
+        let bob_commitment = VerificationSession::commit(&bob_eph, &txn);
+
+        let alice_sas = alice.accept_remote_key(bob_eph, bob_commitment).unwrap();
+        let bob_sas = bob.accept_remote_key(alice_eph, alice_commitment).unwrap();
+
+        assert_eq!(alice_sas, bob_sas);
+        let _ = bob_txn;
+
+        let alice_mac = alice.confirm_match().unwrap();
+        let bob_mac = bob.confirm_match().unwrap();
+
+        let alice_trust = alice.verify_peer_mac(&bob_mac).unwrap();
+        let bob_trust = bob.verify_peer_mac(&alice_mac).unwrap();
+
+        assert_eq!(alice_trust.subject_public_key, bob_key);
+        assert_eq!(bob_trust.subject_public_key, alice_key);
+    }
+
+    #[test]
+    fn tampered_commitment_is_rejected() {
+        let alice_key = b"alice".to_vec();
+        let bob_key = b"bob".to_vec();
+
+        let (mut alice, _alice_eph, _txn, _commitment) =
+            VerificationSession::begin_verification(alice_key, bob_key.clone());
+        let (_bob, bob_eph, _bob_txn, _bob_commitment) =
+            VerificationSession::begin_verification(bob_key, b"alice".to_vec());
+
+        let bogus_commitment = [0u8; 32];
+        assert_eq!(
+            alice.accept_remote_key(bob_eph, bogus_commitment),
+            Err(VerificationError::CommitmentMismatch)
+        );
+    }
+}
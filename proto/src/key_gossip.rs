@@ -0,0 +1,159 @@
+// garden-core/src/key_gossip.rs
+//
+// Autocrypt-style opportunistic key distribution: a `MessageHeader` carries
+// a sender's user_id, device public key, and encryption preference
+// alongside a message (see `GardenClient::send_message_with_header`), and
+// the recipient's `PeerKeyCache` learns the mapping as a side effect of
+// receiving it (`GardenClient::apply_message_header`). This is strictly a
+// convenience for getting *some* key on file without an out-of-band
+// exchange - it does not by itself establish trust, which is still
+// `crate::verification::TrustStore`'s job. A key that has been promoted via
+// `PeerKeyCache::mark_verified` can't be overwritten by a later gossiped
+// header for a different key.
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PeerKeyCacheError {
+    #[error("peer key cache lock was poisoned")]
+    Poisoned,
+}
+
+pub type PeerKeyCacheResult<T> = Result<T, PeerKeyCacheError>;
+
+/// Rides alongside a message so its recipient can learn (or refresh) the
+/// sender's device key without an out-of-band exchange.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageHeader {
+    pub user_id: String,
+    pub device_public_key: Vec<u8>,
+    /// Whether the sender would prefer this conversation move to an
+    /// encrypted group (see `crate::group_crypto`) rather than stay
+    /// plaintext.
+    pub prefers_encryption: bool,
+}
+
+/// `message` wrapped with the sender's `MessageHeader` for transport over
+/// `GardenClient::send_message_with_header`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GossipEnvelope {
+    pub header: MessageHeader,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Clone)]
+struct PeerKeyEntry {
+    public_key: Vec<u8>,
+    verified: bool,
+}
+
+/// Per-client cache of the last (or best) key seen from each `user_id`.
+#[derive(Default)]
+pub struct PeerKeyCache {
+    keys: RwLock<HashMap<String, PeerKeyEntry>>,
+}
+
+impl PeerKeyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `public_key` as the key last seen from `user_id`. A no-op if
+    /// `user_id`'s cached key has already been verified and `public_key`
+    /// differs from it - a gossiped header, forged or stale, never
+    /// downgrades an already-verified key.
+    pub fn observe(&self, user_id: &str, public_key: Vec<u8>) -> PeerKeyCacheResult<()> {
+        let mut keys = self.keys.write().map_err(|_| PeerKeyCacheError::Poisoned)?;
+        if let Some(existing) = keys.get(user_id) {
+            if existing.verified && existing.public_key != public_key {
+                return Ok(());
+            }
+        }
+        keys.insert(user_id.to_string(), PeerKeyEntry { public_key, verified: false });
+        Ok(())
+    }
+
+    /// Promote `user_id`'s cached key to verified, e.g. once it has passed
+    /// SAS verification or cross-signing. Overwrites whatever was cached
+    /// before, verified or not.
+    pub fn mark_verified(&self, user_id: &str, public_key: Vec<u8>) -> PeerKeyCacheResult<()> {
+        let mut keys = self.keys.write().map_err(|_| PeerKeyCacheError::Poisoned)?;
+        keys.insert(user_id.to_string(), PeerKeyEntry { public_key, verified: true });
+        Ok(())
+    }
+
+    /// The verifying key on file for `user_id`, if any and if it decodes as
+    /// a valid ed25519 key.
+    pub fn get(&self, user_id: &str) -> PeerKeyCacheResult<Option<VerifyingKey>> {
+        let keys = self.keys.read().map_err(|_| PeerKeyCacheError::Poisoned)?;
+        Ok(keys.get(user_id).and_then(|entry| {
+            let bytes: [u8; 32] = entry.public_key.as_slice().try_into().ok()?;
+            VerifyingKey::from_bytes(&bytes).ok()
+        }))
+    }
+
+    /// Whether `user_id`'s cached key (if any) has been verified.
+    pub fn is_verified(&self, user_id: &str) -> PeerKeyCacheResult<bool> {
+        let keys = self.keys.read().map_err(|_| PeerKeyCacheError::Poisoned)?;
+        Ok(keys.get(user_id).map(|entry| entry.verified).unwrap_or(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+    use rand::RngCore;
+
+    fn test_public_key() -> Vec<u8> {
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        SigningKey::from_bytes(&bytes).verifying_key().to_bytes().to_vec()
+    }
+
+    #[test]
+    fn observing_a_key_makes_it_available_but_unverified() {
+        let cache = PeerKeyCache::new();
+        cache.observe("alice", test_public_key()).unwrap();
+
+        assert!(!cache.is_verified("alice").unwrap());
+        assert!(cache.get("alice").unwrap().is_some());
+    }
+
+    #[test]
+    fn a_later_observation_overwrites_an_unverified_key() {
+        let cache = PeerKeyCache::new();
+        cache.observe("alice", test_public_key()).unwrap();
+        let second_key = test_public_key();
+        cache.observe("alice", second_key.clone()).unwrap();
+
+        assert_eq!(cache.get("alice").unwrap().unwrap().to_bytes().to_vec(), second_key);
+    }
+
+    #[test]
+    fn a_verified_key_is_not_downgraded_by_a_later_observation_of_a_different_key() {
+        let cache = PeerKeyCache::new();
+        let verified_key = test_public_key();
+        cache.mark_verified("alice", verified_key.clone()).unwrap();
+
+        cache.observe("alice", test_public_key()).unwrap();
+
+        assert!(cache.is_verified("alice").unwrap());
+        assert_eq!(cache.get("alice").unwrap().unwrap().to_bytes().to_vec(), verified_key);
+    }
+
+    #[test]
+    fn an_observation_of_the_same_key_a_verified_entry_already_holds_is_harmless() {
+        let cache = PeerKeyCache::new();
+        let verified_key = test_public_key();
+        cache.mark_verified("alice", verified_key.clone()).unwrap();
+
+        cache.observe("alice", verified_key).unwrap();
+
+        assert!(cache.is_verified("alice").unwrap());
+    }
+}
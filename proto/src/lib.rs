@@ -0,0 +1,20 @@
+// garden-core/src/lib.rs
+pub mod auth;
+pub mod capability_ledger;
+pub mod clock;
+pub mod commands;
+pub mod data;
+pub mod entries;
+pub mod group_crypto;
+pub mod handshake;
+pub mod history;
+pub mod identity;
+pub mod key_gossip;
+pub mod p2p;
+pub mod path;
+pub mod revocation;
+pub mod service;
+pub mod store;
+pub mod token_store;
+pub mod types;
+pub mod verification;
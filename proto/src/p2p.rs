@@ -1,5 +1,6 @@
 // garden-core/src/p2p.rs
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -19,6 +20,7 @@ use p2panda_net::{
     NetworkId,
     TopicId,
     ToNetwork,
+    FromNetwork,
 };
 
 use p2panda_sync::{
@@ -28,8 +30,26 @@ use p2panda_sync::{
 // Remove the unused import
 // use p2panda_group;
 
-use crate::identity::{Identity, Device};
-use crate::types::{SubspaceId, NamespaceId};
+use crate::auth::mfa::MfaPolicy;
+use crate::auth::AuthToken;
+use crate::entries::GardenEntry;
+use crate::history::{HistoryLog, HistoryResult, HistorySelector};
+use crate::identity::device_list::DeviceList;
+use crate::identity::verify::VerificationSession;
+use crate::handshake::{HandshakeSession, Hello, NegotiatedSession, ReconnectBackoff, ResumeToken};
+use crate::identity::{Capability, Identity, Device};
+use crate::key_gossip::{GossipEnvelope, MessageHeader, PeerKeyCache};
+use crate::service::GardenService;
+use crate::types::{SubspaceId, NamespaceId, Timestamp};
+use crate::token_store::{InMemoryTokenStore, TokenRevocationNotice, TokenStore};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use crate::verification::{TrustStore, VerificationCancel, VerificationHandle, VerificationMac, VerificationOffer};
+use crate::data::group_backup::{self, GroupKeyRecord};
+use crate::group_crypto::{self, GroupCiphertext, ParticipantKeyShare};
+use crate::store::{GardenStore, PeerRecord, StoreBackend};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
 use futures_util::FutureExt;
@@ -58,6 +78,18 @@ pub enum P2PError {
 
     #[error("Group error: {0}")]
     GroupError(String),
+
+    #[error("Verification error: {0}")]
+    VerificationError(String),
+
+    #[error("peer is temporarily banned: {0}")]
+    PeerBanned(String),
+
+    #[error("handshake error: {0}")]
+    HandshakeError(String),
+
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
 }
 
 // Result type for P2P operations
@@ -92,6 +124,196 @@ impl TopicId for Topic {
 
 impl TopicQuery for Topic {}
 
+/// Which `GardenEntry` variant an incoming entry is, for handlers that only
+/// care about one kind rather than matching the entry themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    DirectMessage,
+    GroupMessage,
+    FriendRequest,
+    BlockedUser,
+    MutedUser,
+    Profile,
+    SlashCommand,
+    DeviceKey,
+    GroupMeta,
+    GroupMember,
+    DeviceList,
+    RevocationEntry,
+    CapabilityOp,
+    CapabilityCheckpoint,
+}
+
+impl EntryKind {
+    fn of(entry: &GardenEntry) -> Self {
+        match entry {
+            GardenEntry::DirectMessage { .. } => EntryKind::DirectMessage,
+            GardenEntry::GroupMessage { .. } => EntryKind::GroupMessage,
+            GardenEntry::FriendRequest { .. } => EntryKind::FriendRequest,
+            GardenEntry::BlockedUser { .. } => EntryKind::BlockedUser,
+            GardenEntry::MutedUser { .. } => EntryKind::MutedUser,
+            GardenEntry::Profile { .. } => EntryKind::Profile,
+            GardenEntry::SlashCommand { .. } => EntryKind::SlashCommand,
+            GardenEntry::DeviceKey { .. } => EntryKind::DeviceKey,
+            GardenEntry::GroupMeta { .. } => EntryKind::GroupMeta,
+            GardenEntry::GroupMember { .. } => EntryKind::GroupMember,
+            GardenEntry::DeviceList { .. } => EntryKind::DeviceList,
+            GardenEntry::RevocationEntry { .. } => EntryKind::RevocationEntry,
+            GardenEntry::CapabilityOp { .. } => EntryKind::CapabilityOp,
+            GardenEntry::CapabilityCheckpoint { .. } => EntryKind::CapabilityCheckpoint,
+        }
+    }
+}
+
+/// A network or peer-lifecycle event delivered to every receiver handed
+/// out by `events_stream()`. `MessageReceived` carries the raw, still-encoded
+/// bytes - handlers registered via `on`/`set_entry_handler`/`on_topic` still
+/// get a best-effort decoded `GardenEntry` through `dispatch_entry`, but not
+/// every consumer wants to pay for decoding or cares about `GardenEntry` at
+/// all (e.g. an app showing "N unread on this topic").
+#[derive(Debug, Clone)]
+pub enum GardenEvent {
+    /// A message arrived on a subscribed topic.
+    MessageReceived { topic: Topic, peer: String, bytes: Vec<u8> },
+    /// `connect_to_peer` successfully (re)established a connection.
+    PeerJoined { peer: String },
+    /// `disconnect_from_peer` tore down a connection.
+    PeerLeft { peer: String },
+    /// A topic's gossip overlay finished joining and is ready to send/receive.
+    GossipReady { topic: Topic },
+    /// Anything else the underlying network layer surfaces through
+    /// `Network::events()` that doesn't have its own typed variant above
+    /// (peer discovery, sync progress, ...) - `Debug`-formatted rather than
+    /// dropped, so a consumer watching `events_stream()` still sees it.
+    Other(String),
+}
+
+/// How many events `events_stream()` buffers per receiver before a slow
+/// consumer starts missing the oldest ones - see `tokio::sync::broadcast`.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// A discovery-backend-agnostic address for a manually configured peer
+/// (bootstrap list or one added at runtime via `add_peer`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PeerAddr(String);
+
+impl PeerAddr {
+    pub fn new(addr: &str) -> Self {
+        PeerAddr(addr.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A peer's identity, sealed so it can only be constructed from a verified
+/// identity public key - never fabricated from an address string or other
+/// unauthenticated input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PeerId([u8; 32]);
+
+impl PeerId {
+    /// The only way to construct a `PeerId`: from an `Identity` whose public
+    /// key has already been verified (e.g. against a signed `DeviceKey` or
+    /// `DeviceList` entry).
+    pub fn from_verified_identity(identity: &Identity) -> Option<Self> {
+        let bytes: [u8; 32] = identity.public_key.clone().try_into().ok()?;
+        Some(PeerId(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+pub type HandlerId = u64;
+type EntryHandler = Box<dyn Fn(&GardenEntry) + Send + Sync>;
+
+struct RegisteredHandler {
+    id: HandlerId,
+    kind: Option<EntryKind>,
+    topic: Option<Topic>,
+    callback: EntryHandler,
+}
+
+/// Record `entry`, received on `topic`, to history/storage and run every
+/// handler whose kind/topic filters match. Shared by `GardenClient::dispatch_entry`
+/// and the per-topic ingest task spawned by `subscribe`, which only has the
+/// individually `Arc`-wrapped fields rather than a whole `&GardenClient`.
+///
+/// Also merges `entry`'s own timestamp into `clock` via `observe` - every
+/// entry that reaches here, whether authored locally or received from a
+/// peer, advances this client's causal clock, the same way `stamp_event`
+/// does for events we originate ourselves. A rejected merge (excessive
+/// drift - see `crate::clock::ClockError`) is intentionally not fatal to
+/// dispatch: it only means we decline to let a bogus remote clock drag our
+/// own clock forward, not that the entry itself is refused.
+fn dispatch_entry_to(
+    history: &HistoryLog,
+    store: &dyn GardenStore,
+    handlers: &Mutex<Vec<RegisteredHandler>>,
+    clock: &Mutex<crate::clock::HybridLogicalClock>,
+    topic: &Topic,
+    entry: &GardenEntry,
+) -> P2PResult<()> {
+    if let Ok(mut clock) = clock.lock() {
+        let _ = clock.observe(entry.timestamp());
+    }
+
+    history.record(topic, entry.clone())
+        .map_err(|e| P2PError::StorageError(e.to_string()))?;
+
+    store.save_last_entry(topic, entry)
+        .map_err(|e| P2PError::StorageError(e.to_string()))?;
+
+    let handlers = handlers.lock()
+        .map_err(|_| P2PError::StorageError("Failed to lock handlers".to_string()))?;
+    let entry_kind = EntryKind::of(entry);
+
+    for handler in handlers.iter() {
+        if handler.kind.is_some_and(|k| k != entry_kind) {
+            continue;
+        }
+        if handler.topic.as_ref().is_some_and(|t| t != topic) {
+            continue;
+        }
+        let callback = &handler.callback;
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| callback(entry)));
+    }
+
+    Ok(())
+}
+
+/// Discovery-backend selection and manual peer configuration, grouped into
+/// its own struct so a caller can build and reuse a discovery policy
+/// independently of the rest of a client's configuration.
+#[derive(Debug, Clone)]
+pub struct DiscoveryConfig {
+    /// Whether to enable mDNS local-network discovery. Disable this on
+    /// privacy-sensitive or locked-down networks where broadcasting
+    /// presence over the local network is undesirable.
+    pub enable_mdns: bool,
+    /// Peers to dial directly on startup, bypassing discovery entirely -
+    /// needed on networks mDNS can't reach, or when mDNS is disabled.
+    pub bootstrap_peers: Vec<PeerAddr>,
+    /// Identifies which network mesh this client should join. Hashed into
+    /// the `NetworkId` p2panda-net partitions peers by, so two clients
+    /// configured with different namespaces never discover or connect to
+    /// each other even on the same physical network.
+    pub network_namespace: String,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        DiscoveryConfig {
+            enable_mdns: true,
+            bootstrap_peers: Vec::new(),
+            network_namespace: "garden-default".to_string(),
+        }
+    }
+}
+
 // Garden P2P Configuration
 #[derive(Debug, Clone)]
 pub struct GardenConfig {
@@ -99,12 +321,30 @@ pub struct GardenConfig {
     pub discovery_timeout: Duration,
     pub connection_timeout: Duration,
     pub max_connections: usize,
-    
+
     // App-specific settings
     pub user_identity: Option<Identity>,
     pub device: Option<Device>,
     pub namespaces: HashMap<String, NamespaceId>,
     pub data_directory: String,
+
+    /// Discovery backend selection (mDNS on/off), manual bootstrap peers,
+    /// and the namespace the `NetworkId` is derived from.
+    pub discovery: DiscoveryConfig,
+
+    /// Where subscribed topics, subspace mappings, and group session state
+    /// persist across restarts. Defaults to in-memory, i.e. nothing
+    /// survives `shutdown()`.
+    pub store_backend: StoreBackend,
+
+    /// How aggressively to trade bandwidth for latency, from `1`
+    /// (minimize bandwidth: longer discovery intervals, a smaller
+    /// connection mesh, larger send-side batching windows) to `5`
+    /// (maximize responsiveness: short intervals, a larger mesh,
+    /// near-immediate sends). Out-of-range values clamp to `1..=5`. Drives
+    /// `discovery_timeout`/`max_connections` and the send path's batching
+    /// delay - see `NetworkLoadProfile::for_level`.
+    pub network_load: u8,
 }
 
 impl Default for GardenConfig {
@@ -117,6 +357,202 @@ impl Default for GardenConfig {
             device: None,
             namespaces: HashMap::new(),
             data_directory: "./garden-data".to_string(),
+            discovery: DiscoveryConfig::default(),
+            store_backend: StoreBackend::default(),
+            network_load: 3,
+        }
+    }
+}
+
+/// Concrete settings `network_load` maps to: how long discovery gets before
+/// it's considered idle, how many simultaneous connections the mesh
+/// maintains, and how long `send_message` delays a send to give nearby
+/// calls a chance to go out together instead of as separate transmissions.
+/// Mirrors the bandwidth-vs-latency tradeoff gossip-heavy p2p stacks (e.g.
+/// libp2p's gossipsub) expose as a tunable profile rather than a single
+/// fixed behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NetworkLoadProfile {
+    discovery_timeout: Duration,
+    max_connections: usize,
+    batch_interval: Duration,
+}
+
+impl NetworkLoadProfile {
+    fn for_level(level: u8) -> Self {
+        match level.clamp(1, 5) {
+            1 => NetworkLoadProfile {
+                discovery_timeout: Duration::from_secs(120),
+                max_connections: 10,
+                batch_interval: Duration::from_millis(2000),
+            },
+            2 => NetworkLoadProfile {
+                discovery_timeout: Duration::from_secs(60),
+                max_connections: 25,
+                batch_interval: Duration::from_millis(1000),
+            },
+            3 => NetworkLoadProfile {
+                discovery_timeout: Duration::from_secs(30),
+                max_connections: 50,
+                batch_interval: Duration::from_millis(250),
+            },
+            4 => NetworkLoadProfile {
+                discovery_timeout: Duration::from_secs(10),
+                max_connections: 100,
+                batch_interval: Duration::from_millis(50),
+            },
+            _ => NetworkLoadProfile {
+                discovery_timeout: Duration::from_secs(3),
+                max_connections: 200,
+                batch_interval: Duration::ZERO,
+            },
+        }
+    }
+}
+
+/// How far back `BandwidthCounter::stats` looks for its oldest sample when
+/// computing a moving-average rate.
+const BANDWIDTH_RATE_WINDOW: Duration = Duration::from_secs(10);
+/// Cap on how many samples a single bucket keeps, so a bucket that's
+/// queried constantly doesn't grow its sample history unbounded.
+const BANDWIDTH_MAX_SAMPLES: usize = 32;
+
+/// One bandwidth bucket's (a topic's or a peer's) byte totals and
+/// short-term throughput, as of the moment `bandwidth_stats` was called.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandwidthStats {
+    pub inbound_bytes: u64,
+    pub outbound_bytes: u64,
+    pub inbound_rate_bytes_per_sec: f64,
+    pub outbound_rate_bytes_per_sec: f64,
+}
+
+/// Everything `bandwidth_stats` reports, bucketed by topic and by peer.
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthReport {
+    pub by_topic: HashMap<Topic, BandwidthStats>,
+    pub by_peer: HashMap<String, BandwidthStats>,
+}
+
+/// One bucket's running byte totals plus enough recent history to compute a
+/// moving-average rate. Totals are plain atomics so the hot send/receive
+/// path never blocks on a lock; the sample history, needed only when
+/// `bandwidth_stats` is actually called, is the one part behind a `Mutex`.
+#[derive(Debug, Default)]
+struct BandwidthCounter {
+    inbound_total: AtomicU64,
+    outbound_total: AtomicU64,
+    samples: Mutex<VecDeque<(std::time::Instant, u64, u64)>>,
+}
+
+impl BandwidthCounter {
+    fn record_inbound(&self, bytes: u64) {
+        self.inbound_total.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn record_outbound(&self, bytes: u64) {
+        self.outbound_total.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Snapshot this bucket's totals and a moving-average rate measured
+    /// from the oldest sample still within `BANDWIDTH_RATE_WINDOW`, then
+    /// record the current totals as a fresh sample for the next call.
+    fn stats(&self) -> P2PResult<BandwidthStats> {
+        let inbound_total = self.inbound_total.load(Ordering::Relaxed);
+        let outbound_total = self.outbound_total.load(Ordering::Relaxed);
+        let now = std::time::Instant::now();
+
+        let mut samples = self.samples.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock bandwidth samples".to_string()))?;
+        samples.retain(|(at, _, _)| now.saturating_duration_since(*at) < BANDWIDTH_RATE_WINDOW);
+
+        let (inbound_rate, outbound_rate) = match samples.front() {
+            Some((at, in_then, out_then)) => {
+                let elapsed = now.saturating_duration_since(*at).as_secs_f64();
+                if elapsed > 0.0 {
+                    (
+                        inbound_total.saturating_sub(*in_then) as f64 / elapsed,
+                        outbound_total.saturating_sub(*out_then) as f64 / elapsed,
+                    )
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            None => (0.0, 0.0),
+        };
+
+        samples.push_back((now, inbound_total, outbound_total));
+        if samples.len() > BANDWIDTH_MAX_SAMPLES {
+            samples.pop_front();
+        }
+
+        Ok(BandwidthStats {
+            inbound_bytes: inbound_total,
+            outbound_bytes: outbound_total,
+            inbound_rate_bytes_per_sec: inbound_rate,
+            outbound_rate_bytes_per_sec: outbound_rate,
+        })
+    }
+}
+
+/// Look up (or lazily create) the bandwidth counter for `key` in `map`.
+/// Shared by `GardenClient`'s own methods and the per-topic ingest task
+/// spawned by `subscribe`, which only has `Arc`-cloned fields rather than a
+/// whole `&GardenClient`.
+fn bandwidth_counter_for<K: std::hash::Hash + Eq + Clone>(
+    map: &Mutex<HashMap<K, Arc<BandwidthCounter>>>,
+    key: &K,
+) -> P2PResult<Arc<BandwidthCounter>> {
+    let mut map = map.lock()
+        .map_err(|_| P2PError::StorageError("Failed to lock bandwidth counters".to_string()))?;
+    Ok(Arc::clone(map.entry(key.clone()).or_insert_with(|| Arc::new(BandwidthCounter::default()))))
+}
+
+/// Cap on how many peers' connection state is persisted across restarts,
+/// mirroring how beacon nodes bound their on-disk DHT/peer table.
+const MAX_PERSISTED_PEERS: usize = 200;
+/// Peers not seen within this long are pruned rather than persisted or
+/// redialed, so a long-dead address isn't carried forward forever.
+const PEER_RECORD_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How severely `report_peer`'s `action` adjusts a peer's reputation score.
+/// Thresholds loosely follow the ones beacon-node libp2p stacks use: a
+/// low-tolerance violation needs to accumulate a few times before
+/// mattering, while a single high-tolerance one can ban a peer outright.
+const SCORE_LOW_TOLERANCE_ERROR: i32 = -5;
+const SCORE_MID_TOLERANCE_ERROR: i32 = -10;
+const SCORE_HIGH_TOLERANCE_ERROR: i32 = -20;
+const SCORE_VALID_MESSAGE: i32 = 1;
+
+/// A peer whose score falls to or below this is banned.
+const BAN_SCORE_THRESHOLD: i32 = -20;
+/// How long a ban lasts once triggered - the 30s default several
+/// beacon-node P2P stacks use for a first offense.
+const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(30);
+const MIN_SCORE: i32 = -100;
+const MAX_SCORE: i32 = 100;
+
+/// An observation about a peer's behavior, fed to `report_peer` to adjust
+/// its reputation score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerAction {
+    /// A minor protocol violation - a handful of these are tolerated.
+    LowToleranceError,
+    /// A more serious violation - tolerated only a couple of times before banning.
+    MidToleranceError,
+    /// A severe violation that can ban a peer outright.
+    HighToleranceError,
+    /// Well-formed, useful traffic - slowly repairs a peer's score.
+    ValidMessage,
+}
+
+impl PeerAction {
+    fn score_delta(self) -> i32 {
+        match self {
+            PeerAction::LowToleranceError => SCORE_LOW_TOLERANCE_ERROR,
+            PeerAction::MidToleranceError => SCORE_MID_TOLERANCE_ERROR,
+            PeerAction::HighToleranceError => SCORE_HIGH_TOLERANCE_ERROR,
+            PeerAction::ValidMessage => SCORE_VALID_MESSAGE,
         }
     }
 }
@@ -128,6 +564,12 @@ pub struct PeerConnection {
     pub topics: Vec<Topic>,
     pub last_seen: std::time::Instant,
     pub is_active: bool,
+    /// Reputation score, adjusted by `report_peer`. Starts peers off
+    /// neutral; dropping to or below `BAN_SCORE_THRESHOLD` bans the peer.
+    pub score: i32,
+    /// Set by `report_peer` once `score` crosses the ban threshold;
+    /// `connect_to_peer` refuses to (re)connect until this elapses.
+    pub banned_until: Option<std::time::Instant>,
 }
 
 // Mock implementations for p2panda-group functionality
@@ -141,9 +583,133 @@ struct MockGroup {
 }
 
 #[derive(Debug, Clone)]
-struct MockGroupSession {
+struct GroupSession {
     #[allow(dead_code)]
     group_id: String,
+    /// Bumped by `remove_group_member`/`rotate_group_key`. Messages tagged
+    /// with an older epoch than this are from before the group's last
+    /// rekey and are rejected, so a removed member can't keep decrypting
+    /// traffic forever even if it's still received.
+    epoch: u64,
+    /// This participant's index in the group's Feldman-VSS DKG (see
+    /// `crate::group_crypto`).
+    index: u64,
+    /// This participant's real, Feldman-verified share of the group
+    /// secret - unlike the old mock's random bytes, nobody (not even the
+    /// group's creator) ever holds the full secret this sums to once
+    /// `threshold > 1`.
+    secret_share: Scalar,
+    /// The aggregate DKG commitments, kept so this session's own share can
+    /// be re-verified later and so a future real multi-peer resharing round
+    /// would have something to check incoming shares against.
+    commitments: Vec<RistrettoPoint>,
+    group_public_key: RistrettoPoint,
+    threshold: usize,
+}
+
+/// Recover the `group_id` a topic created by `create_group_message_topic`
+/// ("garden/group/{group_id}") carries, or `None` for any other topic shape.
+fn group_id_from_topic(topic: &Topic) -> Option<&str> {
+    topic.name().strip_prefix("garden/group/")
+}
+
+/// Derive a `NetworkId` deterministically from a namespace string, so two
+/// clients configured with different `DiscoveryConfig::network_namespace`s
+/// never discover or connect to each other even over the same physical
+/// network.
+fn network_id_for_namespace(namespace: &str) -> NetworkId {
+    let digest = Sha256::digest(namespace.as_bytes());
+    let mut network_id = [0u8; 32];
+    network_id.copy_from_slice(&digest);
+    network_id
+}
+
+/// Rekey `group_id`'s session by running a fresh DKG round. Bumps `epoch`
+/// and rotates the secret share, same as the old mock's random-bytes
+/// rotation, just with real key material.
+fn new_group_session(group_id: &str, epoch: u64, participant_indices: &[u64], threshold: usize, index: u64) -> GroupSession {
+    let shares = group_crypto::run_dkg(participant_indices, threshold);
+    let share = shares.into_iter().find(|s| s.index == index)
+        .expect("the requested index was part of the DKG's participant list");
+    group_session_from_share(group_id, epoch, share)
+}
+
+fn group_session_from_share(group_id: &str, epoch: u64, share: ParticipantKeyShare) -> GroupSession {
+    GroupSession {
+        group_id: group_id.to_string(),
+        epoch,
+        index: share.index,
+        secret_share: share.secret_share,
+        commitments: share.commitments,
+        group_public_key: share.group_public_key,
+        threshold: share.threshold,
+    }
+}
+
+/// The wire form of a `GroupSession`'s key material, exchanged between
+/// `create_encrypted_group` and `join_encrypted_group`. See
+/// `join_encrypted_group`'s doc comment for why this has to carry the
+/// actual secret share rather than just the group's public key.
+#[derive(Debug, Serialize, Deserialize)]
+struct GroupKeyPackage {
+    group_public_key: [u8; 32],
+    commitments: Vec<[u8; 32]>,
+    threshold: usize,
+    secret_share: [u8; 32],
+}
+
+impl GroupKeyPackage {
+    fn from_session(session: &GroupSession) -> Self {
+        GroupKeyPackage {
+            group_public_key: session.group_public_key.compress().to_bytes(),
+            commitments: session.commitments.iter().map(|c| c.compress().to_bytes()).collect(),
+            threshold: session.threshold,
+            secret_share: session.secret_share.to_bytes(),
+        }
+    }
+
+    fn to_bytes(&self) -> P2PResult<Vec<u8>> {
+        serde_json::to_vec(self)
+            .map_err(|e| P2PError::GroupError(format!("failed to serialize key package: {}", e)))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> P2PResult<Self> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| P2PError::GroupError(format!("failed to parse key package: {}", e)))
+    }
+
+    /// Verify the package's share against its own commitments via Feldman
+    /// VSS, then turn it into a session at `index` for this new member.
+    /// `index` doesn't affect correctness while `threshold == 1` (every
+    /// index evaluates a constant polynomial to the same value), but is
+    /// kept meaningful for when a real resharing round can issue distinct
+    /// per-member shares.
+    fn into_session(self, group_id: &str, index: u64) -> P2PResult<GroupSession> {
+        let decompress = |bytes: [u8; 32]| -> P2PResult<RistrettoPoint> {
+            CompressedRistretto(bytes).decompress()
+                .ok_or_else(|| P2PError::GroupError("key package contains an invalid curve point".to_string()))
+        };
+
+        let group_public_key = decompress(self.group_public_key)?;
+        let commitments = self.commitments.iter().map(|c| decompress(*c)).collect::<P2PResult<Vec<_>>>()?;
+        let secret_share = Scalar::from_bytes_mod_order(self.secret_share);
+
+        if !group_crypto::verify_share(&secret_share, index, &commitments) {
+            return Err(P2PError::GroupError(
+                group_crypto::GroupCryptoError::ShareVerificationFailed.to_string(),
+            ));
+        }
+
+        Ok(GroupSession {
+            group_id: group_id.to_string(),
+            epoch: 0,
+            index,
+            secret_share,
+            commitments,
+            group_public_key,
+            threshold: self.threshold,
+        })
+    }
 }
 
 // Garden P2P Client
@@ -155,47 +721,232 @@ pub struct GardenClient {
     // Garden-specific state
     pub config: GardenConfig, // Used to store configuration for reference
     connections: Arc<Mutex<HashMap<String, PeerConnection>>>,
-    subscribed_topics: Arc<Mutex<Vec<Topic>>>,
-    user_subspaces: Arc<Mutex<HashMap<String, SubspaceId>>>,
-    
+
+    // Subspace lookups and topic subscriptions each own their own lock via
+    // `GardenService`'s registries, rather than being bundled behind a
+    // single client-wide mutex.
+    service: GardenService,
+
     // Group messaging state - using mock implementations until actual API is available
     groups: Arc<Mutex<HashMap<String, MockGroup>>>,
-    group_sessions: Arc<Mutex<HashMap<String, MockGroupSession>>>,
+    group_sessions: Arc<Mutex<HashMap<String, GroupSession>>>,
+
+    // Event-handler callbacks, demultiplexed by entry kind and/or topic at dispatch time.
+    handlers: Arc<Mutex<Vec<RegisteredHandler>>>,
+    next_handler_id: Arc<AtomicU64>,
+
+    // Manually configured peers: the config's bootstrap list plus any added at runtime.
+    manual_peers: Arc<Mutex<Vec<PeerAddr>>>,
+
+    // Per-topic log of entries this client has observed, for CHATHISTORY-style backfill.
+    history: Arc<HistoryLog>,
+
+    // Devices that have passed out-of-band SAS verification (see `crate::verification`).
+    trust_store: Arc<TrustStore>,
+
+    // Keys gossiped via message headers, keyed by user_id (see `crate::key_gossip`).
+    peer_keys: Arc<PeerKeyCache>,
+
+    // Negotiated handshake sessions, keyed by peer_id (see `crate::handshake`).
+    sessions: Arc<Mutex<HashMap<String, NegotiatedSession>>>,
+
+    // Per-path MFA step-up freshness requirements (see `crate::auth::mfa`),
+    // consulted by sensitive mutations like `remove_group_member` and
+    // `revoke_device` alongside the usual capability check.
+    mfa_policy: Arc<Mutex<MfaPolicy>>,
+
+    // Keys a delegated `AuthToken`'s chain must ultimately trace back to
+    // (see `AuthToken::verify_chain`/`has_verified_capability`), registered
+    // via `add_trusted_capability_root`. A token with no delegation `proof`
+    // is unaffected by this - only a token that actually claims a
+    // delegation chain needs it to verify against one of these.
+    trusted_capability_roots: Arc<Mutex<Vec<VerifyingKey>>>,
+
+    // The most recently recorded `DeviceList` per user_id (see
+    // `crate::identity::device_list`), registered via `record_device_list`.
+    // Consulted by `enforce_step_up` so a token whose device was since
+    // revoked from this list can't keep acting on `devices/<user_id>/...`
+    // paths just because the token itself hasn't expired yet.
+    device_lists: Arc<Mutex<HashMap<String, DeviceList>>>,
+
+    // This client's causal clock (see `crate::clock`). `stamp_event` advances
+    // it for events we author ourselves; `dispatch_entry_to` merges in the
+    // timestamp carried by every entry we dispatch, local or remote, via
+    // `observe`, so the local clock never falls behind whatever the rest of
+    // the garden has already seen.
+    clock: Arc<Mutex<crate::clock::HybridLogicalClock>>,
+
+    // Where topic subscriptions, subspace mappings, and group session state
+    // persist across restarts (see `crate::store`).
+    store: Arc<dyn GardenStore>,
+
+    // Issued tokens and per-id revocations (see `crate::token_store`) -
+    // pluggable like `store` above, defaulting to an in-memory backend.
+    token_store: Arc<dyn TokenStore>,
+
+    // Broadcasts `GardenEvent`s to every receiver handed out by `events_stream()`.
+    events_tx: tokio::sync::broadcast::Sender<GardenEvent>,
+
+    // Concrete discovery/connection/batching settings derived from
+    // `config.network_load` - see `NetworkLoadProfile::for_level`.
+    load_profile: NetworkLoadProfile,
+
+    // Byte counters for `bandwidth_stats`, bucketed by topic and by peer.
+    topic_bandwidth: Arc<Mutex<HashMap<Topic, Arc<BandwidthCounter>>>>,
+    peer_bandwidth: Arc<Mutex<HashMap<String, Arc<BandwidthCounter>>>>,
 }
 
 impl GardenClient {
     /// Create a new Garden P2P client with the given configuration
     pub async fn new(config: GardenConfig, private_key: PrivateKey) -> P2PResult<Self> {
+        let bootstrap_peers = config.discovery.bootstrap_peers.clone();
+        let store: Arc<dyn GardenStore> = config.store_backend.open()
+            .map_err(|e| P2PError::StorageError(e.to_string()))?
+            .into();
+        let (events_tx, _) = tokio::sync::broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let load_profile = NetworkLoadProfile::for_level(config.network_load);
         Ok(GardenClient {
             network: None,
             private_key,
             config,
             connections: Arc::new(Mutex::new(HashMap::new())),
-            subscribed_topics: Arc::new(Mutex::new(Vec::new())),
-            user_subspaces: Arc::new(Mutex::new(HashMap::new())),
+            service: GardenService::new(),
             groups: Arc::new(Mutex::new(HashMap::new())),
             group_sessions: Arc::new(Mutex::new(HashMap::new())),
+            handlers: Arc::new(Mutex::new(Vec::new())),
+            next_handler_id: Arc::new(AtomicU64::new(1)),
+            manual_peers: Arc::new(Mutex::new(bootstrap_peers)),
+            history: Arc::new(HistoryLog::new()),
+            trust_store: Arc::new(TrustStore::new()),
+            peer_keys: Arc::new(PeerKeyCache::new()),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            mfa_policy: Arc::new(Mutex::new(MfaPolicy::new())),
+            trusted_capability_roots: Arc::new(Mutex::new(Vec::new())),
+            device_lists: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(Mutex::new(crate::clock::HybridLogicalClock::new())),
+            store,
+            token_store: Arc::new(InMemoryTokenStore::new()),
+            events_tx,
+            load_profile,
+            topic_bandwidth: Arc::new(Mutex::new(HashMap::new())),
+            peer_bandwidth: Arc::new(Mutex::new(HashMap::new())),
         })
     }
 
+    /// Snapshot accumulated byte totals and a moving-average rate,
+    /// bucketed by topic and by peer. Each call also records a fresh
+    /// sample, so rates reflect throughput since the last call (or since
+    /// `BANDWIDTH_RATE_WINDOW` ago, whichever is shorter).
+    pub fn bandwidth_stats(&self) -> P2PResult<BandwidthReport> {
+        let topic_bandwidth = self.topic_bandwidth.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock topic bandwidth counters".to_string()))?;
+        let mut by_topic = HashMap::new();
+        for (topic, counter) in topic_bandwidth.iter() {
+            by_topic.insert(topic.clone(), counter.stats()?);
+        }
+        drop(topic_bandwidth);
+
+        let peer_bandwidth = self.peer_bandwidth.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock peer bandwidth counters".to_string()))?;
+        let mut by_peer = HashMap::new();
+        for (peer, counter) in peer_bandwidth.iter() {
+            by_peer.insert(peer.clone(), counter.stats()?);
+        }
+
+        Ok(BandwidthReport { by_topic, by_peer })
+    }
+
+    /// Subscribe to this client's stream of `GardenEvent`s - message
+    /// delivery, peer join/leave, and gossip readiness - instead of
+    /// registering a callback. Each call returns an independent receiver; a
+    /// receiver that falls behind drops the oldest missed events rather than
+    /// blocking delivery to anyone else (see `tokio::sync::broadcast`).
+    pub fn events_stream(&self) -> tokio::sync::broadcast::Receiver<GardenEvent> {
+        self.events_tx.subscribe()
+    }
+
     /// Initialize the P2P network components
     pub async fn initialize(&mut self) -> P2PResult<()> {
-        // Create a unique network ID
-        let network_id: NetworkId = [1; 32];
-        
-        // Create local discovery service
-        let local_discovery = LocalDiscovery::new();
-        
-        // Build the network
-        let network = NetworkBuilder::new(network_id)
+        if !self.config.discovery.enable_mdns && self.config.discovery.bootstrap_peers.is_empty() {
+            return Err(P2PError::DiscoveryError(
+                "no discovery backend enabled: set enable_mdns or configure at least one bootstrap peer".to_string(),
+            ));
+        }
+
+        let network_id = network_id_for_namespace(&self.config.discovery.network_namespace);
+
+        // Re-derive the load profile in case `config.network_load` was
+        // changed since `new()`, and reflect it back onto the legacy
+        // `discovery_timeout`/`max_connections` fields so callers reading
+        // them see the level's actual values.
+        self.load_profile = NetworkLoadProfile::for_level(self.config.network_load);
+        self.config.discovery_timeout = self.load_profile.discovery_timeout;
+        self.config.max_connections = self.load_profile.max_connections;
+
+        let mut builder = NetworkBuilder::new(network_id)
             .private_key(self.private_key.clone())
-            .discovery(local_discovery)
+            .max_connections(self.load_profile.max_connections);
+        if self.config.discovery.enable_mdns {
+            builder = builder.discovery(LocalDiscovery::new());
+        }
+
+        // Build the network
+        let network = builder
             .build()
             .await
             .map_err(|e| P2PError::InitializationError(format!("Failed to build network: {}", e)))?;
-            
+
         self.network = Some(network);
 
+        // Record every configured bootstrap peer in `connections` as active.
+        // No connection is dialed here; reaching the peer is left to
+        // p2panda-net.
+        let mut connections = self.connections.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
+        for peer in &self.config.discovery.bootstrap_peers {
+            connections.insert(
+                peer.as_str().to_string(),
+                PeerConnection {
+                    peer_id: peer.as_str().to_string(),
+                    topics: Vec::new(),
+                    last_seen: std::time::Instant::now(),
+                    is_active: true,
+                    score: 0,
+                    banned_until: None,
+                },
+            );
+        }
+        drop(connections);
+
+        // Re-dial recently-seen peers from the last run, so this client has
+        // a head start before mDNS/bootstrap discovery completes.
+        self.redial_persisted_peers().await?;
+
+        Ok(())
+    }
+
+    /// Rejoin every topic and encrypted-group session this client had
+    /// persisted to its `GardenStore`, so a client created from a
+    /// previously used store picks up its memberships automatically
+    /// instead of starting empty. Must run after `initialize`, since
+    /// resubscribing needs the network to already be up.
+    pub async fn rehydrate_from_store(&self) -> P2PResult<()> {
+        for (name, subspace) in self.store.subspaces()
+            .map_err(|e| P2PError::StorageError(e.to_string()))? {
+            self.service.register_subspace(&name, subspace)
+                .map_err(|e| P2PError::StorageError(e.to_string()))?;
+        }
+
+        for record in self.store.group_sessions()
+            .map_err(|e| P2PError::StorageError(e.to_string()))? {
+            self.restore_group_key_record(record)?;
+        }
+
+        for topic in self.store.topics()
+            .map_err(|e| P2PError::StorageError(e.to_string()))? {
+            self.subscribe(topic).await?;
+        }
+
         Ok(())
     }
 
@@ -221,19 +972,71 @@ impl GardenClient {
         }
     }
 
-    /// Subscribe to a topic
+    /// Subscribe to a topic. Keeps the topic's gossip stream alive and
+    /// delivers what arrives on it - a `GardenEvent::MessageReceived` to
+    /// every `events_stream()` receiver, and, for anything that decodes as a
+    /// `GardenEntry`, a further `dispatch_entry` to handlers registered via
+    /// `on`/`set_entry_handler`/`on_topic`. Previously `_rx`/`_ready` were
+    /// dropped immediately, so incoming messages were silently discarded.
     pub async fn subscribe(&self, topic: Topic) -> P2PResult<()> {
         if let Some(network) = &self.network {
             // Subscribe to the topic using p2panda-net's API
-            let (_tx, _rx, _ready) = network.subscribe(topic.clone())
+            let (_tx, mut rx, ready) = network.subscribe(topic.clone())
                 .await
                 .map_err(|e| P2PError::NetworkError(format!("Failed to subscribe to topic: {}", e)))?;
-            
-            // Add to our list of subscribed topics
-            let mut topics = self.subscribed_topics.lock()
-                .map_err(|_| P2PError::NetworkError("Failed to lock subscribed topics".to_string()))?;
-            
-            topics.push(topic);
+
+            self.service.subscribe_topic(topic.clone())
+                .map_err(|e| P2PError::NetworkError(e.to_string()))?;
+
+            self.store.save_topic(&topic)
+                .map_err(|e| P2PError::StorageError(e.to_string()))?;
+
+            let ingest_topic = topic.clone();
+            let history = Arc::clone(&self.history);
+            let store = Arc::clone(&self.store);
+            let handlers = Arc::clone(&self.handlers);
+            let clock = Arc::clone(&self.clock);
+            let events_tx = self.events_tx.clone();
+            let topic_bandwidth = Arc::clone(&self.topic_bandwidth);
+            let peer_bandwidth = Arc::clone(&self.peer_bandwidth);
+            tokio::spawn(async move {
+                while let Ok(event) = rx.recv().await {
+                    let (bytes, peer) = match event {
+                        FromNetwork::GossipMessage { bytes, delivered_from } => {
+                            (bytes, format!("{delivered_from:?}"))
+                        }
+                        FromNetwork::SyncMessage { payload: Some(bytes), delivered_from, .. } => {
+                            (bytes, format!("{delivered_from:?}"))
+                        }
+                        FromNetwork::SyncMessage { payload: None, .. } => continue,
+                    };
+
+                    if let Ok(counter) = bandwidth_counter_for(&topic_bandwidth, &ingest_topic) {
+                        counter.record_inbound(bytes.len() as u64);
+                    }
+                    if let Ok(counter) = bandwidth_counter_for(&peer_bandwidth, &peer) {
+                        counter.record_inbound(bytes.len() as u64);
+                    }
+
+                    let _ = events_tx.send(GardenEvent::MessageReceived {
+                        topic: ingest_topic.clone(),
+                        peer,
+                        bytes: bytes.clone(),
+                    });
+
+                    if let Ok(entry) = serde_json::from_slice::<GardenEntry>(&bytes) {
+                        let _ = dispatch_entry_to(&history, store.as_ref(), &handlers, &clock, &ingest_topic, &entry);
+                    }
+                }
+            });
+
+            let ready_topic = topic.clone();
+            let ready_events_tx = self.events_tx.clone();
+            tokio::spawn(async move {
+                let _ = ready.await;
+                let _ = ready_events_tx.send(GardenEvent::GossipReady { topic: ready_topic });
+            });
+
             Ok(())
         } else {
             Err(P2PError::NetworkError("Network not initialized".to_string()))
@@ -245,17 +1048,46 @@ impl GardenClient {
         if let Some(_network) = &self.network {
             // p2panda-net doesn't provide a direct way to unsubscribe
             // Instead, we'll just remove it from our tracking
-            let mut topics = self.subscribed_topics.lock()
-                .map_err(|_| P2PError::NetworkError("Failed to lock subscribed topics".to_string()))?;
-            
-            topics.retain(|t| t != topic);
-            Ok(())
+            self.service.unsubscribe_topic(topic)
+                .map_err(|e| P2PError::NetworkError(e.to_string()))?;
+
+            self.store.remove_topic(topic)
+                .map_err(|e| P2PError::StorageError(e.to_string()))
         } else {
             Err(P2PError::NetworkError("Network not initialized".to_string()))
         }
     }
 
-    /// Connect to a peer
+    /// Add a manually configured peer to the `manual_peers` set. This only
+    /// records the address; no connection to it is opened. A no-op if
+    /// already present.
+    pub async fn add_peer(&self, addr: PeerAddr) -> P2PResult<()> {
+        let mut peers = self.manual_peers.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock manual peers".to_string()))?;
+        if !peers.contains(&addr) {
+            peers.push(addr);
+        }
+        Ok(())
+    }
+
+    /// Remove a manually configured peer. A no-op if it wasn't present.
+    pub async fn remove_peer(&self, addr: &PeerAddr) -> P2PResult<()> {
+        let mut peers = self.manual_peers.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock manual peers".to_string()))?;
+        peers.retain(|p| p != addr);
+        Ok(())
+    }
+
+    /// The current set of manually configured peers (config's bootstrap list
+    /// plus any added at runtime, minus any removed).
+    pub fn manual_peers(&self) -> P2PResult<Vec<PeerAddr>> {
+        let peers = self.manual_peers.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock manual peers".to_string()))?;
+        Ok(peers.clone())
+    }
+
+    /// Connect to a peer. Refuses to (re)connect while the peer is banned
+    /// (see `report_peer`) - retry after the ban expires.
     pub async fn connect_to_peer(&self, peer_id: &str) -> P2PResult<()> {
         // p2panda-net automatically manages connections to peers
         // This is kept for API compatibility
@@ -263,7 +1095,20 @@ impl GardenClient {
             // Track the connection in our state
             let mut connections = self.connections.lock()
                 .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
-            
+
+            let existing_score = if let Some(existing) = connections.get(peer_id) {
+                if let Some(banned_until) = existing.banned_until {
+                    if std::time::Instant::now() < banned_until {
+                        return Err(P2PError::PeerBanned(format!(
+                            "peer {} is banned until its ban expires", peer_id
+                        )));
+                    }
+                }
+                existing.score
+            } else {
+                0
+            };
+
             connections.insert(
                 peer_id.to_string(),
                 PeerConnection {
@@ -271,54 +1116,209 @@ impl GardenClient {
                     topics: Vec::new(),
                     last_seen: std::time::Instant::now(),
                     is_active: true,
+                    score: existing_score,
+                    banned_until: None,
                 },
             );
-            
-            Ok(())
-        } else {
-            Err(P2PError::NetworkError("Network not initialized".to_string()))
-        }
-    }
+            drop(connections);
+
+            let _ = self.events_tx.send(GardenEvent::PeerJoined { peer: peer_id.to_string() });
 
-    /// Disconnect from a peer
-    pub async fn disconnect_from_peer(&self, peer_id: &str) -> P2PResult<()> {
-        // p2panda-net manages connections automatically
-        // This is kept for API compatibility
-        if self.network.is_some() {
-            // Update connection tracking
-            let mut connections = self.connections.lock()
-                .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
-            
-            if let Some(conn) = connections.get_mut(peer_id) {
-                conn.is_active = false;
-            }
-            
             Ok(())
         } else {
             Err(P2PError::NetworkError("Network not initialized".to_string()))
         }
     }
 
-    /// Send a message on a topic
-    pub async fn send_message(&self, topic: &Topic, message: &[u8]) -> P2PResult<()> {
-        if let Some(network) = &self.network {
-            // Get the topic stream
-            let (tx, _rx, _ready) = network.subscribe(topic.clone())
-                .await
-                .map_err(|e| P2PError::NetworkError(format!("Failed to get topic stream: {}", e)))?;
-            
-            // Send the message wrapped in ToNetwork::Message
-            tx.send(ToNetwork::Message { bytes: message.to_vec() })
-                .await
-                .map_err(|e| P2PError::NetworkError(format!("Failed to send message: {}", e)))?;
-                
-            Ok(())
-        } else {
-            Err(P2PError::NetworkError("Network not initialized".to_string()))
+    /// Adjust `peer_id`'s reputation score in response to observed
+    /// behavior. A score that drops to or below `BAN_SCORE_THRESHOLD` bans
+    /// the peer for `DEFAULT_BAN_DURATION`; `connect_to_peer` refuses to
+    /// (re)connect to a banned peer until the ban expires. Scores recover
+    /// gradually toward neutral via `process_events` (see
+    /// `recover_peer_scores`), so a peer isn't punished forever for a past
+    /// burst of bad behavior.
+    pub fn report_peer(&self, peer_id: &str, action: PeerAction) -> P2PResult<()> {
+        let mut connections = self.connections.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
+
+        let conn = connections.entry(peer_id.to_string()).or_insert_with(|| PeerConnection {
+            peer_id: peer_id.to_string(),
+            topics: Vec::new(),
+            last_seen: std::time::Instant::now(),
+            is_active: false,
+            score: 0,
+            banned_until: None,
+        });
+
+        conn.score = (conn.score + action.score_delta()).clamp(MIN_SCORE, MAX_SCORE);
+
+        if conn.score <= BAN_SCORE_THRESHOLD {
+            conn.banned_until = Some(std::time::Instant::now() + DEFAULT_BAN_DURATION);
         }
+
+        Ok(())
     }
 
-    /// Process P2P events
+    /// `peer_id`'s current reputation score, or `0` if it's never been seen.
+    pub fn peer_score(&self, peer_id: &str) -> P2PResult<i32> {
+        let connections = self.connections.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
+        Ok(connections.get(peer_id).map(|c| c.score).unwrap_or(0))
+    }
+
+    /// Whether `peer_id` is currently serving out a ban imposed by `report_peer`.
+    pub fn is_peer_banned(&self, peer_id: &str) -> P2PResult<bool> {
+        let connections = self.connections.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
+        Ok(connections.get(peer_id)
+            .and_then(|c| c.banned_until)
+            .is_some_and(|banned_until| std::time::Instant::now() < banned_until))
+    }
+
+    /// Snapshot `connections` to the configured `GardenStore`, bounded to
+    /// the `MAX_PERSISTED_PEERS` most-recently-seen peers and pruned of
+    /// anything older than `PEER_RECORD_TTL`. Called on `shutdown()` and
+    /// periodically from `process_events`, so a restart can redial
+    /// recently-seen peers before discovery completes instead of starting
+    /// from nothing. `Instant` has no stable epoch, so the wall-clock
+    /// timestamp is reconstructed from how long ago `last_seen` was
+    /// relative to now.
+    fn persist_connections(&self) -> P2PResult<()> {
+        let connections = self.connections.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
+
+        let now_instant = std::time::Instant::now();
+        let now_unix_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut records: Vec<PeerRecord> = connections.values()
+            .filter(|conn| now_instant.saturating_duration_since(conn.last_seen) < PEER_RECORD_TTL)
+            .map(|conn| {
+                let age_secs = now_instant.saturating_duration_since(conn.last_seen).as_secs();
+                PeerRecord {
+                    peer_id: conn.peer_id.clone(),
+                    last_seen_unix_secs: now_unix_secs.saturating_sub(age_secs),
+                    topics: conn.topics.clone(),
+                }
+            })
+            .collect();
+        drop(connections);
+
+        records.sort_by(|a, b| b.last_seen_unix_secs.cmp(&a.last_seen_unix_secs));
+        records.truncate(MAX_PERSISTED_PEERS);
+
+        self.store.save_peers(&records).map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Re-dial every peer persisted by a previous `persist_connections`
+    /// call that's still within `PEER_RECORD_TTL`, giving a freshly started
+    /// client a head start on its peer set before mDNS/bootstrap discovery
+    /// completes. A peer that fails to redial (e.g. it's since been
+    /// banned) is skipped rather than failing startup.
+    async fn redial_persisted_peers(&self) -> P2PResult<()> {
+        let records = self.store.peers().map_err(|e| P2PError::StorageError(e.to_string()))?;
+        let now_unix_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        for record in records {
+            let age_secs = now_unix_secs.saturating_sub(record.last_seen_unix_secs);
+            if age_secs >= PEER_RECORD_TTL.as_secs() {
+                continue;
+            }
+            let _ = self.connect_to_peer(&record.peer_id).await;
+        }
+
+        Ok(())
+    }
+
+    /// Nudge every peer's score a step back toward neutral, and lift bans
+    /// whose timeout has elapsed. Run on every `process_events` call rather
+    /// than from a separate background task, matching this client's
+    /// existing pump-driven maintenance style.
+    fn recover_peer_scores(&self) -> P2PResult<()> {
+        let mut connections = self.connections.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
+
+        let now = std::time::Instant::now();
+        for conn in connections.values_mut() {
+            if let Some(banned_until) = conn.banned_until {
+                if now >= banned_until {
+                    conn.banned_until = None;
+                }
+            }
+            match conn.score.cmp(&0) {
+                std::cmp::Ordering::Less => conn.score += 1,
+                std::cmp::Ordering::Greater => conn.score -= 1,
+                std::cmp::Ordering::Equal => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disconnect from a peer
+    pub async fn disconnect_from_peer(&self, peer_id: &str) -> P2PResult<()> {
+        // p2panda-net manages connections automatically
+        // This is kept for API compatibility
+        if self.network.is_some() {
+            // Update connection tracking
+            let mut connections = self.connections.lock()
+                .map_err(|_| P2PError::NetworkError("Failed to lock connections".to_string()))?;
+            
+            if let Some(conn) = connections.get_mut(peer_id) {
+                conn.is_active = false;
+            }
+            drop(connections);
+
+            let _ = self.events_tx.send(GardenEvent::PeerLeft { peer: peer_id.to_string() });
+
+            Ok(())
+        } else {
+            Err(P2PError::NetworkError("Network not initialized".to_string()))
+        }
+    }
+
+    /// Send a message on a topic
+    pub async fn send_message(&self, topic: &Topic, message: &[u8]) -> P2PResult<()> {
+        if let Some(network) = &self.network {
+            // Get the topic stream
+            let (tx, _rx, _ready) = network.subscribe(topic.clone())
+                .await
+                .map_err(|e| P2PError::NetworkError(format!("Failed to get topic stream: {}", e)))?;
+
+            // Hold the flush for `network_load`'s batching window - at low
+            // load levels this gives other sends queued around the same
+            // time a chance to go out as part of the same burst instead of
+            // each paying its own wakeup; at the top load level the window
+            // is zero and this is a no-op.
+            if self.load_profile.batch_interval > Duration::ZERO {
+                time::sleep(self.load_profile.batch_interval).await;
+            }
+
+            // Send the message wrapped in ToNetwork::Message
+            tx.send(ToNetwork::Message { bytes: message.to_vec() })
+                .await
+                .map_err(|e| P2PError::NetworkError(format!("Failed to send message: {}", e)))?;
+
+            // Covers `send_encrypted_group_message` too, since it sends
+            // through this same method rather than the raw network sender.
+            bandwidth_counter_for(&self.topic_bandwidth, topic)?
+                .record_outbound(message.len() as u64);
+
+            Ok(())
+        } else {
+            Err(P2PError::NetworkError("Network not initialized".to_string()))
+        }
+    }
+
+    /// Poll the underlying network layer once for a pending event and, if
+    /// one arrived, forward it to `events_stream()` as `GardenEvent::Other`
+    /// (see `subscribe`'s per-topic ingest task for the typed
+    /// `MessageReceived`/`GossipReady` events instead).
     pub async fn process_events(&self) -> P2PResult<()> {
         if let Some(network) = &self.network {
             // Get network events
@@ -334,14 +1334,17 @@ impl GardenClient {
             tokio::select! {
                 event = event_future => {
                     if let Ok(event) = event {
-                        println!("Network event: {:?}", event);
+                        let _ = self.events_tx.send(GardenEvent::Other(format!("{:?}", event)));
                     }
                 }
                 _ = timeout => {
                     // Timeout, no events available
                 }
             }
-            
+
+            self.recover_peer_scores()?;
+            self.persist_connections()?;
+
             Ok(())
         } else {
             Err(P2PError::NetworkError("Network not initialized".to_string()))
@@ -360,21 +1363,472 @@ impl GardenClient {
         Topic::new(&topic_name)
     }
 
+    /// The dedicated topic a SAS verification exchange with `peer_user_id`
+    /// runs on.
+    pub fn create_verification_topic(&self, peer_user_id: &str) -> Topic {
+        let topic_name = format!("garden/verify/{}", peer_user_id);
+        Topic::new(&topic_name)
+    }
+
+    /// Begin an interactive SAS verification of `peer_user_id`'s long-term
+    /// key. Generates our ephemeral keypair and commits to it before
+    /// anything from the peer is known, so we can't bias the exchange
+    /// after seeing their key. Send `handle.our_offer()` to the peer over
+    /// `create_verification_topic` (via `send_verification_offer`); once
+    /// their offer comes back, pass it to `handle.accept` to derive the SAS.
+    pub fn start_verification(
+        &self,
+        peer_user_id: &str,
+        local_long_term_key: Vec<u8>,
+        peer_long_term_key: Vec<u8>,
+    ) -> VerificationHandle {
+        let (session, ephemeral_public, transaction_id, commitment) =
+            VerificationSession::begin_verification(local_long_term_key, peer_long_term_key);
+
+        let offer = VerificationOffer {
+            transaction_id,
+            ephemeral_public: *ephemeral_public.as_bytes(),
+            commitment,
+        };
+
+        VerificationHandle::new(peer_user_id.to_string(), session, offer)
+    }
+
+    /// Respond to a peer's `VerificationOffer`: begins our own session and
+    /// immediately consumes theirs, deriving the SAS in one step. Send the
+    /// returned handle's `our_offer()` back to the peer so they can derive
+    /// the same SAS on their end via `handle.accept`.
+    pub fn accept_verification(
+        &self,
+        peer_user_id: &str,
+        local_long_term_key: Vec<u8>,
+        peer_long_term_key: Vec<u8>,
+        peer_offer: VerificationOffer,
+    ) -> P2PResult<VerificationHandle> {
+        let mut handle = self.start_verification(peer_user_id, local_long_term_key, peer_long_term_key);
+        handle.accept(peer_offer)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))?;
+        Ok(handle)
+    }
+
+    /// Send a `VerificationOffer` to `peer_user_id` over the dedicated
+    /// verification topic.
+    pub async fn send_verification_offer(&self, peer_user_id: &str, offer: &VerificationOffer) -> P2PResult<()> {
+        let bytes = serde_json::to_vec(offer)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))?;
+        let topic = self.create_verification_topic(peer_user_id);
+        self.send_message(&topic, &bytes).await
+    }
+
+    /// Send our MAC (from `VerificationHandle::confirm`) to `peer_user_id`
+    /// over the verification topic, once the user has confirmed the SAS
+    /// matches out of band.
+    pub async fn send_verification_mac(&self, peer_user_id: &str, mac: &VerificationMac) -> P2PResult<()> {
+        let bytes = serde_json::to_vec(mac)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))?;
+        let topic = self.create_verification_topic(peer_user_id);
+        self.send_message(&topic, &bytes).await
+    }
+
+    /// Tell `peer_user_id` we've backed out of a verification in progress -
+    /// see `VerificationHandle::cancel`.
+    pub async fn send_verification_cancel(&self, peer_user_id: &str, cancel: &VerificationCancel) -> P2PResult<()> {
+        let bytes = serde_json::to_vec(cancel)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))?;
+        let topic = self.create_verification_topic(peer_user_id);
+        self.send_message(&topic, &bytes).await
+    }
+
+    /// The topic `user_id`'s issued-token revocations are broadcast on, so
+    /// every peer holding a cached copy of a revoked token can drop it
+    /// instead of trusting it until it expires on its own.
+    pub fn create_token_revocation_topic(&self, user_id: &str) -> Topic {
+        let topic_name = format!("garden/revocations/{}", user_id);
+        Topic::new(&topic_name)
+    }
+
+    /// Record `token` as issued, so it can later be looked up or revoked by
+    /// `token.id`. See `crate::token_store`.
+    pub fn record_issued_token(&self, token: &AuthToken) -> P2PResult<()> {
+        self.token_store.insert(token).map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Check a token against this client's local revocation records - see
+    /// `AuthToken::verify_with_store`.
+    pub fn is_token_revoked(&self, token_id: &str) -> P2PResult<bool> {
+        self.token_store.is_revoked(token_id).map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Revoke `token_id` locally and broadcast a `TokenRevocationNotice`
+    /// over `create_token_revocation_topic` so other peers drop any cached
+    /// copy of it before it would otherwise expire.
+    pub async fn revoke_token(&self, user_id: &str, token_id: &str) -> P2PResult<()> {
+        self.token_store.revoke(token_id).map_err(|e| P2PError::StorageError(e.to_string()))?;
+
+        let notice = TokenRevocationNotice { token_id: token_id.to_string() };
+        let bytes = serde_json::to_vec(&notice).map_err(|e| P2PError::StorageError(e.to_string()))?;
+        let topic = self.create_token_revocation_topic(user_id);
+        self.send_message(&topic, &bytes).await
+    }
+
+    /// Apply a `TokenRevocationNotice` received on `create_token_revocation_topic`
+    /// (e.g. from another of the user's devices, or the original issuer) to
+    /// this client's own token store.
+    pub fn apply_token_revocation_notice(&self, notice: &TokenRevocationNotice) -> P2PResult<()> {
+        self.token_store.revoke(&notice.token_id).map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Record a device that has passed mutual SAS confirmation, independent
+    /// of any `Capability` it may separately hold.
+    pub fn record_verified_device(&self, device: crate::verification::VerifiedDevice) -> P2PResult<()> {
+        self.trust_store.record(device)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))
+    }
+
+    /// Whether `public_key` has passed SAS verification with this client.
+    pub fn is_device_verified(&self, public_key: &[u8]) -> P2PResult<bool> {
+        self.trust_store.is_verified(public_key)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))
+    }
+
+    /// Build the key-gossip header to attach to an outgoing message - our
+    /// user_id, device public key, and whether we'd prefer this
+    /// conversation move to an encrypted group. `None` if this client has
+    /// no identity configured, i.e. nothing to gossip.
+    pub fn message_header(&self, prefers_encryption: bool) -> Option<MessageHeader> {
+        let identity = self.config.user_identity.as_ref()?;
+        Some(MessageHeader {
+            user_id: identity.user_id.clone(),
+            device_public_key: identity.public_key.clone(),
+            prefers_encryption,
+        })
+    }
+
+    /// As `send_message`, but wraps `message` in a `GossipEnvelope` carrying
+    /// our `message_header` (see `crate::key_gossip`) so the recipient can
+    /// learn our key as a side effect of receiving it. Falls back to a
+    /// plain `send_message` if this client has no identity to gossip.
+    pub async fn send_message_with_header(
+        &self,
+        topic: &Topic,
+        message: &[u8],
+        prefers_encryption: bool,
+    ) -> P2PResult<()> {
+        let Some(header) = self.message_header(prefers_encryption) else {
+            return self.send_message(topic, message).await;
+        };
+        let envelope = GossipEnvelope { header, payload: message.to_vec() };
+        let bytes = serde_json::to_vec(&envelope)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?;
+        self.send_message(topic, &bytes).await
+    }
+
+    /// Unwrap a `GossipEnvelope` received from `send_message_with_header`,
+    /// feeding its header into the peer-key cache (last-seen-wins, but
+    /// never downgrading an already-verified key) and returning the
+    /// original payload beneath it.
+    pub fn receive_message_with_header(&self, bytes: &[u8]) -> P2PResult<Vec<u8>> {
+        let envelope: GossipEnvelope = serde_json::from_slice(bytes)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?;
+        self.apply_message_header(&envelope.header)?;
+        Ok(envelope.payload)
+    }
+
+    /// Feed a `MessageHeader` into this client's peer-key cache directly,
+    /// for a caller that already parsed one out of a transport it manages
+    /// itself rather than going through `receive_message_with_header`.
+    pub fn apply_message_header(&self, header: &MessageHeader) -> P2PResult<()> {
+        self.peer_keys.observe(&header.user_id, header.device_public_key.clone())
+            .map_err(|e| P2PError::VerificationError(e.to_string()))
+    }
+
+    /// The verifying key last seen (or verified) for `user_id`, gossiped via
+    /// a message header or promoted via `promote_peer_key` - `None` if
+    /// we've never heard from them. Lets an incoming `AuthToken` be
+    /// verified without already knowing the sender's key out of band.
+    pub fn peer_key(&self, user_id: &str) -> P2PResult<Option<VerifyingKey>> {
+        self.peer_keys.get(user_id)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))
+    }
+
+    /// Whether `user_id`'s cached key has been promoted via `promote_peer_key`,
+    /// as opposed to merely observed from a gossiped header.
+    pub fn is_peer_key_verified(&self, user_id: &str) -> P2PResult<bool> {
+        self.peer_keys.is_verified(user_id)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))
+    }
+
+    /// Promote `user_id`'s cached key to verified - call this alongside
+    /// `record_verified_device`/`AuthToken::verify_cross_signed` once
+    /// `public_key` has actually been established as theirs, so a later
+    /// gossiped header for a different key can't silently replace it.
+    pub fn promote_peer_key(&self, user_id: &str, public_key: Vec<u8>) -> P2PResult<()> {
+        self.peer_keys.mark_verified(user_id, public_key)
+            .map_err(|e| P2PError::VerificationError(e.to_string()))
+    }
+
+    /// Start a handshake with a peer, advertising every protocol
+    /// version/compression codec/ciphersuite this build supports. Send the
+    /// returned `Hello` to the peer and pass their reply to
+    /// `complete_handshake` (see `crate::handshake`).
+    pub fn begin_handshake(&self) -> (HandshakeSession, Hello) {
+        HandshakeSession::begin()
+    }
+
+    /// Finish a handshake with `peer_id` using their `Hello`, negotiating
+    /// the highest mutually-supported version plus a codec and cipher (see
+    /// `negotiate`) and deriving a session key. The resulting session is recorded under `peer_id` so
+    /// `create_resume_token`/`resume_session` can refer back to it later.
+    pub fn complete_handshake(
+        &self,
+        peer_id: &str,
+        session: HandshakeSession,
+        remote_hello: &Hello,
+    ) -> P2PResult<NegotiatedSession> {
+        let negotiated = session.complete(remote_hello)
+            .map_err(|e| P2PError::HandshakeError(e.to_string()))?;
+
+        let mut sessions = self.sessions.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock sessions".to_string()))?;
+        sessions.insert(peer_id.to_string(), negotiated.clone());
+
+        Ok(negotiated)
+    }
+
+    /// The negotiated session on record for `peer_id`, if any.
+    pub fn negotiated_session(&self, peer_id: &str) -> P2PResult<Option<NegotiatedSession>> {
+        let sessions = self.sessions.lock()
+            .map_err(|_| P2PError::NetworkError("Failed to lock sessions".to_string()))?;
+        Ok(sessions.get(peer_id).cloned())
+    }
+
+    /// Sign a `ResumeToken` for the session on record with `peer_id`, to
+    /// present on reconnect instead of redoing the handshake. Requires this
+    /// client to have a configured identity and an existing session with
+    /// that peer.
+    pub fn create_resume_token(&self, peer_id: &str, signing_key: &SigningKey) -> P2PResult<ResumeToken> {
+        let identity = self.config.user_identity.as_ref()
+            .ok_or_else(|| P2PError::IdentityError("no identity configured".to_string()))?;
+        let session = self.negotiated_session(peer_id)?
+            .ok_or_else(|| P2PError::HandshakeError(format!("no session on record for {}", peer_id)))?;
+
+        Ok(ResumeToken::create(&session.session_id, &identity.user_id, signing_key))
+    }
+
+    /// Reinstate the session `token` refers to without a full
+    /// re-handshake: verify it, check it's for the session already on
+    /// record with `peer_id`, reconnect, and resubscribe to every topic
+    /// this client had subscribed to before the disconnect (see
+    /// `GardenService::subscribed_topics`). Any in-flight `AuthToken`
+    /// context is unaffected, since it's tracked independently in
+    /// `crate::token_store` rather than torn down on disconnect.
+    pub async fn resume_session(
+        &self,
+        peer_id: &str,
+        token: &ResumeToken,
+        verifying_key: &VerifyingKey,
+    ) -> P2PResult<Vec<Topic>> {
+        if !token.verify(verifying_key) {
+            return Err(P2PError::HandshakeError("resume token signature does not verify".to_string()));
+        }
+        let session = self.negotiated_session(peer_id)?
+            .ok_or_else(|| P2PError::HandshakeError(format!("no session on record for {}", peer_id)))?;
+        if session.session_id != token.session_id {
+            return Err(P2PError::HandshakeError(
+                "resume token references a different session than the one on record".to_string(),
+            ));
+        }
+
+        self.connect_to_peer(peer_id).await?;
+
+        let topics = self.service.subscribed_topics()
+            .map_err(|e| P2PError::NetworkError(e.to_string()))?;
+        for topic in &topics {
+            self.subscribe(topic.clone()).await?;
+        }
+
+        Ok(topics)
+    }
+
+    /// As `resume_session`, but retries with exponential backoff
+    /// (`base`..`max`, doubling each attempt) instead of failing on the
+    /// first transient disconnect - for a link flaky enough that one retry
+    /// isn't enough to rejoin.
+    pub async fn reconnect_with_backoff(
+        &self,
+        peer_id: &str,
+        token: &ResumeToken,
+        verifying_key: &VerifyingKey,
+        max_attempts: u32,
+        base: Duration,
+        max: Duration,
+    ) -> P2PResult<Vec<Topic>> {
+        let mut backoff = ReconnectBackoff::new(base, max);
+        let mut last_err = P2PError::HandshakeError("reconnect_with_backoff called with max_attempts == 0".to_string());
+
+        for _ in 0..max_attempts {
+            match self.resume_session(peer_id, token, verifying_key).await {
+                Ok(topics) => return Ok(topics),
+                Err(e) => {
+                    last_err = e;
+                    time::sleep(backoff.next_delay()).await;
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+
     /// Register a new subspace
     pub fn register_subspace(&self, name: &str, subspace: SubspaceId) -> P2PResult<()> {
-        let mut subspaces = self.user_subspaces.lock()
-            .map_err(|_| P2PError::StorageError("Failed to lock subspaces".to_string()))?;
-        
-        subspaces.insert(name.to_string(), subspace);
-        Ok(())
+        self.store.save_subspace(name, &subspace)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?;
+
+        self.service.register_subspace(name, subspace)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
     }
 
     /// Get a subspace by name
     pub fn get_subspace(&self, name: &str) -> P2PResult<Option<SubspaceId>> {
-        let subspaces = self.user_subspaces.lock()
-            .map_err(|_| P2PError::StorageError("Failed to lock subspaces".to_string()))?;
-        
-        Ok(subspaces.get(name).cloned())
+        self.service.get_subspace(name)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Join a group's membership roster (tracked independently of any
+    /// encrypted-group session state).
+    pub fn join_group(&self, group_id: &str, user_id: &str) -> P2PResult<()> {
+        self.service.join_group(group_id, user_id)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Leave a group's membership roster.
+    pub fn leave_group(&self, group_id: &str, user_id: &str) -> P2PResult<()> {
+        self.service.leave_group(group_id, user_id)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// List a group's current members.
+    pub fn group_members(&self, group_id: &str) -> P2PResult<Vec<String>> {
+        self.service.group_members(group_id)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    fn register_handler(&self, kind: Option<EntryKind>, topic: Option<Topic>, callback: EntryHandler) -> P2PResult<HandlerId> {
+        let id = self.next_handler_id.fetch_add(1, Ordering::SeqCst);
+        let mut handlers = self.handlers.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock handlers".to_string()))?;
+        handlers.push(RegisteredHandler { id, kind, topic, callback });
+        Ok(id)
+    }
+
+    /// Register a handler that runs for every incoming entry regardless of
+    /// kind or topic. Returns an id `remove_handler` can later use to
+    /// unregister it.
+    pub fn on<F>(&self, handler: F) -> P2PResult<HandlerId>
+    where
+        F: Fn(&GardenEntry) + Send + Sync + 'static,
+    {
+        self.register_handler(None, None, Box::new(handler))
+    }
+
+    /// Register a handler scoped to one `GardenEntry` variant, across all topics.
+    pub fn set_entry_handler<F>(&self, kind: EntryKind, handler: F) -> P2PResult<HandlerId>
+    where
+        F: Fn(&GardenEntry) + Send + Sync + 'static,
+    {
+        self.register_handler(Some(kind), None, Box::new(handler))
+    }
+
+    /// Register a handler scoped to one topic, across all entry kinds.
+    pub fn on_topic<F>(&self, topic: Topic, handler: F) -> P2PResult<HandlerId>
+    where
+        F: Fn(&GardenEntry) + Send + Sync + 'static,
+    {
+        self.register_handler(None, Some(topic), Box::new(handler))
+    }
+
+    /// Unregister a previously registered handler. Returns `false` if no
+    /// handler with that id was found (e.g. already removed).
+    pub fn remove_handler(&self, id: HandlerId) -> P2PResult<bool> {
+        let mut handlers = self.handlers.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock handlers".to_string()))?;
+        let before = handlers.len();
+        handlers.retain(|h| h.id != id);
+        Ok(handlers.len() != before)
+    }
+
+    /// Demultiplex `entry`, received on `topic`, to every handler whose kind
+    /// and topic filters match. Each handler runs behind `catch_unwind` so a
+    /// panicking handler can't stop the others from running.
+    pub fn dispatch_entry(&self, topic: &Topic, entry: &GardenEntry) -> P2PResult<()> {
+        dispatch_entry_to(&self.history, self.store.as_ref(), &self.handlers, &self.clock, topic, entry)
+    }
+
+    /// Backfill entries for `topic` matching `selector`, IRC CHATHISTORY
+    /// style - lets a client that subscribed late, or was offline, catch up
+    /// on what it missed. Enforces `auth`'s `Capability::ReadMessages` on
+    /// the topic (verifying the full delegation chain, not just trusting
+    /// `auth.capabilities`, if `auth` claims one - see `verify_capability`),
+    /// that `auth`'s device hasn't been revoked, and that `auth.id` itself
+    /// hasn't been revoked via `revoke_token` (see `is_token_revoked`)
+    /// before returning anything - and, for a group topic, also drops any
+    /// `GroupMessage` entry `auth` wasn't a member for at the epoch it was
+    /// sealed under (see `can_access_group_entry`).
+    ///
+    /// Only serves what this client has itself already observed (see
+    /// `crate::history`) - it does not yet reach out to connected peers for
+    /// ranges this client never saw.
+    pub fn query_history(
+        &self,
+        topic: &Topic,
+        selector: HistorySelector,
+        limit: usize,
+        auth: &AuthToken,
+    ) -> P2PResult<HistoryResult> {
+        // A capability granted on another of the user's devices may not
+        // have made it into this `auth` token yet, so also consult the
+        // replicated ledger (see `crate::capability_ledger`) before
+        // rejecting - either source proving the capability is sufficient.
+        let required = Capability::ReadMessages(topic.name().to_string());
+        let authorized_by_ledger = self.service.has_ledger_capability(&auth.user_id, &required)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?;
+        if !self.verify_capability(auth, &required)? && !authorized_by_ledger {
+            return Ok(HistoryResult::Unauthorized);
+        }
+
+        if self.service.is_device_revoked(&auth.user_id, &auth.device_id)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?
+        {
+            return Ok(HistoryResult::Unauthorized);
+        }
+
+        if self.is_token_revoked(&auth.id)? {
+            return Ok(HistoryResult::Unauthorized);
+        }
+
+        let entries = self.history.query(topic, &selector, limit)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?;
+
+        let entries = match group_id_from_topic(topic) {
+            Some(group_id) => entries.into_iter()
+                .map(|entry| match &entry {
+                    GardenEntry::GroupMessage { epoch, .. } => {
+                        Ok((self.can_access_group_entry(group_id, *epoch, auth)?, entry))
+                    }
+                    _ => Ok((true, entry)),
+                })
+                .collect::<P2PResult<Vec<_>>>()?
+                .into_iter()
+                .filter_map(|(authorized, entry)| authorized.then_some(entry))
+                .collect(),
+            None => entries,
+        };
+
+        Ok(if entries.is_empty() {
+            HistoryResult::Empty
+        } else {
+            HistoryResult::Messages(entries)
+        })
     }
 
     /// Clean up resources
@@ -387,6 +1841,9 @@ impl GardenClient {
         for (_, conn) in connections.iter_mut() {
             conn.is_active = false;
         }
+        drop(connections);
+
+        self.persist_connections()?;
 
         Ok(())
     }
@@ -397,124 +1854,542 @@ impl GardenClient {
         Ok(())
     }
 
-    /// Create an encrypted group - using mock implementation
+    /// Create an encrypted group, running a real Feldman-VSS DKG round (see
+    /// `crate::group_crypto`) to derive its aggregate public key instead of
+    /// the old mock's random secret. The group starts as a single-dealer,
+    /// threshold-1 instance of the general scheme: the creator is for now
+    /// the sole participant, so this step alone doesn't yet deliver the
+    /// "no single peer holds the full secret" property a multi-dealer
+    /// group gets - see `join_encrypted_group` for why.
     pub async fn create_encrypted_group(&self, group_id: &str) -> P2PResult<Vec<u8>> {
+        let creator_id = format!("creator-{}", self.private_key.clone());
+
         // Create a mock group
         let group = MockGroup {
             id: group_id.to_string(),
-            members: vec![format!("creator-{}", self.private_key.clone())],
+            members: vec![creator_id.clone()],
         };
-        
+
         // Store the group
         let mut groups = self.groups.lock()
             .map_err(|_| P2PError::StorageError("Failed to lock groups".to_string()))?;
         groups.insert(group_id.to_string(), group.clone());
-        
-        // Create a session for this group
-        let session = MockGroupSession {
-            group_id: group_id.to_string(),
-        };
-        
+        drop(groups);
+
+        let session = new_group_session(group_id, 0, &[1], 1, 1);
+        let key_package = GroupKeyPackage::from_session(&session);
+
         // Store the session
         let mut sessions = self.group_sessions.lock()
             .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
         sessions.insert(group_id.to_string(), session);
-        
-        // Return a mock key package (serialized group ID for now)
-        Ok(group_id.as_bytes().to_vec())
+        drop(sessions);
+
+        // Track membership in the group registry too, independent of the
+        // mock session state above.
+        self.join_group(group_id, &creator_id)?;
+        self.persist_group_session(group_id)?;
+
+        key_package.to_bytes()
     }
 
-    /// Join an encrypted group - using mock implementation
+    /// Join an encrypted group via its `key_package`. Because this mock
+    /// network has no live transport for a dealer to issue each joiner a
+    /// fresh, individually addressed share, `key_package` necessarily
+    /// carries the whole group's secret share (not just its public key) -
+    /// a real multi-peer deployment would instead have current members run
+    /// a resharing round over `create_direct_message_topic` and send the
+    /// new participant only their own share. What *is* real here: the
+    /// package's share is rejected via `GroupCryptoError::ShareVerificationFailed`
+    /// if it doesn't verify against its own Feldman commitments.
     pub async fn join_encrypted_group(&self, group_id: &str, key_package: Vec<u8>) -> P2PResult<()> {
-        // Verify the key package (should contain the group ID)
-        let pkg_group_id = String::from_utf8(key_package.clone())
-            .map_err(|_| P2PError::GroupError("Invalid key package".to_string()))?;
-        
-        if pkg_group_id != group_id {
-            return Err(P2PError::GroupError("Key package doesn't match group ID".to_string()));
-        }
-        
+        let package = GroupKeyPackage::from_bytes(&key_package)?;
+        let session = package.into_session(group_id, 2)?;
+
+        let member_id = format!("member-{}", self.private_key.clone());
+
         // Create a mock group for this member
         let group = MockGroup {
             id: group_id.to_string(),
-            members: vec![format!("member-{}", self.private_key.clone())],
+            members: vec![member_id.clone()],
         };
-        
+
         // Store the group
         let mut groups = self.groups.lock()
             .map_err(|_| P2PError::StorageError("Failed to lock groups".to_string()))?;
         groups.insert(group_id.to_string(), group);
-        
-        // Create a session for this group
-        let session = MockGroupSession {
-            group_id: group_id.to_string(),
-        };
-        
+        drop(groups);
+
         // Store the session
         let mut sessions = self.group_sessions.lock()
             .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
         sessions.insert(group_id.to_string(), session);
-        
+        drop(sessions);
+
+        // Track membership in the group registry too, independent of the
+        // mock session state above.
+        self.join_group(group_id, &member_id)?;
+        self.persist_group_session(group_id)?;
+
         Ok(())
     }
 
-    /// Send an encrypted group message - using mock implementation
+    /// Encrypt and send a group message against the group's aggregate DKG
+    /// public key (see `crate::group_crypto`).
     pub async fn send_encrypted_group_message(&self, group_id: &str, content: &[u8]) -> P2PResult<()> {
         // Get the group session
         let sessions = self.group_sessions.lock()
             .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
-        
-        // Just check if the session exists
-        if !sessions.contains_key(group_id) {
-            return Err(P2PError::GroupError("Group session not found".to_string()));
-        }
-        
-        // Mock encryption - prepend "ENCRYPTED:" to the message
+
+        let session = sessions.get(group_id)
+            .ok_or_else(|| P2PError::GroupError("Group session not found".to_string()))?;
+
+        let ciphertext = group_crypto::encrypt(&session.group_public_key, content);
+        let epoch = session.epoch;
+        drop(sessions);
+
+        let ciphertext_bytes = serde_json::to_vec(&ciphertext)
+            .map_err(|e| P2PError::GroupError(format!("failed to serialize ciphertext: {}", e)))?;
+
         let mut encrypted = b"ENCRYPTED:".to_vec();
-        encrypted.extend_from_slice(content);
-        
+        encrypted.extend_from_slice(&epoch.to_be_bytes());
+        encrypted.extend_from_slice(&ciphertext_bytes);
+
         // Send the encrypted message to the group topic
         let topic = self.create_group_message_topic(group_id);
         self.send_message(&topic, &encrypted).await?;
-        
+
         Ok(())
     }
 
-    /// Receive and decrypt a group message - using mock implementation
+    /// Receive a group message, decrypting it by combining this session's
+    /// share's partial decryption with whatever others the group's
+    /// threshold requires (just this one, for the threshold-1 groups this
+    /// client currently supports - see `join_encrypted_group`).
     pub async fn receive_encrypted_group_message(&self, group_id: &str, encrypted: &[u8]) -> P2PResult<Vec<u8>> {
         // Get the group session
         let sessions = self.group_sessions.lock()
             .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
-        
-        // Just check if the session exists
-        if !sessions.contains_key(group_id) {
-            return Err(P2PError::GroupError("Group session not found".to_string()));
-        }
-        
-        // Mock decryption - remove "ENCRYPTED:" prefix
-        if encrypted.len() < 10 || &encrypted[0..10] != b"ENCRYPTED:" {
+
+        let session = sessions.get(group_id)
+            .ok_or_else(|| P2PError::GroupError("Group session not found".to_string()))?;
+
+        if encrypted.len() < 18 || &encrypted[0..10] != b"ENCRYPTED:" {
             return Err(P2PError::GroupError("Invalid encrypted message format".to_string()));
         }
-        
-        Ok(encrypted[10..].to_vec())
+
+        let mut epoch_bytes = [0u8; 8];
+        epoch_bytes.copy_from_slice(&encrypted[10..18]);
+        let msg_epoch = u64::from_be_bytes(epoch_bytes);
+
+        if msg_epoch < session.epoch {
+            return Err(P2PError::GroupError(format!(
+                "message is from epoch {} but the group has rekeyed past it to epoch {}",
+                msg_epoch, session.epoch
+            )));
+        }
+
+        let ciphertext: GroupCiphertext = serde_json::from_slice(&encrypted[18..])
+            .map_err(|e| P2PError::GroupError(format!("failed to parse ciphertext: {}", e)))?;
+
+        let partial = group_crypto::partial_decrypt(&ciphertext, &session.secret_share)
+            .map_err(|e| P2PError::GroupError(e.to_string()))?;
+        let partials = [(session.index, partial)];
+        let threshold = session.threshold;
+        drop(sessions);
+
+        group_crypto::combine_partial_decryptions(&ciphertext, &partials, threshold)
+            .map_err(|e| P2PError::GroupError(e.to_string()))
     }
-}
 
-// Helper function to create a garden client with a new keypair
-pub async fn create_garden_client(config: GardenConfig) -> P2PResult<GardenClient> {
-    // Generate a new private key for this client
-    let private_key = PrivateKey::new();
-    
-    // Create and initialize the client
-    let mut client = GardenClient::new(config, private_key).await?;
-    client.initialize().await?;
-    
-    Ok(client)
-}
+    /// Trust `key` as a root a delegated `AuthToken`'s chain may terminate
+    /// at - see `AuthToken::verify_chain`. Until at least one root is
+    /// registered, no delegated (i.e. `proof.is_some()`) token can verify,
+    /// since `verify_chain` rejects an empty `trusted_roots` list outright.
+    pub fn add_trusted_capability_root(&self, key: VerifyingKey) -> P2PResult<()> {
+        let mut roots = self.trusted_capability_roots.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock trusted capability roots".to_string()))?;
+        if !roots.contains(&key) {
+            roots.push(key);
+        }
+        Ok(())
+    }
 
-// Helper function to create standard garden topics for a user
-pub fn create_user_topics(user_id: &str) -> Vec<Topic> {
-    vec![
+    /// Record `list` as the current `DeviceList` for its `user_id`, consulted
+    /// by `enforce_step_up` on `devices/<user_id>/...` paths - see
+    /// `AuthToken::can_access_device_path`. Ignored if it doesn't supersede
+    /// whatever list (if any) is already recorded for that user, the same
+    /// stale-version rule `DeviceList::from_entry` applies.
+    pub fn record_device_list(&self, list: DeviceList) -> P2PResult<()> {
+        let mut device_lists = self.device_lists.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock device lists".to_string()))?;
+        let is_newer = match device_lists.get(&list.user_id) {
+            Some(current) => current.supersedes(list.version),
+            None => true,
+        };
+        if is_newer {
+            device_lists.insert(list.user_id.clone(), list);
+        }
+        Ok(())
+    }
+
+    /// Advance this client's causal clock for an event we're authoring right
+    /// now (a revocation, a capability grant, ...) and return its timestamp.
+    /// Unlike `HybridLogicalClock::now()`, which always starts a fresh clock
+    /// at counter zero, this draws on the one long-lived clock `dispatch_entry`
+    /// and the network ingest path keep merging remote timestamps into, so
+    /// two events this client authors in the same millisecond still get a
+    /// total order, and neither can be stamped earlier than an entry we've
+    /// already observed from a peer.
+    fn stamp_event(&self) -> P2PResult<Timestamp> {
+        let mut clock = self.clock.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock clock".to_string()))?;
+        Ok(clock.local_event())
+    }
+
+    /// Require that `auth` actually holds `required`. A token that carries a
+    /// delegation `proof` only gets credit for `required` if its whole chain
+    /// verifies back to a `trusted_capability_root` (see
+    /// `AuthToken::has_verified_capability`) - its own `capabilities` list
+    /// isn't trustworthy on its own once it claims to be delegated, since
+    /// anyone can fabricate one. A token with no `proof` is assumed to have
+    /// been authenticated some other way before it ever reached this client,
+    /// and is checked with plain `has_capability` as before.
+    fn verify_capability(&self, auth: &AuthToken, required: &Capability) -> P2PResult<bool> {
+        if auth.proof.is_none() {
+            return Ok(auth.has_capability(required));
+        }
+
+        let trusted_roots = self.trusted_capability_roots.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock trusted capability roots".to_string()))?;
+        let now = crate::clock::HybridLogicalClock::now();
+        Ok(auth.has_verified_capability(required, &trusted_roots, now).unwrap_or(false))
+    }
+
+    /// Require that `auth` holds `required` and, if `path` falls under a
+    /// `Capability::MfaRequired` prefix `auth` also carries, that it has
+    /// stepped up recently enough per this client's `mfa_policy` (see
+    /// `crate::auth::mfa`) - layered on top of the plain capability check
+    /// the same way `AuthToken::can_access_path_with_policy` layers on top
+    /// of `has_capability`.
+    fn enforce_step_up(&self, auth: &AuthToken, required: &Capability, path: &str) -> P2PResult<()> {
+        if !self.verify_capability(auth, required)? {
+            return Err(P2PError::Unauthorized(format!("missing capability for {}", path)));
+        }
+
+        let policy = self.mfa_policy.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock MFA policy".to_string()))?;
+        let now = crate::clock::HybridLogicalClock::now();
+
+        // If we have a recorded `DeviceList` for this user, require the
+        // token's device to still be an active member of it whenever `path`
+        // is a device-management path - see `AuthToken::can_access_device_path`.
+        // With no list registered for the user, only the MFA policy check
+        // applies; a missing list is not by itself a reason to deny.
+        let device_lists = self.device_lists.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock device lists".to_string()))?;
+        let accessible = match device_lists.get(&auth.user_id) {
+            Some(device_list) => auth.can_access_device_path(path, now, &policy, device_list),
+            None => auth.can_access_path_with_policy(path, now, &policy),
+        };
+        if !accessible {
+            return Err(P2PError::Unauthorized(format!("MFA step-up required for {}", path)));
+        }
+
+        Ok(())
+    }
+
+    /// Register `window_ms` as the MFA step-up freshness required for any
+    /// path under `prefix` - see `crate::auth::mfa::MfaPolicy::require_fresh`.
+    /// Lets a group owner, for example, demand a tighter window for
+    /// `groups/*/metadata` writes than for ordinary `MfaRequired` reads.
+    pub fn require_mfa_freshness(&self, prefix: &str, window_ms: u64) -> P2PResult<()> {
+        let mut policy = self.mfa_policy.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock MFA policy".to_string()))?;
+        policy.require_fresh(prefix, window_ms);
+        Ok(())
+    }
+
+    /// Remove `user_id` from `group_id` and advance the group to a new
+    /// epoch - a Welcome-style commit recipients use to drop the old
+    /// ratchet key, so the removed member can't decrypt traffic sent after
+    /// this point even if it's still delivered to them. `expected_epoch`
+    /// must match the group's current epoch: this is the commit's stale-epoch
+    /// check, rejecting a removal built against a view of the group that's
+    /// already been superseded by some other rekey. Requires `auth` to hold
+    /// `Capability::ManageGroup` for `group_id` and, if `auth` carries an
+    /// `MfaRequired` marker over `groups/<group_id>/metadata`, a fresh
+    /// enough step-up (see `enforce_step_up`).
+    pub async fn remove_group_member(
+        &self,
+        group_id: &str,
+        user_id: &str,
+        expected_epoch: u64,
+        auth: &AuthToken,
+    ) -> P2PResult<()> {
+        self.enforce_step_up(
+            auth,
+            &Capability::ManageGroup(group_id.to_string()),
+            &format!("groups/{}/metadata", group_id),
+        )?;
+
+        let current_epoch = self.reject_stale_commit(group_id, expected_epoch)?;
+
+        {
+            let mut groups = self.groups.lock()
+                .map_err(|_| P2PError::StorageError("Failed to lock groups".to_string()))?;
+            let group = groups.get_mut(group_id)
+                .ok_or_else(|| P2PError::GroupError("Group not found".to_string()))?;
+            group.members.retain(|m| m != user_id);
+        }
+
+        self.leave_group(group_id, user_id)?;
+        // The new epoch the removal takes effect at - `can_access_group_entry`
+        // uses this to keep authorizing the member's reads of everything sealed
+        // before it, while refusing everything sealed at or after it.
+        self.service.record_group_member_removal(group_id, user_id, current_epoch + 1)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?;
+
+        self.advance_group_epoch(group_id)
+    }
+
+    /// Record an immediate, expiry-independent revocation of `device_id`'s
+    /// tokens for `user_id` - e.g. right after a group owner evicts a
+    /// compromised device, so access is cut off without waiting for its
+    /// outstanding `AuthToken`s to time out. Takes effect on the very next
+    /// `query_history`/`can_access_group_entry` check. Requires `auth` to
+    /// hold `Capability::ManageGroup` for `user_id` and, if `auth` carries
+    /// an `MfaRequired` marker over `devices/<user_id>/<device_id>`, a
+    /// fresh enough step-up (see `enforce_step_up`).
+    pub fn revoke_device(&self, user_id: &str, device_id: &str, reason: &str, auth: &AuthToken) -> P2PResult<()> {
+        self.enforce_step_up(
+            auth,
+            &Capability::ManageGroup(user_id.to_string()),
+            &format!("devices/{}/{}", user_id, device_id),
+        )?;
+
+        let revoked_at = self.stamp_event()?;
+        self.service.record_revocation(user_id, device_id, revoked_at, reason)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Publish a capability grant for `subject`, stamped as issued by
+    /// `device_id` - see `crate::capability_ledger`. Call sites are
+    /// responsible for checking `auth.has_capability(&Capability::ManageDevice(..))`
+    /// (or equivalent) before granting on another user's behalf.
+    pub fn grant_capability(&self, subject: &str, capability: Capability, device_id: &str) -> P2PResult<()> {
+        let timestamp = self.stamp_event()?;
+        self.service.grant_capability(subject, capability, timestamp, device_id)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Publish a capability revoke for `subject` - see `grant_capability`.
+    pub fn revoke_capability(&self, subject: &str, capability: Capability, device_id: &str) -> P2PResult<()> {
+        let timestamp = self.stamp_event()?;
+        self.service.revoke_capability(subject, capability, timestamp, device_id)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// Rotate the group's key without changing membership - same
+    /// forward-secrecy mechanism as `remove_group_member`, used on a
+    /// schedule rather than in response to a departure. `expected_epoch`
+    /// is checked the same way as `remove_group_member`'s.
+    pub async fn rotate_group_key(&self, group_id: &str, expected_epoch: u64) -> P2PResult<()> {
+        self.reject_stale_commit(group_id, expected_epoch)?;
+        self.advance_group_epoch(group_id)
+    }
+
+    /// Shared stale-epoch guard for both commit-shaped operations above.
+    /// Returns the group's current epoch on success.
+    fn reject_stale_commit(&self, group_id: &str, expected_epoch: u64) -> P2PResult<u64> {
+        let current_epoch = self.group_epoch(group_id)?;
+        if expected_epoch != current_epoch {
+            return Err(P2PError::GroupError(format!(
+                "commit targets epoch {} but the group has already moved to epoch {}",
+                expected_epoch, current_epoch
+            )));
+        }
+        Ok(current_epoch)
+    }
+
+    /// Authorize a read of a `GroupMessage` entry sealed at `epoch` in
+    /// `group_id`: the token must hold the usual `Capability::ReadMessages`
+    /// for the group's topic (`query_history`'s existing gate for every
+    /// other entry kind, chain-verified the same way - see
+    /// `verify_capability`), its device and the token itself (`auth.id`) must
+    /// not have been revoked, *and* its `user_id` must still be a member of
+    /// the group as of that epoch. A capability a removed member happened
+    /// to still be holding doesn't survive being dropped from the group's
+    /// roster - this carries the epoch rekey's post-compromise-security
+    /// guarantee into the authorization layer, not just the key material.
+    pub fn can_access_group_entry(&self, group_id: &str, epoch: u64, auth: &AuthToken) -> P2PResult<bool> {
+        let topic = self.create_group_message_topic(group_id);
+        if !self.verify_capability(auth, &Capability::ReadMessages(topic.name().to_string()))? {
+            return Ok(false);
+        }
+
+        if self.service.is_device_revoked(&auth.user_id, &auth.device_id)
+            .map_err(|e| P2PError::StorageError(e.to_string()))?
+        {
+            return Ok(false);
+        }
+
+        if self.is_token_revoked(&auth.id)? {
+            return Ok(false);
+        }
+
+        self.service.was_group_member_at_epoch(group_id, &auth.user_id, epoch)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    fn advance_group_epoch(&self, group_id: &str) -> P2PResult<()> {
+        {
+            let mut sessions = self.group_sessions.lock()
+                .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
+            let session = sessions.get_mut(group_id)
+                .ok_or_else(|| P2PError::GroupError("Group session not found".to_string()))?;
+            let rekeyed = new_group_session(group_id, session.epoch + 1, &[session.index], session.threshold, session.index);
+            *session = rekeyed;
+        }
+        self.persist_group_session(group_id)
+    }
+
+    /// Write `group_id`'s current session state to the configured
+    /// `GardenStore`, so a restart picks up right where this client left
+    /// off instead of starting from nothing.
+    fn persist_group_session(&self, group_id: &str) -> P2PResult<()> {
+        let record = self.group_key_record(group_id)?;
+        self.store.save_group_session(&record)
+            .map_err(|e| P2PError::StorageError(e.to_string()))
+    }
+
+    /// The group's current epoch, bumped by `remove_group_member` and
+    /// `rotate_group_key`.
+    pub fn group_epoch(&self, group_id: &str) -> P2PResult<u64> {
+        let sessions = self.group_sessions.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
+        let session = sessions.get(group_id)
+            .ok_or_else(|| P2PError::GroupError("Group session not found".to_string()))?;
+        Ok(session.epoch)
+    }
+
+    fn group_key_record(&self, group_id: &str) -> P2PResult<GroupKeyRecord> {
+        let sessions = self.group_sessions.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
+        let session = sessions.get(group_id)
+            .ok_or_else(|| P2PError::GroupError("Group session not found".to_string()))?;
+
+        Ok(GroupKeyRecord {
+            group_id: group_id.to_string(),
+            epoch: session.epoch,
+            ratchet_key: session.secret_share.to_bytes().to_vec(),
+            group_public_key: session.group_public_key.compress().to_bytes().to_vec(),
+            sender_user_ids: self.group_members(group_id)?,
+        })
+    }
+
+    fn restore_group_key_record(&self, record: GroupKeyRecord) -> P2PResult<()> {
+        let mut groups = self.groups.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock groups".to_string()))?;
+        groups.insert(record.group_id.clone(), MockGroup {
+            id: record.group_id.clone(),
+            members: record.sender_user_ids.clone(),
+        });
+        drop(groups);
+
+        let secret_share_bytes: [u8; 32] = record.ratchet_key.try_into()
+            .map_err(|_| P2PError::GroupError("stored ratchet key is not a valid scalar".to_string()))?;
+        let group_public_key_bytes: [u8; 32] = record.group_public_key.try_into()
+            .map_err(|_| P2PError::GroupError("stored group public key is not a valid point".to_string()))?;
+        let group_public_key = CompressedRistretto(group_public_key_bytes).decompress()
+            .ok_or_else(|| P2PError::GroupError("stored group public key does not decode".to_string()))?;
+
+        let mut sessions = self.group_sessions.lock()
+            .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
+        sessions.insert(record.group_id.clone(), GroupSession {
+            group_id: record.group_id.clone(),
+            epoch: record.epoch,
+            index: 1,
+            secret_share: Scalar::from_bytes_mod_order(secret_share_bytes),
+            commitments: vec![group_public_key],
+            group_public_key,
+            threshold: 1,
+        });
+        drop(sessions);
+
+        // Keep the group-membership registry (independent bookkeeping from
+        // the mock session state above) in sync with the restored roster.
+        for user_id in &record.sender_user_ids {
+            self.join_group(&record.group_id, user_id)?;
+        }
+        Ok(())
+    }
+
+    /// Export `group_id`'s current epoch, ratchet secret, and sender roster
+    /// as a passphrase-encrypted bundle, so it can be moved to another
+    /// device or restored after this client's in-memory state is lost.
+    pub fn export_group_keys(&self, group_id: &str, passphrase: &str) -> P2PResult<Vec<u8>> {
+        let record = self.group_key_record(group_id)?;
+        group_backup::export(vec![record], passphrase)
+            .map_err(|e| P2PError::GroupError(e.to_string()))
+    }
+
+    /// Import a bundle produced by `export_group_keys`, merging by the
+    /// higher epoch so a stale bundle can't roll this client's group state
+    /// backwards.
+    pub fn import_group_keys(&self, bundle: &[u8], passphrase: &str) -> P2PResult<()> {
+        let imported = group_backup::import(bundle, passphrase)
+            .map_err(|e| P2PError::GroupError(e.to_string()))?;
+
+        for record in imported {
+            let keep = match self.group_key_record(&record.group_id) {
+                Ok(current) if current.epoch >= record.epoch => current,
+                _ => record,
+            };
+            self.restore_group_key_record(keep)?;
+        }
+        Ok(())
+    }
+
+    /// Export every group this client currently holds session state for.
+    pub fn export_all_group_keys(&self, passphrase: &str) -> P2PResult<Vec<u8>> {
+        let group_ids: Vec<String> = {
+            let sessions = self.group_sessions.lock()
+                .map_err(|_| P2PError::StorageError("Failed to lock group sessions".to_string()))?;
+            sessions.keys().cloned().collect()
+        };
+
+        let records = group_ids.iter()
+            .map(|id| self.group_key_record(id))
+            .collect::<P2PResult<Vec<_>>>()?;
+
+        group_backup::export(records, passphrase)
+            .map_err(|e| P2PError::GroupError(e.to_string()))
+    }
+
+    /// Import a full backup produced by `export_all_group_keys`, merging
+    /// each group by the higher epoch.
+    pub fn import_all_group_keys(&self, bundle: &[u8], passphrase: &str) -> P2PResult<()> {
+        self.import_group_keys(bundle, passphrase)
+    }
+}
+
+// Helper function to create a garden client with a new keypair
+pub async fn create_garden_client(config: GardenConfig) -> P2PResult<GardenClient> {
+    // Generate a new private key for this client
+    let private_key = PrivateKey::new();
+    
+    // Create and initialize the client
+    let mut client = GardenClient::new(config, private_key).await?;
+    client.initialize().await?;
+    client.rehydrate_from_store().await?;
+
+    Ok(client)
+}
+
+// Helper function to create standard garden topics for a user
+pub fn create_user_topics(user_id: &str) -> Vec<Topic> {
+    vec![
         // Personal inbox topic
         Topic::new(&format!("users/{}/inbox", user_id)),
         Topic::new(&format!("users/{}/presence", user_id)),
@@ -553,6 +2428,30 @@ mod tests {
         assert!(config.device.is_none());
         assert!(config.namespaces.is_empty());
         assert_eq!(config.data_directory, "./garden-data");
+        assert_eq!(config.network_load, 3);
+    }
+
+    #[test]
+    fn network_load_level_3_matches_the_prior_fixed_defaults() {
+        let profile = NetworkLoadProfile::for_level(3);
+        assert_eq!(profile.discovery_timeout, Duration::from_secs(30));
+        assert_eq!(profile.max_connections, 50);
+    }
+
+    #[test]
+    fn network_load_trades_bandwidth_for_latency_monotonically() {
+        let low = NetworkLoadProfile::for_level(1);
+        let high = NetworkLoadProfile::for_level(5);
+
+        assert!(low.discovery_timeout > high.discovery_timeout);
+        assert!(low.max_connections < high.max_connections);
+        assert!(low.batch_interval > high.batch_interval);
+    }
+
+    #[test]
+    fn network_load_clamps_out_of_range_levels() {
+        assert_eq!(NetworkLoadProfile::for_level(0), NetworkLoadProfile::for_level(1));
+        assert_eq!(NetworkLoadProfile::for_level(9), NetworkLoadProfile::for_level(5));
     }
 
     #[test]
@@ -583,10 +2482,26 @@ mod tests {
             private_key,
             config,
             connections: Arc::new(Mutex::new(HashMap::new())),
-            subscribed_topics: Arc::new(Mutex::new(Vec::new())),
-            user_subspaces: Arc::new(Mutex::new(HashMap::new())),
+            service: GardenService::new(),
             groups: Arc::new(Mutex::new(HashMap::new())),
             group_sessions: Arc::new(Mutex::new(HashMap::new())),
+            handlers: Arc::new(Mutex::new(Vec::new())),
+            next_handler_id: Arc::new(AtomicU64::new(1)),
+            manual_peers: Arc::new(Mutex::new(Vec::new())),
+            history: Arc::new(HistoryLog::new()),
+            trust_store: Arc::new(TrustStore::new()),
+            peer_keys: Arc::new(PeerKeyCache::new()),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            mfa_policy: Arc::new(Mutex::new(MfaPolicy::new())),
+            trusted_capability_roots: Arc::new(Mutex::new(Vec::new())),
+            device_lists: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(Mutex::new(crate::clock::HybridLogicalClock::new())),
+            store: Arc::new(crate::store::InMemoryStore::new()),
+            token_store: Arc::new(InMemoryTokenStore::new()),
+            events_tx: tokio::sync::broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            load_profile: NetworkLoadProfile::for_level(3),
+            topic_bandwidth: Arc::new(Mutex::new(HashMap::new())),
+            peer_bandwidth: Arc::new(Mutex::new(HashMap::new())),
         };
         
         // Test direct message topic creation
@@ -609,10 +2524,26 @@ mod tests {
             private_key,
             config,
             connections: Arc::new(Mutex::new(HashMap::new())),
-            subscribed_topics: Arc::new(Mutex::new(Vec::new())),
-            user_subspaces: Arc::new(Mutex::new(HashMap::new())),
+            service: GardenService::new(),
             groups: Arc::new(Mutex::new(HashMap::new())),
             group_sessions: Arc::new(Mutex::new(HashMap::new())),
+            handlers: Arc::new(Mutex::new(Vec::new())),
+            next_handler_id: Arc::new(AtomicU64::new(1)),
+            manual_peers: Arc::new(Mutex::new(Vec::new())),
+            history: Arc::new(HistoryLog::new()),
+            trust_store: Arc::new(TrustStore::new()),
+            peer_keys: Arc::new(PeerKeyCache::new()),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            mfa_policy: Arc::new(Mutex::new(MfaPolicy::new())),
+            trusted_capability_roots: Arc::new(Mutex::new(Vec::new())),
+            device_lists: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(Mutex::new(crate::clock::HybridLogicalClock::new())),
+            store: Arc::new(crate::store::InMemoryStore::new()),
+            token_store: Arc::new(InMemoryTokenStore::new()),
+            events_tx: tokio::sync::broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            load_profile: NetworkLoadProfile::for_level(3),
+            topic_bandwidth: Arc::new(Mutex::new(HashMap::new())),
+            peer_bandwidth: Arc::new(Mutex::new(HashMap::new())),
         };
         
         // Register a test subspace
@@ -659,22 +2590,956 @@ mod tests {
         // Create a test group
         let group_id = "test-encrypted-group";
         let key_package = client.create_encrypted_group(group_id).await.unwrap();
-        
-        // Test group creation
-        assert_eq!(String::from_utf8(key_package.clone()).unwrap(), group_id);
-        
+
+        // The key package carries real DKG key material now, not the group id.
+        let package = GroupKeyPackage::from_bytes(&key_package).unwrap();
+        assert_eq!(package.threshold, 1);
+
         // Test message encryption
         let test_message = b"Test encrypted message";
         client.send_encrypted_group_message(group_id, test_message).await.unwrap();
-        
-        // Test message decryption with a mock encrypted message
-        let mock_encrypted = {
-            let mut data = b"ENCRYPTED:".to_vec();
-            data.extend_from_slice(test_message);
-            data
-        };
-        
-        let decrypted = client.receive_encrypted_group_message(group_id, &mock_encrypted).await.unwrap();
+
+        // Build a ciphertext the same way `send_encrypted_group_message`
+        // does, to exercise `receive_encrypted_group_message` directly
+        // without relying on the mock network actually delivering it.
+        let group_public_key = CompressedRistretto(package.group_public_key).decompress().unwrap();
+        let ciphertext = group_crypto::encrypt(&group_public_key, test_message);
+        let ciphertext_bytes = serde_json::to_vec(&ciphertext).unwrap();
+        let mut encrypted = b"ENCRYPTED:".to_vec();
+        encrypted.extend_from_slice(&0u64.to_be_bytes());
+        encrypted.extend_from_slice(&ciphertext_bytes);
+
+        let decrypted = client.receive_encrypted_group_message(group_id, &encrypted).await.unwrap();
         assert_eq!(decrypted, test_message);
     }
+
+    #[tokio::test]
+    async fn removing_a_group_member_advances_the_epoch_and_its_roster() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-epoch-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        assert_eq!(client.group_epoch(group_id).unwrap(), 0);
+
+        let creator_id = client.group_members(group_id).unwrap().remove(0);
+        let auth = test_auth_token(vec![Capability::ManageGroup("*".to_string())]);
+        client.remove_group_member(group_id, &creator_id, 0, &auth).await.unwrap();
+
+        assert_eq!(client.group_epoch(group_id).unwrap(), 1);
+        assert!(!client.group_members(group_id).unwrap().contains(&creator_id));
+    }
+
+    #[tokio::test]
+    async fn remove_group_member_is_denied_without_manage_group_capability() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-unauthorized-removal-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        let member_id = client.group_members(group_id).unwrap().remove(0);
+
+        let auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        let result = client.remove_group_member(group_id, &member_id, 0, &auth).await;
+        assert!(matches!(result, Err(P2PError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn remove_group_member_is_denied_without_a_fresh_enough_mfa_step_up() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+        client.require_mfa_freshness("groups", 15 * 60 * 1000).unwrap();
+
+        let group_id = "test-step-up-removal-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        let member_id = client.group_members(group_id).unwrap().remove(0);
+
+        let mut auth = test_auth_token(vec![
+            Capability::ManageGroup("*".to_string()),
+            Capability::MfaRequired("groups".to_string()),
+        ]);
+
+        let result = client.remove_group_member(group_id, &member_id, 0, &auth).await;
+        assert!(matches!(result, Err(P2PError::Unauthorized(_))),
+            "a ManageGroup token with no recent step-up must be denied when the path requires MFA");
+
+        auth.mfa_verified_at = Some(crate::clock::HybridLogicalClock::now());
+        client.remove_group_member(group_id, &member_id, 0, &auth).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rotating_the_group_key_advances_the_epoch_without_changing_membership() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-rotate-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        let members_before = client.group_members(group_id).unwrap();
+
+        client.rotate_group_key(group_id, 0).await.unwrap();
+
+        assert_eq!(client.group_epoch(group_id).unwrap(), 1);
+        assert_eq!(client.group_members(group_id).unwrap(), members_before);
+    }
+
+    #[tokio::test]
+    async fn a_commit_built_against_a_stale_epoch_is_rejected() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-stale-commit-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        client.rotate_group_key(group_id, 0).await.unwrap();
+        assert_eq!(client.group_epoch(group_id).unwrap(), 1);
+
+        // Still targets epoch 0, but the group already moved to epoch 1.
+        let result = client.rotate_group_key(group_id, 0).await;
+        assert!(matches!(result, Err(P2PError::GroupError(_))));
+        assert_eq!(client.group_epoch(group_id).unwrap(), 1, "a rejected commit must not advance the epoch");
+    }
+
+    #[tokio::test]
+    async fn a_removed_member_loses_access_to_messages_sealed_after_their_removal() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-access-control-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        let member_id = client.group_members(group_id).unwrap().remove(0);
+
+        let mut auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        auth.user_id = member_id.clone();
+
+        assert!(client.can_access_group_entry(group_id, 0, &auth).unwrap());
+
+        let admin_auth = test_auth_token(vec![Capability::ManageGroup("*".to_string())]);
+        client.remove_group_member(group_id, &member_id, 0, &admin_auth).await.unwrap();
+
+        assert!(client.can_access_group_entry(group_id, 0, &auth).unwrap(),
+            "a removed member still had access to what it already could read before removal");
+        assert!(!client.can_access_group_entry(group_id, 1, &auth).unwrap(),
+            "a removed member must lose access to anything sealed at or after its removal epoch");
+    }
+
+    #[tokio::test]
+    async fn a_revoked_device_loses_access_regardless_of_token_expiry() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-revocation-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        let member_id = client.group_members(group_id).unwrap().remove(0);
+
+        let mut auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        auth.user_id = member_id.clone();
+        auth.device_id = "compromised-device".to_string();
+        auth.expires_at = u64::MAX;
+
+        assert!(client.can_access_group_entry(group_id, 0, &auth).unwrap());
+
+        let admin_auth = test_auth_token(vec![Capability::ManageGroup("*".to_string())]);
+        client.revoke_device(&member_id, "compromised-device", "reported stolen", &admin_auth).unwrap();
+
+        assert!(!client.can_access_group_entry(group_id, 0, &auth).unwrap(),
+            "a revoked device must lose access immediately even though its token hasn't expired");
+    }
+
+    #[tokio::test]
+    async fn can_access_group_entry_is_denied_for_a_revoked_token_regardless_of_expiry() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-revoked-token-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+        let member_id = client.group_members(group_id).unwrap().remove(0);
+
+        let mut auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        auth.user_id = member_id.clone();
+        auth.expires_at = u64::MAX;
+        client.record_issued_token(&auth).unwrap();
+
+        assert!(client.can_access_group_entry(group_id, 0, &auth).unwrap());
+
+        client.revoke_token(&auth.user_id, &auth.id).await.unwrap();
+
+        assert!(!client.can_access_group_entry(group_id, 0, &auth).unwrap(),
+            "a revoked token must lose access immediately even though it hasn't expired");
+    }
+
+    #[tokio::test]
+    async fn revoke_device_is_denied_once_the_admins_own_device_is_dropped_from_its_device_list() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let admin_auth = test_auth_token(vec![Capability::ManageGroup("*".to_string())]);
+
+        let mut device_list = DeviceList::new(&admin_auth.user_id);
+        let signing_key = SigningKey::from_bytes(&[3u8; 32]);
+        device_list.rotate(
+            vec![Device {
+                device_id: admin_auth.device_id.clone(),
+                public_key: vec![1, 2, 3],
+                signature: Vec::new(),
+                capabilities: Vec::new(),
+            }],
+            vec![],
+            &signing_key,
+        );
+        client.record_device_list(device_list.clone()).unwrap();
+
+        // `devices/<user_id>/...` paths are gated against the DeviceList for
+        // that same `user_id`, so target a device under the admin's own
+        // user_id here - while its own device is still listed, a MFA-free
+        // revocation requires no step-up and succeeds as before.
+        client.revoke_device(&admin_auth.user_id, "some-other-device", "test", &admin_auth).unwrap();
+
+        // Now drop the admin's device from its own DeviceList (e.g. it was
+        // itself reported stolen) and bump the version so the new list is
+        // recorded.
+        device_list.rotate(vec![], vec![admin_auth.device_id.clone()], &signing_key);
+        client.record_device_list(device_list).unwrap();
+
+        let result = client.revoke_device(&admin_auth.user_id, "some-other-device", "test", &admin_auth);
+        assert!(matches!(result, Err(P2PError::Unauthorized(_))),
+            "a token whose device was dropped from its user's DeviceList must lose access to devices/<user_id>/... paths");
+    }
+
+    #[tokio::test]
+    async fn query_history_is_unauthorized_for_a_revoked_device() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/history");
+
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        let mut auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        let admin_auth = test_auth_token(vec![Capability::ManageGroup("*".to_string())]);
+        client.revoke_device(&auth.user_id, &auth.device_id, "lost device", &admin_auth).unwrap();
+
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap();
+        assert!(matches!(result, HistoryResult::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn query_history_is_unauthorized_for_a_revoked_token_even_before_it_expires() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/revoked-token-history");
+
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        let auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        client.record_issued_token(&auth).unwrap();
+        assert!(matches!(
+            client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap(),
+            HistoryResult::Messages(_)
+        ));
+
+        client.revoke_token(&auth.user_id, &auth.id).await.unwrap();
+
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap();
+        assert!(matches!(result, HistoryResult::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn query_history_is_authorized_by_a_ledger_grant_the_token_does_not_carry() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/ledger-history");
+
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        // A token with no matching capability at all - access should only
+        // come from the replicated ledger, e.g. a grant made on another of
+        // the user's devices that hasn't reached this token yet.
+        let auth = test_auth_token(vec![]);
+        assert!(matches!(
+            client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap(),
+            HistoryResult::Unauthorized
+        ));
+
+        client.grant_capability(
+            &auth.user_id,
+            Capability::ReadMessages(topic.name().to_string()),
+            "another-device",
+        ).unwrap();
+
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap();
+        assert!(matches!(result, HistoryResult::Messages(_)));
+    }
+
+    #[tokio::test]
+    async fn a_message_from_a_superseded_epoch_is_rejected() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-stale-epoch-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+
+        let stale_message = {
+            let mut data = b"ENCRYPTED:".to_vec();
+            data.extend_from_slice(&0u64.to_be_bytes());
+            data.extend_from_slice(b"before the rotation");
+            data
+        };
+
+        client.rotate_group_key(group_id, 0).await.unwrap();
+
+        let result = client.receive_encrypted_group_message(group_id, &stale_message).await;
+        assert!(matches!(result, Err(P2PError::GroupError(_))));
+    }
+
+    #[tokio::test]
+    async fn creating_an_encrypted_group_populates_its_membership_roster() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        let group_id = "test-roster-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+
+        let members = client.group_members(group_id).unwrap();
+        assert_eq!(members.len(), 1);
+
+        client.leave_group(group_id, &members[0]).unwrap();
+        assert!(client.group_members(group_id).unwrap().is_empty());
+    }
+
+    fn test_auth_token(capabilities: Vec<Capability>) -> AuthToken {
+        AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-1".to_string(),
+            device_id: "device-1".to_string(),
+            capabilities,
+            signature: None,
+            expires_at: u64::MAX,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn revoking_a_token_is_visible_locally_and_via_a_peers_applied_notice() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let token = test_auth_token(vec![Capability::CreateInvites]);
+        client.record_issued_token(&token).unwrap();
+        assert!(!client.is_token_revoked(&token.id).unwrap());
+
+        client.revoke_token(&token.user_id, &token.id).await.unwrap();
+        assert!(client.is_token_revoked(&token.id).unwrap());
+
+        // A second client only learns of the revocation once it applies the
+        // notice that would have arrived over create_token_revocation_topic.
+        let peer_config = GardenConfig::default();
+        let peer = create_garden_client(peer_config).await.unwrap();
+        assert!(!peer.is_token_revoked(&token.id).unwrap());
+        peer.apply_token_revocation_notice(&TokenRevocationNotice { token_id: token.id.clone() }).unwrap();
+        assert!(peer.is_token_revoked(&token.id).unwrap());
+    }
+
+    #[tokio::test]
+    async fn query_history_returns_entries_the_client_already_observed() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/history");
+
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        let auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap();
+        match result {
+            HistoryResult::Messages(entries) => assert_eq!(entries.len(), 1),
+            other => panic!("expected Messages, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn query_history_is_unauthorized_without_read_capability() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/history");
+
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        let auth = test_auth_token(vec![]);
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap();
+        assert!(matches!(result, HistoryResult::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn query_history_on_an_unseen_topic_is_empty() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/never-subscribed");
+
+        let auth = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &auth).unwrap();
+        assert!(matches!(result, HistoryResult::Empty));
+    }
+
+    #[tokio::test]
+    async fn query_history_accepts_a_delegated_token_that_chains_to_a_trusted_root() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/history");
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        let root_key = SigningKey::from_bytes(&[7u8; 32]);
+        client.add_trusted_capability_root(root_key.verifying_key()).unwrap();
+
+        let mut root = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        root.sign(&root_key);
+
+        let device_key = SigningKey::from_bytes(&[9u8; 32]);
+        let delegated = root
+            .delegate(&root_key, &device_key, vec![Capability::ReadMessages("*".to_string())], u64::MAX - 1)
+            .unwrap();
+
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &delegated).unwrap();
+        match result {
+            HistoryResult::Messages(entries) => assert_eq!(entries.len(), 1),
+            other => panic!("expected Messages, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn query_history_rejects_a_delegated_token_chaining_to_an_untrusted_root() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/test/history");
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        // Note: unlike the test above, this root is never registered via
+        // `add_trusted_capability_root` - a delegated token's own
+        // `capabilities` list is not enough on its own, no matter how
+        // internally consistent its chain is.
+        let root_key = SigningKey::from_bytes(&[13u8; 32]);
+
+        let mut root = test_auth_token(vec![Capability::ReadMessages("*".to_string())]);
+        root.sign(&root_key);
+
+        let device_key = SigningKey::from_bytes(&[15u8; 32]);
+        let delegated = root
+            .delegate(&root_key, &device_key, vec![Capability::ReadMessages("*".to_string())], u64::MAX - 1)
+            .unwrap();
+
+        let result = client.query_history(&topic, HistorySelector::Latest, 10, &delegated).unwrap();
+        assert!(matches!(result, HistoryResult::Unauthorized));
+    }
+
+    fn test_profile_entry() -> GardenEntry {
+        GardenEntry::Profile {
+            user_id: "user-1".to_string(),
+            subspace_id: SubspaceId("sub-1".to_string()),
+            field_type: crate::types::ProfileField::DisplayName,
+            content: vec![1, 2, 3],
+            timestamp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn multiple_handlers_all_run_for_a_matching_entry() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let calls_a = Arc::new(Mutex::new(0));
+        let calls_b = Arc::new(Mutex::new(0));
+        let (ca, cb) = (calls_a.clone(), calls_b.clone());
+
+        client.on(move |_entry| { *ca.lock().unwrap() += 1; }).unwrap();
+        client.on(move |_entry| { *cb.lock().unwrap() += 1; }).unwrap();
+
+        let topic = Topic::new("garden/test/profile");
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        assert_eq!(*calls_a.lock().unwrap(), 1);
+        assert_eq!(*calls_b.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn entry_handler_is_filtered_by_kind() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let calls = Arc::new(Mutex::new(0));
+        let c = calls.clone();
+        client.set_entry_handler(EntryKind::DirectMessage, move |_entry| { *c.lock().unwrap() += 1; }).unwrap();
+
+        let topic = Topic::new("garden/test/profile");
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn removed_handler_no_longer_runs() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let calls = Arc::new(Mutex::new(0));
+        let c = calls.clone();
+        let id = client.on(move |_entry| { *c.lock().unwrap() += 1; }).unwrap();
+
+        assert!(client.remove_handler(id).unwrap());
+        assert!(!client.remove_handler(id).unwrap());
+
+        let topic = Topic::new("garden/test/profile");
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+        assert_eq!(*calls.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn a_panicking_handler_does_not_prevent_others_from_running() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let calls = Arc::new(Mutex::new(0));
+        let c = calls.clone();
+        client.on(|_entry| panic!("boom")).unwrap();
+        client.on(move |_entry| { *c.lock().unwrap() += 1; }).unwrap();
+
+        let topic = Topic::new("garden/test/profile");
+        client.dispatch_entry(&topic, &test_profile_entry()).unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn initialize_fails_with_no_discovery_backend_enabled() {
+        let config = GardenConfig {
+            discovery: DiscoveryConfig {
+                enable_mdns: false,
+                bootstrap_peers: Vec::new(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let private_key = PrivateKey::new();
+        let mut client = GardenClient::new(config, private_key).await.unwrap();
+
+        let result = client.initialize().await;
+        assert!(matches!(result, Err(P2PError::DiscoveryError(_))));
+    }
+
+    #[tokio::test]
+    async fn a_high_tolerance_error_bans_a_peer_and_blocks_reconnection() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let peer_id = "peer-1";
+        client.report_peer(peer_id, PeerAction::HighToleranceError).unwrap();
+        assert!(client.is_peer_banned(peer_id).unwrap());
+
+        let result = client.connect_to_peer(peer_id).await;
+        assert!(matches!(result, Err(P2PError::PeerBanned(_))));
+    }
+
+    #[tokio::test]
+    async fn low_tolerance_errors_must_accumulate_before_banning() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let peer_id = "peer-2";
+        for _ in 0..3 {
+            client.report_peer(peer_id, PeerAction::LowToleranceError).unwrap();
+        }
+        assert!(!client.is_peer_banned(peer_id).unwrap());
+        assert!(client.connect_to_peer(peer_id).await.is_ok());
+
+        client.report_peer(peer_id, PeerAction::LowToleranceError).unwrap();
+        assert!(client.is_peer_banned(peer_id).unwrap());
+    }
+
+    #[tokio::test]
+    async fn add_and_remove_peer_updates_the_manual_peer_set() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+
+        let peer = PeerAddr::new("garden://peer-1");
+        client.add_peer(peer.clone()).await.unwrap();
+        assert_eq!(client.manual_peers().unwrap(), vec![peer.clone()]);
+
+        client.remove_peer(&peer).await.unwrap();
+        assert!(client.manual_peers().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn connecting_to_a_peer_emits_a_peer_joined_event() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let mut events = client.events_stream();
+
+        client.connect_to_peer("peer-1").await.unwrap();
+
+        let event = events.recv().await.unwrap();
+        assert!(matches!(event, GardenEvent::PeerJoined { peer } if peer == "peer-1"));
+    }
+
+    #[tokio::test]
+    async fn disconnecting_from_a_peer_emits_a_peer_left_event() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        client.connect_to_peer("peer-1").await.unwrap();
+        let mut events = client.events_stream();
+
+        client.disconnect_from_peer("peer-1").await.unwrap();
+
+        let event = events.recv().await.unwrap();
+        assert!(matches!(event, GardenEvent::PeerLeft { peer } if peer == "peer-1"));
+    }
+
+    #[tokio::test]
+    async fn sending_a_message_accounts_outbound_bytes_against_its_topic() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = Topic::new("garden/bandwidth-test");
+        client.subscribe(topic.clone()).await.unwrap();
+
+        client.send_message(&topic, b"hello").await.unwrap();
+
+        let report = client.bandwidth_stats().unwrap();
+        let stats = report.by_topic.get(&topic).unwrap();
+        assert_eq!(stats.outbound_bytes, 5);
+        assert_eq!(stats.inbound_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn the_confirm_mac_and_cancel_legs_publish_to_the_same_pair_topic_as_the_offer() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let topic = client.create_verification_topic("bob");
+        client.subscribe(topic.clone()).await.unwrap();
+
+        client.send_verification_mac("bob", &VerificationMac {
+            transaction_id: "txn".to_string(),
+            mac: [0u8; 32],
+        }).await.unwrap();
+        client.send_verification_cancel("bob", &VerificationCancel {
+            transaction_id: "txn".to_string(),
+        }).await.unwrap();
+
+        let report = client.bandwidth_stats().unwrap();
+        let stats = report.by_topic.get(&topic).unwrap();
+        assert!(stats.outbound_bytes > 0);
+    }
+
+    #[test]
+    fn bandwidth_counter_reports_zero_rate_before_a_second_sample() {
+        let counter = BandwidthCounter::default();
+        counter.record_outbound(100);
+
+        let stats = counter.stats().unwrap();
+        assert_eq!(stats.outbound_bytes, 100);
+        assert_eq!(stats.outbound_rate_bytes_per_sec, 0.0);
+    }
+
+    #[test]
+    fn peer_id_can_only_be_built_from_a_valid_identity_public_key() {
+        let (identity, _) = Identity::generate_identity();
+        assert!(PeerId::from_verified_identity(&identity).is_some());
+
+        let mut malformed = identity.clone();
+        malformed.public_key = vec![1, 2, 3];
+        assert!(PeerId::from_verified_identity(&malformed).is_none());
+    }
+
+    #[tokio::test]
+    async fn mutual_sas_confirmation_records_a_verified_device() {
+        let (alice_identity, alice_signing_key) = Identity::generate_identity();
+        let (bob_identity, bob_signing_key) = Identity::generate_identity();
+
+        let mut alice_config = GardenConfig::default();
+        alice_config.user_identity = Some(alice_identity.clone());
+        let alice = create_garden_client(alice_config).await.unwrap();
+
+        let mut bob_config = GardenConfig::default();
+        bob_config.user_identity = Some(bob_identity.clone());
+        let bob = create_garden_client(bob_config).await.unwrap();
+
+        // Alice begins the exchange and sends Bob her offer.
+        let mut alice_handle = alice.start_verification(
+            &bob_identity.user_id,
+            alice_identity.public_key.clone(),
+            bob_identity.public_key.clone(),
+        );
+
+        // Bob consumes Alice's offer, deriving his SAS immediately, and
+        // sends his own offer back.
+        let mut bob_handle = bob.accept_verification(
+            &alice_identity.user_id,
+            bob_identity.public_key.clone(),
+            alice_identity.public_key.clone(),
+            alice_handle.our_offer().clone(),
+        ).unwrap();
+
+        // Alice consumes Bob's offer to derive her own SAS.
+        alice_handle.accept(bob_handle.our_offer().clone()).unwrap();
+
+        assert!(alice_handle.sas().is_some());
+        assert!(bob_handle.sas().is_some());
+
+        // Both users confirm the emojis matched out of band, and exchange MACs.
+        let alice_mac = alice_handle.confirm().unwrap();
+        let bob_mac = bob_handle.confirm().unwrap();
+
+        let alice_verified_bob = alice_handle.finish(&bob_mac, &alice_signing_key).unwrap();
+        let bob_verified_alice = bob_handle.finish(&alice_mac, &bob_signing_key).unwrap();
+
+        assert_eq!(alice_verified_bob.public_key, bob_identity.public_key);
+        assert_eq!(bob_verified_alice.public_key, alice_identity.public_key);
+
+        alice.record_verified_device(alice_verified_bob).unwrap();
+        bob.record_verified_device(bob_verified_alice).unwrap();
+
+        assert!(alice.is_device_verified(&bob_identity.public_key).unwrap());
+        assert!(bob.is_device_verified(&alice_identity.public_key).unwrap());
+    }
+
+    #[tokio::test]
+    async fn a_gossiped_header_lets_a_peer_verify_an_auth_token_it_never_exchanged_keys_for() {
+        let (alice_identity, alice_signing_key) = Identity::generate_identity();
+        let (bob_identity, _) = Identity::generate_identity();
+
+        let mut alice_config = GardenConfig::default();
+        alice_config.user_identity = Some(alice_identity.clone());
+        let alice = create_garden_client(alice_config).await.unwrap();
+
+        let mut bob_config = GardenConfig::default();
+        bob_config.user_identity = Some(bob_identity.clone());
+        let bob = create_garden_client(bob_config).await.unwrap();
+
+        assert!(bob.peer_key(&alice_identity.user_id).unwrap().is_none());
+
+        let mut token = test_auth_token(vec![Capability::CreateInvites]);
+        token.user_id = alice_identity.user_id.clone();
+        token.sign(&alice_signing_key);
+
+        let topic = Topic::new("garden/test/key-gossip");
+        bob.subscribe(topic.clone()).await.unwrap();
+        let sent = serde_json::to_vec(&token).unwrap();
+        let header = alice.message_header(false).unwrap();
+        alice.send_message_with_header(&topic, &sent, false).await.unwrap();
+
+        // Bob wouldn't actually get Alice's header this way in a real
+        // network hop - simulate the receive side directly, since there's
+        // no loopback transport in this test harness.
+        let envelope = GossipEnvelope { header, payload: sent };
+        let received = bob.receive_message_with_header(&serde_json::to_vec(&envelope).unwrap()).unwrap();
+        let received_token: AuthToken = serde_json::from_slice(&received).unwrap();
+
+        let key = bob.peer_key(&alice_identity.user_id).unwrap().expect("header should have populated the cache");
+        assert!(received_token.verify(&key));
+        assert!(!bob.is_peer_key_verified(&alice_identity.user_id).unwrap());
+
+        bob.promote_peer_key(&alice_identity.user_id, alice_identity.public_key.clone()).unwrap();
+        assert!(bob.is_peer_key_verified(&alice_identity.user_id).unwrap());
+
+        // A later gossiped header for a different key can't undo that.
+        let (impostor_identity, _) = Identity::generate_identity();
+        bob.apply_message_header(&MessageHeader {
+            user_id: alice_identity.user_id.clone(),
+            device_public_key: impostor_identity.public_key.clone(),
+            prefers_encryption: false,
+        }).unwrap();
+        assert_eq!(bob.peer_key(&alice_identity.user_id).unwrap().unwrap().to_bytes().to_vec(), alice_identity.public_key);
+    }
+
+    #[tokio::test]
+    async fn a_negotiated_session_can_be_resumed_with_a_signed_token_instead_of_a_full_handshake() {
+        let (identity, signing_key) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+        let client = create_garden_client(config).await.unwrap();
+
+        client.connect_to_peer("peer-1").await.unwrap();
+
+        // Simulate the other side of the handshake in-process, since there's
+        // no loopback transport in this test harness.
+        let (our_session, our_hello) = client.begin_handshake();
+        let (peer_session, peer_hello) = HandshakeSession::begin();
+        let negotiated = client.complete_handshake("peer-1", our_session, &peer_hello).unwrap();
+        let peer_negotiated = peer_session.complete(&our_hello).unwrap();
+        assert_eq!(negotiated.session_id, peer_negotiated.session_id);
+        assert_eq!(negotiated.session_key, peer_negotiated.session_key);
+
+        let topic = Topic::new("garden/dm/peer-1");
+        client.subscribe(topic.clone()).await.unwrap();
+
+        let token = client.create_resume_token("peer-1", &signing_key).unwrap();
+        assert!(token.verify(&signing_key.verifying_key()));
+
+        let rejoined = client.resume_session("peer-1", &token, &signing_key.verifying_key()).await.unwrap();
+        assert!(rejoined.contains(&topic));
+
+        // A token for a session this client never negotiated is rejected.
+        let bogus_token = ResumeToken::create("not-a-real-session", &identity.user_id, &signing_key);
+        assert!(matches!(
+            client.resume_session("peer-1", &bogus_token, &signing_key.verifying_key()).await,
+            Err(P2PError::HandshakeError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reconnect_with_backoff_gives_up_after_max_attempts_against_an_unresumable_peer() {
+        let config = GardenConfig::default();
+        let client = create_garden_client(config).await.unwrap();
+        let (_, signing_key) = Identity::generate_identity();
+
+        // No session was ever negotiated for "ghost-peer", so every attempt
+        // fails the same way - this just checks the retry loop terminates
+        // and surfaces the error rather than looping forever.
+        let token = ResumeToken::create("session-x", "user-x", &signing_key);
+        let result = client.reconnect_with_backoff(
+            "ghost-peer",
+            &token,
+            &signing_key.verifying_key(),
+            3,
+            Duration::from_millis(1),
+            Duration::from_millis(5),
+        ).await;
+
+        assert!(matches!(result, Err(P2PError::HandshakeError(_))));
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_verification_blocks_further_progress() {
+        let (alice_identity, _) = Identity::generate_identity();
+        let (bob_identity, _) = Identity::generate_identity();
+
+        let mut alice_config = GardenConfig::default();
+        alice_config.user_identity = Some(alice_identity.clone());
+        let alice = create_garden_client(alice_config).await.unwrap();
+
+        let mut handle = alice.start_verification(
+            &bob_identity.user_id,
+            alice_identity.public_key.clone(),
+            bob_identity.public_key.clone(),
+        );
+        handle.cancel();
+
+        assert_eq!(
+            handle.accept(VerificationOffer {
+                transaction_id: "txn".to_string(),
+                ephemeral_public: [0u8; 32],
+                commitment: [0u8; 32],
+            }),
+            Err(crate::verification::VerificationError::InvalidState)
+        );
+    }
+
+    #[tokio::test]
+    async fn exported_group_keys_restore_membership_and_epoch_on_another_client() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let source = create_garden_client(config).await.unwrap();
+        source.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-backup-group";
+        source.create_encrypted_group(group_id).await.unwrap();
+        source.rotate_group_key(group_id, 0).await.unwrap();
+
+        let bundle = source.export_group_keys(group_id, "hunter2").unwrap();
+
+        let (other_identity, _) = Identity::generate_identity();
+        let mut other_config = GardenConfig::default();
+        other_config.user_identity = Some(other_identity);
+        let restored = create_garden_client(other_config).await.unwrap();
+
+        restored.import_group_keys(&bundle, "hunter2").unwrap();
+
+        assert_eq!(restored.group_epoch(group_id).unwrap(), 1);
+        assert_eq!(
+            restored.group_members(group_id).unwrap(),
+            source.group_members(group_id).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn importing_a_stale_backup_does_not_downgrade_the_epoch() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let client = create_garden_client(config).await.unwrap();
+        client.initialize_group_encryption().await.unwrap();
+
+        let group_id = "test-no-downgrade-group";
+        client.create_encrypted_group(group_id).await.unwrap();
+
+        let stale_bundle = client.export_group_keys(group_id, "hunter2").unwrap();
+
+        client.rotate_group_key(group_id, 0).await.unwrap();
+        client.rotate_group_key(group_id, 1).await.unwrap();
+        assert_eq!(client.group_epoch(group_id).unwrap(), 2);
+
+        client.import_group_keys(&stale_bundle, "hunter2").unwrap();
+        assert_eq!(client.group_epoch(group_id).unwrap(), 2, "importing a stale backup must not roll the epoch back");
+    }
+
+    #[tokio::test]
+    async fn export_all_and_import_all_round_trip_every_group() {
+        let (identity, _) = Identity::generate_identity();
+        let mut config = GardenConfig::default();
+        config.user_identity = Some(identity.clone());
+
+        let source = create_garden_client(config).await.unwrap();
+        source.initialize_group_encryption().await.unwrap();
+        source.create_encrypted_group("group-a").await.unwrap();
+        source.create_encrypted_group("group-b").await.unwrap();
+
+        let bundle = source.export_all_group_keys("backup passphrase").unwrap();
+
+        let (other_identity, _) = Identity::generate_identity();
+        let mut other_config = GardenConfig::default();
+        other_config.user_identity = Some(other_identity);
+        let restored = create_garden_client(other_config).await.unwrap();
+
+        restored.import_all_group_keys(&bundle, "backup passphrase").unwrap();
+
+        assert_eq!(restored.group_epoch("group-a").unwrap(), 0);
+        assert_eq!(restored.group_epoch("group-b").unwrap(), 0);
+    }
 }
@@ -0,0 +1,445 @@
+// garden-core/src/service.rs
+//
+// Model/service separation for `GardenClient`'s in-memory bookkeeping.
+// Previously subspace lookups, topic subscriptions, and group membership
+// were bundled behind whichever lock `GardenClient` happened to hold for
+// that field, serializing unrelated operations against each other as the
+// app grows. Each registry here owns only its own data behind its own
+// lock and holds no reference to the others; `GardenService` is a thin
+// facade that coordinates them and is what `GardenClient` (and, in the
+// Tauri app, commands) should call through.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use thiserror::Error;
+
+use crate::capability_ledger::{CapabilityLedger, Checkpoint};
+use crate::identity::Capability;
+use crate::p2p::Topic;
+use crate::revocation::RevocationRegistry;
+use crate::types::{SubspaceId, Timestamp};
+use ed25519_dalek::SigningKey;
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("registry lock was poisoned")]
+    Poisoned,
+}
+
+/// Named subspace lookups for the local user, keyed by a short name
+/// ("personal", "inbox", ...) rather than the raw `SubspaceId`.
+#[derive(Default)]
+pub struct SubspaceRegistry {
+    subspaces: RwLock<HashMap<String, SubspaceId>>,
+}
+
+impl SubspaceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, name: &str, subspace: SubspaceId) -> Result<(), RegistryError> {
+        let mut subspaces = self.subspaces.write().map_err(|_| RegistryError::Poisoned)?;
+        subspaces.insert(name.to_string(), subspace);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<SubspaceId>, RegistryError> {
+        let subspaces = self.subspaces.read().map_err(|_| RegistryError::Poisoned)?;
+        Ok(subspaces.get(name).cloned())
+    }
+}
+
+/// Which topics the local client is currently subscribed to.
+#[derive(Default)]
+pub struct TopicRegistry {
+    subscribed: RwLock<Vec<Topic>>,
+}
+
+impl TopicRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, topic: Topic) -> Result<(), RegistryError> {
+        let mut subscribed = self.subscribed.write().map_err(|_| RegistryError::Poisoned)?;
+        if !subscribed.contains(&topic) {
+            subscribed.push(topic);
+        }
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, topic: &Topic) -> Result<(), RegistryError> {
+        let mut subscribed = self.subscribed.write().map_err(|_| RegistryError::Poisoned)?;
+        subscribed.retain(|t| t != topic);
+        Ok(())
+    }
+
+    pub fn is_subscribed(&self, topic: &Topic) -> Result<bool, RegistryError> {
+        let subscribed = self.subscribed.read().map_err(|_| RegistryError::Poisoned)?;
+        Ok(subscribed.contains(topic))
+    }
+
+    pub fn subscribed_topics(&self) -> Result<Vec<Topic>, RegistryError> {
+        let subscribed = self.subscribed.read().map_err(|_| RegistryError::Poisoned)?;
+        Ok(subscribed.clone())
+    }
+}
+
+/// Group membership lists, independent of any encrypted-group session
+/// state (see `GardenClient`'s MLS-style group sessions) - this registry
+/// only tracks who is in which group, plus the epoch each past member was
+/// removed at (`removed_at`) so an epoch-scoped access check can tell
+/// whether a message's epoch predates a member's removal, rather than
+/// only ever seeing the live roster.
+#[derive(Default)]
+pub struct GroupRegistry {
+    members: Mutex<HashMap<String, Vec<String>>>,
+    removed_at: Mutex<HashMap<String, Vec<(String, u64)>>>,
+}
+
+impl GroupRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn join(&self, group_id: &str, user_id: &str) -> Result<(), RegistryError> {
+        let mut members = self.members.lock().map_err(|_| RegistryError::Poisoned)?;
+        let roster = members.entry(group_id.to_string()).or_default();
+        if !roster.iter().any(|m| m == user_id) {
+            roster.push(user_id.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn leave(&self, group_id: &str, user_id: &str) -> Result<(), RegistryError> {
+        let mut members = self.members.lock().map_err(|_| RegistryError::Poisoned)?;
+        if let Some(roster) = members.get_mut(group_id) {
+            roster.retain(|m| m != user_id);
+        }
+        Ok(())
+    }
+
+    pub fn members(&self, group_id: &str) -> Result<Vec<String>, RegistryError> {
+        let members = self.members.lock().map_err(|_| RegistryError::Poisoned)?;
+        Ok(members.get(group_id).cloned().unwrap_or_default())
+    }
+
+    /// Record that `user_id` was removed from `group_id` as of `epoch` -
+    /// the first epoch they no longer have access to. Doesn't touch the
+    /// live roster; callers remove from `members` separately via `leave`.
+    pub fn record_removal(&self, group_id: &str, user_id: &str, epoch: u64) -> Result<(), RegistryError> {
+        let mut removed_at = self.removed_at.lock().map_err(|_| RegistryError::Poisoned)?;
+        removed_at.entry(group_id.to_string()).or_default().push((user_id.to_string(), epoch));
+        Ok(())
+    }
+
+    /// Was `user_id` a member of `group_id` as of `epoch` - i.e. not yet
+    /// removed by the time the group advanced past it? Checked against the
+    /// removal record first so a member who has since left the live
+    /// roster is still recognized as having had access to earlier epochs.
+    pub fn was_member_at_epoch(&self, group_id: &str, user_id: &str, epoch: u64) -> Result<bool, RegistryError> {
+        let removed_at = self.removed_at.lock().map_err(|_| RegistryError::Poisoned)?;
+        if let Some(removals) = removed_at.get(group_id) {
+            if let Some(&(_, removed_epoch)) = removals.iter().find(|(u, _)| u == user_id) {
+                return Ok(epoch < removed_epoch);
+            }
+        }
+        drop(removed_at);
+
+        let members = self.members.lock().map_err(|_| RegistryError::Poisoned)?;
+        Ok(members.get(group_id).is_some_and(|roster| roster.iter().any(|m| m == user_id)))
+    }
+}
+
+/// Mutex wrapper sharing one `RevocationRegistry` the same way the other
+/// registries here share their state - see `crate::revocation` for the fold
+/// logic and its monotonicity guarantee.
+#[derive(Default)]
+pub struct RevocationTracker {
+    registry: Mutex<RevocationRegistry>,
+}
+
+impl RevocationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, user_id: &str, device_id: &str, revoked_at: Timestamp, reason: &str) -> Result<(), RegistryError> {
+        let mut registry = self.registry.lock().map_err(|_| RegistryError::Poisoned)?;
+        registry.record(user_id, device_id, revoked_at, reason);
+        Ok(())
+    }
+
+    pub fn is_revoked(&self, user_id: &str, device_id: &str) -> Result<bool, RegistryError> {
+        let registry = self.registry.lock().map_err(|_| RegistryError::Poisoned)?;
+        Ok(registry.is_revoked_device(user_id, device_id))
+    }
+
+    pub fn compact(&self, now: Timestamp, max_token_ttl_ms: u64) -> Result<usize, RegistryError> {
+        let mut registry = self.registry.lock().map_err(|_| RegistryError::Poisoned)?;
+        Ok(registry.compact(now, max_token_ttl_ms))
+    }
+}
+
+/// Mutex wrapper sharing one `CapabilityLedger` the same way the other
+/// registries here share their state - see `crate::capability_ledger` for
+/// the replay/reconciliation logic. Unlike a single `AuthToken`'s embedded
+/// capabilities, this reflects grants and revokes from every device that
+/// has published an op, reconciled deterministically regardless of the
+/// order they were received in.
+#[derive(Default)]
+pub struct CapabilityTracker {
+    ledger: Mutex<CapabilityLedger>,
+}
+
+impl CapabilityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_grant(&self, subject: &str, capability: Capability, timestamp: Timestamp, device_id: &str) -> Result<(), RegistryError> {
+        let mut ledger = self.ledger.lock().map_err(|_| RegistryError::Poisoned)?;
+        ledger.record(crate::capability_ledger::CapabilityOp {
+            subject: subject.to_string(),
+            capability,
+            action: crate::capability_ledger::CapabilityAction::Grant,
+            timestamp,
+            device_id: device_id.to_string(),
+        });
+        Ok(())
+    }
+
+    pub fn record_revoke(&self, subject: &str, capability: Capability, timestamp: Timestamp, device_id: &str) -> Result<(), RegistryError> {
+        let mut ledger = self.ledger.lock().map_err(|_| RegistryError::Poisoned)?;
+        ledger.record(crate::capability_ledger::CapabilityOp {
+            subject: subject.to_string(),
+            capability,
+            action: crate::capability_ledger::CapabilityAction::Revoke,
+            timestamp,
+            device_id: device_id.to_string(),
+        });
+        Ok(())
+    }
+
+    pub fn has_capability(&self, subject: &str, capability: &Capability) -> Result<bool, RegistryError> {
+        let ledger = self.ledger.lock().map_err(|_| RegistryError::Poisoned)?;
+        Ok(ledger.has_capability(subject, capability))
+    }
+
+    pub fn checkpoint(&self, watermark: Timestamp, signing_key: &SigningKey) -> Result<(Checkpoint, Vec<u8>), RegistryError> {
+        let mut ledger = self.ledger.lock().map_err(|_| RegistryError::Poisoned)?;
+        Ok(ledger.checkpoint_at(watermark, signing_key))
+    }
+}
+
+/// Thin application-logic facade over the independent registries. Holds an
+/// `Arc` to each so it (and anyone it's cloned to, e.g. a Tauri command
+/// handler) shares the same underlying state without the registries
+/// referencing each other.
+#[derive(Clone)]
+pub struct GardenService {
+    subspaces: Arc<SubspaceRegistry>,
+    topics: Arc<TopicRegistry>,
+    groups: Arc<GroupRegistry>,
+    revocations: Arc<RevocationTracker>,
+    capabilities: Arc<CapabilityTracker>,
+}
+
+impl Default for GardenService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GardenService {
+    pub fn new() -> Self {
+        Self {
+            subspaces: Arc::new(SubspaceRegistry::new()),
+            topics: Arc::new(TopicRegistry::new()),
+            groups: Arc::new(GroupRegistry::new()),
+            revocations: Arc::new(RevocationTracker::new()),
+            capabilities: Arc::new(CapabilityTracker::new()),
+        }
+    }
+
+    pub fn register_subspace(&self, name: &str, subspace: SubspaceId) -> Result<(), RegistryError> {
+        self.subspaces.register(name, subspace)
+    }
+
+    pub fn get_subspace(&self, name: &str) -> Result<Option<SubspaceId>, RegistryError> {
+        self.subspaces.get(name)
+    }
+
+    pub fn subscribe_topic(&self, topic: Topic) -> Result<(), RegistryError> {
+        self.topics.subscribe(topic)
+    }
+
+    pub fn unsubscribe_topic(&self, topic: &Topic) -> Result<(), RegistryError> {
+        self.topics.unsubscribe(topic)
+    }
+
+    pub fn is_subscribed(&self, topic: &Topic) -> Result<bool, RegistryError> {
+        self.topics.is_subscribed(topic)
+    }
+
+    pub fn subscribed_topics(&self) -> Result<Vec<Topic>, RegistryError> {
+        self.topics.subscribed_topics()
+    }
+
+    pub fn join_group(&self, group_id: &str, user_id: &str) -> Result<(), RegistryError> {
+        self.groups.join(group_id, user_id)
+    }
+
+    pub fn leave_group(&self, group_id: &str, user_id: &str) -> Result<(), RegistryError> {
+        self.groups.leave(group_id, user_id)
+    }
+
+    pub fn group_members(&self, group_id: &str) -> Result<Vec<String>, RegistryError> {
+        self.groups.members(group_id)
+    }
+
+    pub fn record_group_member_removal(&self, group_id: &str, user_id: &str, epoch: u64) -> Result<(), RegistryError> {
+        self.groups.record_removal(group_id, user_id, epoch)
+    }
+
+    pub fn was_group_member_at_epoch(&self, group_id: &str, user_id: &str, epoch: u64) -> Result<bool, RegistryError> {
+        self.groups.was_member_at_epoch(group_id, user_id, epoch)
+    }
+
+    /// Record an immediate, expiry-independent revocation of `(user_id,
+    /// device_id)` - e.g. a group owner with `Capability::ManageGroup`
+    /// evicting a compromised device. Takes effect on the next check rather
+    /// than waiting for the device's outstanding `AuthToken`s to expire.
+    pub fn record_revocation(&self, user_id: &str, device_id: &str, revoked_at: Timestamp, reason: &str) -> Result<(), RegistryError> {
+        self.revocations.record(user_id, device_id, revoked_at, reason)
+    }
+
+    pub fn is_device_revoked(&self, user_id: &str, device_id: &str) -> Result<bool, RegistryError> {
+        self.revocations.is_revoked(user_id, device_id)
+    }
+
+    /// Drop revocation records that no longer do any work - see
+    /// `RevocationRegistry::compact`.
+    pub fn compact_revocations(&self, now: Timestamp, max_token_ttl_ms: u64) -> Result<usize, RegistryError> {
+        self.revocations.compact(now, max_token_ttl_ms)
+    }
+
+    /// Publish a capability grant for `subject` from `device_id` into the
+    /// replicated ledger (see `crate::capability_ledger`). Concurrent grants
+    /// and revokes from other devices reconcile deterministically once
+    /// observed, regardless of arrival order.
+    pub fn grant_capability(&self, subject: &str, capability: Capability, timestamp: Timestamp, device_id: &str) -> Result<(), RegistryError> {
+        self.capabilities.record_grant(subject, capability, timestamp, device_id)
+    }
+
+    pub fn revoke_capability(&self, subject: &str, capability: Capability, timestamp: Timestamp, device_id: &str) -> Result<(), RegistryError> {
+        self.capabilities.record_revoke(subject, capability, timestamp, device_id)
+    }
+
+    /// Whether the replicated ledger's effective state for `subject`
+    /// includes `capability` - the authorization layer should consult this
+    /// in addition to (not instead of) whatever capabilities a presented
+    /// `AuthToken` already carries, since the ledger can reflect grants made
+    /// on another of the user's devices that haven't made it into a signed
+    /// token yet.
+    pub fn has_ledger_capability(&self, subject: &str, capability: &Capability) -> Result<bool, RegistryError> {
+        self.capabilities.has_capability(subject, capability)
+    }
+
+    /// Fold the capability ledger up to `watermark` into a signed
+    /// checkpoint, bounding replay cost - see `CapabilityLedger::checkpoint_at`.
+    pub fn checkpoint_capabilities(&self, watermark: Timestamp, signing_key: &SigningKey) -> Result<(Checkpoint, Vec<u8>), RegistryError> {
+        self.capabilities.checkpoint(watermark, signing_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subspace_registry_is_independent_of_topic_registry() {
+        let service = GardenService::new();
+        service.register_subspace("personal", SubspaceId("sub-1".to_string())).unwrap();
+        service.subscribe_topic(Topic::new("garden/test")).unwrap();
+
+        assert_eq!(service.get_subspace("personal").unwrap(), Some(SubspaceId("sub-1".to_string())));
+        assert!(service.is_subscribed(&Topic::new("garden/test")).unwrap());
+        assert_eq!(service.get_subspace("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn unsubscribe_removes_a_topic() {
+        let service = GardenService::new();
+        let topic = Topic::new("garden/test");
+        service.subscribe_topic(topic.clone()).unwrap();
+        service.unsubscribe_topic(&topic).unwrap();
+
+        assert!(!service.is_subscribed(&topic).unwrap());
+        assert!(service.subscribed_topics().unwrap().is_empty());
+    }
+
+    #[test]
+    fn group_membership_tracks_joins_and_leaves() {
+        let service = GardenService::new();
+        service.join_group("garden-1", "user-a").unwrap();
+        service.join_group("garden-1", "user-b").unwrap();
+        assert_eq!(service.group_members("garden-1").unwrap(), vec!["user-a", "user-b"]);
+
+        service.leave_group("garden-1", "user-a").unwrap();
+        assert_eq!(service.group_members("garden-1").unwrap(), vec!["user-b"]);
+    }
+
+    #[test]
+    fn a_removed_member_keeps_access_to_epochs_before_their_removal() {
+        let service = GardenService::new();
+        service.join_group("garden-1", "user-a").unwrap();
+        service.record_group_member_removal("garden-1", "user-a", 3).unwrap();
+
+        assert!(service.was_group_member_at_epoch("garden-1", "user-a", 0).unwrap());
+        assert!(service.was_group_member_at_epoch("garden-1", "user-a", 2).unwrap());
+        assert!(!service.was_group_member_at_epoch("garden-1", "user-a", 3).unwrap());
+        assert!(!service.was_group_member_at_epoch("garden-1", "user-a", 9).unwrap());
+    }
+
+    #[test]
+    fn a_user_who_never_joined_is_not_a_member_of_any_epoch() {
+        let service = GardenService::new();
+        service.join_group("garden-1", "user-a").unwrap();
+
+        assert!(!service.was_group_member_at_epoch("garden-1", "user-z", 0).unwrap());
+    }
+
+    #[test]
+    fn a_recorded_revocation_is_reported_as_revoked() {
+        let service = GardenService::new();
+        assert!(!service.is_device_revoked("user-a", "device-1").unwrap());
+
+        service.record_revocation("user-a", "device-1", 100, "device compromised").unwrap();
+
+        assert!(service.is_device_revoked("user-a", "device-1").unwrap());
+        assert!(!service.is_device_revoked("user-a", "device-2").unwrap());
+    }
+
+    #[test]
+    fn cloning_the_service_shares_the_same_underlying_registries() {
+        let service = GardenService::new();
+        let cloned = service.clone();
+
+        service.register_subspace("shared", SubspaceId("sub-1".to_string())).unwrap();
+        assert_eq!(cloned.get_subspace("shared").unwrap(), Some(SubspaceId("sub-1".to_string())));
+    }
+
+    #[test]
+    fn a_capability_granted_on_one_device_is_visible_through_the_shared_ledger() {
+        let service = GardenService::new();
+        assert!(!service.has_ledger_capability("user-a", &Capability::CreateInvites).unwrap());
+
+        service.grant_capability("user-a", Capability::CreateInvites, 100, "device-1").unwrap();
+        assert!(service.has_ledger_capability("user-a", &Capability::CreateInvites).unwrap());
+
+        service.revoke_capability("user-a", Capability::CreateInvites, 200, "device-2").unwrap();
+        assert!(!service.has_ledger_capability("user-a", &Capability::CreateInvites).unwrap());
+    }
+}
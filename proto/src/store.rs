@@ -0,0 +1,445 @@
+// garden-core/src/store.rs
+//
+// Everything `GardenClient` tracks beyond its own process lifetime -
+// subscribed `Topic`s, subspace name mappings, and encrypted-group session
+// state - otherwise evaporates on `shutdown()`, so `create_garden_client`
+// starts every client from nothing even if it already belongs to gardens
+// and groups. `GardenStore` is the persistence seam: an in-memory
+// implementation for tests and ephemeral clients, and a SQLite-backed one
+// (selected via `GardenConfig::store_backend`) for anything that needs to
+// come back the way it left off.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data::group_backup::GroupKeyRecord;
+use crate::entries::GardenEntry;
+use crate::p2p::Topic;
+use crate::types::SubspaceId;
+
+/// A persisted entry from `GardenClient`'s peer/connection table - enough
+/// to re-dial a recently-seen peer at startup before discovery completes.
+/// `last_seen_unix_secs` is wall-clock time rather than an `Instant`
+/// (which has no stable epoch and can't be serialized).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PeerRecord {
+    pub peer_id: String,
+    pub last_seen_unix_secs: u64,
+    pub topics: Vec<Topic>,
+}
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("store lock was poisoned")]
+    Poisoned,
+
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// Persists the state a `GardenClient` otherwise only holds in memory, so a
+/// restarted client can rejoin its topics and keep decrypting group traffic
+/// on reboot, rather than starting from nothing every time.
+pub trait GardenStore: Send + Sync {
+    /// Record `topic` as subscribed. Idempotent.
+    fn save_topic(&self, topic: &Topic) -> StoreResult<()>;
+    /// Forget a topic, e.g. after `unsubscribe`.
+    fn remove_topic(&self, topic: &Topic) -> StoreResult<()>;
+    /// Every topic currently recorded as subscribed.
+    fn topics(&self) -> StoreResult<Vec<Topic>>;
+
+    /// Record `name` as resolving to `subspace`. Idempotent.
+    fn save_subspace(&self, name: &str, subspace: &SubspaceId) -> StoreResult<()>;
+    /// Every name -> `SubspaceId` mapping currently recorded.
+    fn subspaces(&self) -> StoreResult<HashMap<String, SubspaceId>>;
+
+    /// Persist (or overwrite) a group's current epoch, ratchet secret, and
+    /// sender roster, keyed by `record.group_id`.
+    fn save_group_session(&self, record: &GroupKeyRecord) -> StoreResult<()>;
+    /// Every group session currently recorded.
+    fn group_sessions(&self) -> StoreResult<Vec<GroupKeyRecord>>;
+
+    /// Record the most recent entry seen on `topic`, overwriting any
+    /// previous one.
+    fn save_last_entry(&self, topic: &Topic, entry: &GardenEntry) -> StoreResult<()>;
+    /// The most recent entry recorded for `topic`, if any.
+    fn last_entry(&self, topic: &Topic) -> StoreResult<Option<GardenEntry>>;
+
+    /// Replace the entire persisted peer table with `peers`. Called with
+    /// an already-bounded, already-TTL-pruned snapshot, so this is a
+    /// wholesale replace rather than a per-peer upsert.
+    fn save_peers(&self, peers: &[PeerRecord]) -> StoreResult<()>;
+    /// Every peer currently recorded.
+    fn peers(&self) -> StoreResult<Vec<PeerRecord>>;
+}
+
+/// In-memory `GardenStore` - the default, and what every ephemeral or
+/// test client uses. Nothing survives the process exiting.
+#[derive(Default)]
+pub struct InMemoryStore {
+    topics: Mutex<Vec<Topic>>,
+    subspaces: Mutex<HashMap<String, SubspaceId>>,
+    group_sessions: Mutex<HashMap<String, GroupKeyRecord>>,
+    last_entries: Mutex<HashMap<Topic, GardenEntry>>,
+    peers: Mutex<HashMap<String, PeerRecord>>,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GardenStore for InMemoryStore {
+    fn save_topic(&self, topic: &Topic) -> StoreResult<()> {
+        let mut topics = self.topics.lock().map_err(|_| StoreError::Poisoned)?;
+        if !topics.contains(topic) {
+            topics.push(topic.clone());
+        }
+        Ok(())
+    }
+
+    fn remove_topic(&self, topic: &Topic) -> StoreResult<()> {
+        let mut topics = self.topics.lock().map_err(|_| StoreError::Poisoned)?;
+        topics.retain(|t| t != topic);
+        Ok(())
+    }
+
+    fn topics(&self) -> StoreResult<Vec<Topic>> {
+        let topics = self.topics.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(topics.clone())
+    }
+
+    fn save_subspace(&self, name: &str, subspace: &SubspaceId) -> StoreResult<()> {
+        let mut subspaces = self.subspaces.lock().map_err(|_| StoreError::Poisoned)?;
+        subspaces.insert(name.to_string(), subspace.clone());
+        Ok(())
+    }
+
+    fn subspaces(&self) -> StoreResult<HashMap<String, SubspaceId>> {
+        let subspaces = self.subspaces.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(subspaces.clone())
+    }
+
+    fn save_group_session(&self, record: &GroupKeyRecord) -> StoreResult<()> {
+        let mut sessions = self.group_sessions.lock().map_err(|_| StoreError::Poisoned)?;
+        sessions.insert(record.group_id.clone(), record.clone());
+        Ok(())
+    }
+
+    fn group_sessions(&self) -> StoreResult<Vec<GroupKeyRecord>> {
+        let sessions = self.group_sessions.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(sessions.values().cloned().collect())
+    }
+
+    fn save_last_entry(&self, topic: &Topic, entry: &GardenEntry) -> StoreResult<()> {
+        let mut last_entries = self.last_entries.lock().map_err(|_| StoreError::Poisoned)?;
+        last_entries.insert(topic.clone(), entry.clone());
+        Ok(())
+    }
+
+    fn last_entry(&self, topic: &Topic) -> StoreResult<Option<GardenEntry>> {
+        let last_entries = self.last_entries.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(last_entries.get(topic).cloned())
+    }
+
+    fn save_peers(&self, peers: &[PeerRecord]) -> StoreResult<()> {
+        let mut stored = self.peers.lock().map_err(|_| StoreError::Poisoned)?;
+        *stored = peers.iter().map(|p| (p.peer_id.clone(), p.clone())).collect();
+        Ok(())
+    }
+
+    fn peers(&self) -> StoreResult<Vec<PeerRecord>> {
+        let stored = self.peers.lock().map_err(|_| StoreError::Poisoned)?;
+        Ok(stored.values().cloned().collect())
+    }
+}
+
+/// SQLite-backed `GardenStore`, for clients that need their membership and
+/// group state to survive a restart. `rusqlite::Connection` isn't `Sync`,
+/// so access is serialized behind a `Mutex` like the rest of this crate's
+/// shared state.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open (creating if necessary) a SQLite-backed store at `path`.
+    pub fn open(path: &PathBuf) -> StoreResult<Self> {
+        let conn = Connection::open(path)?;
+        Self::migrate(&conn)?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// An in-memory SQLite database - useful for exercising the SQLite
+    /// codepath in tests without touching the filesystem.
+    pub fn open_in_memory() -> StoreResult<Self> {
+        let conn = Connection::open_in_memory()?;
+        Self::migrate(&conn)?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn migrate(conn: &Connection) -> StoreResult<()> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS topics (name TEXT PRIMARY KEY);
+             CREATE TABLE IF NOT EXISTS subspaces (name TEXT PRIMARY KEY, subspace_id TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS group_sessions (group_id TEXT PRIMARY KEY, record TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS last_entries (topic TEXT PRIMARY KEY, entry TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS peers (peer_id TEXT PRIMARY KEY, last_seen_unix_secs INTEGER NOT NULL, topics TEXT NOT NULL);",
+        )?;
+        Ok(())
+    }
+}
+
+impl GardenStore for SqliteStore {
+    fn save_topic(&self, topic: &Topic) -> StoreResult<()> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO topics (name) VALUES (?1)",
+            [topic.name()],
+        )?;
+        Ok(())
+    }
+
+    fn remove_topic(&self, topic: &Topic) -> StoreResult<()> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        conn.execute("DELETE FROM topics WHERE name = ?1", [topic.name()])?;
+        Ok(())
+    }
+
+    fn topics(&self) -> StoreResult<Vec<Topic>> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut stmt = conn.prepare("SELECT name FROM topics")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut topics = Vec::new();
+        for row in rows {
+            topics.push(Topic::new(&row?));
+        }
+        Ok(topics)
+    }
+
+    fn save_subspace(&self, name: &str, subspace: &SubspaceId) -> StoreResult<()> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO subspaces (name, subspace_id) VALUES (?1, ?2)",
+            rusqlite::params![name, &subspace.0],
+        )?;
+        Ok(())
+    }
+
+    fn subspaces(&self) -> StoreResult<HashMap<String, SubspaceId>> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut stmt = conn.prepare("SELECT name, subspace_id FROM subspaces")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut subspaces = HashMap::new();
+        for row in rows {
+            let (name, subspace_id) = row?;
+            subspaces.insert(name, SubspaceId(subspace_id));
+        }
+        Ok(subspaces)
+    }
+
+    fn save_group_session(&self, record: &GroupKeyRecord) -> StoreResult<()> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        let json = serde_json::to_string(record)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO group_sessions (group_id, record) VALUES (?1, ?2)",
+            rusqlite::params![&record.group_id, &json],
+        )?;
+        Ok(())
+    }
+
+    fn group_sessions(&self) -> StoreResult<Vec<GroupKeyRecord>> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut stmt = conn.prepare("SELECT record FROM group_sessions")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(serde_json::from_str(&row?)?);
+        }
+        Ok(records)
+    }
+
+    fn save_last_entry(&self, topic: &Topic, entry: &GardenEntry) -> StoreResult<()> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        let json = serde_json::to_string(entry)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO last_entries (topic, entry) VALUES (?1, ?2)",
+            rusqlite::params![topic.name(), &json],
+        )?;
+        Ok(())
+    }
+
+    fn last_entry(&self, topic: &Topic) -> StoreResult<Option<GardenEntry>> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut stmt = conn.prepare("SELECT entry FROM last_entries WHERE topic = ?1")?;
+        let mut rows = stmt.query_map([topic.name()], |row| row.get::<_, String>(0))?;
+        match rows.next() {
+            Some(row) => Ok(Some(serde_json::from_str(&row?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_peers(&self, peers: &[PeerRecord]) -> StoreResult<()> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        conn.execute("DELETE FROM peers", [])?;
+        for peer in peers {
+            let topics_json = serde_json::to_string(&peer.topics)?;
+            conn.execute(
+                "INSERT OR REPLACE INTO peers (peer_id, last_seen_unix_secs, topics) VALUES (?1, ?2, ?3)",
+                rusqlite::params![&peer.peer_id, peer.last_seen_unix_secs as i64, &topics_json],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn peers(&self) -> StoreResult<Vec<PeerRecord>> {
+        let conn = self.conn.lock().map_err(|_| StoreError::Poisoned)?;
+        let mut stmt = conn.prepare("SELECT peer_id, last_seen_unix_secs, topics FROM peers")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+        })?;
+        let mut peers = Vec::new();
+        for row in rows {
+            let (peer_id, last_seen_unix_secs, topics_json) = row?;
+            peers.push(PeerRecord {
+                peer_id,
+                last_seen_unix_secs: last_seen_unix_secs as u64,
+                topics: serde_json::from_str(&topics_json)?,
+            });
+        }
+        Ok(peers)
+    }
+}
+
+/// Which `GardenStore` implementation a `GardenConfig` selects. Mirrors the
+/// Tauri backend's `StorageBackend` enum (see `data::willow::StorageBackend`
+/// there), but this one is proto-local and SQLite-specific rather than
+/// sled/Willow-specific, since `garden-core` can't depend on that crate.
+#[derive(Debug, Clone)]
+pub enum StoreBackend {
+    /// Nothing persists past this process - the default, and what every
+    /// test in this crate uses.
+    InMemory,
+    /// Persist to a SQLite database at this path.
+    Sqlite(PathBuf),
+}
+
+impl Default for StoreBackend {
+    fn default() -> Self {
+        StoreBackend::InMemory
+    }
+}
+
+impl StoreBackend {
+    pub fn open(&self) -> StoreResult<Box<dyn GardenStore>> {
+        match self {
+            StoreBackend::InMemory => Ok(Box::new(InMemoryStore::new())),
+            StoreBackend::Sqlite(path) => Ok(Box::new(SqliteStore::open(path)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group_record(group_id: &str, epoch: u64) -> GroupKeyRecord {
+        GroupKeyRecord {
+            group_id: group_id.to_string(),
+            epoch,
+            ratchet_key: vec![epoch as u8; 32],
+            group_public_key: vec![epoch as u8; 32],
+            sender_user_ids: vec!["user-1".to_string()],
+        }
+    }
+
+    #[test]
+    fn in_memory_store_round_trips_topics_subspaces_and_group_sessions() {
+        let store = InMemoryStore::new();
+
+        let topic = Topic::new("garden/group/abc");
+        store.save_topic(&topic).unwrap();
+        store.save_topic(&topic).unwrap();
+        assert_eq!(store.topics().unwrap(), vec![topic.clone()]);
+
+        store.remove_topic(&topic).unwrap();
+        assert!(store.topics().unwrap().is_empty());
+
+        store.save_subspace("personal", &SubspaceId("space-1".to_string())).unwrap();
+        assert_eq!(store.subspaces().unwrap().get("personal").unwrap().0, "space-1");
+
+        store.save_group_session(&group_record("garden-1", 3)).unwrap();
+        let sessions = store.group_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].epoch, 3);
+    }
+
+    #[test]
+    fn sqlite_store_round_trips_topics_subspaces_and_group_sessions() {
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        let topic = Topic::new("garden/group/abc");
+        store.save_topic(&topic).unwrap();
+        assert_eq!(store.topics().unwrap(), vec![topic.clone()]);
+
+        store.save_subspace("personal", &SubspaceId("space-1".to_string())).unwrap();
+        assert_eq!(store.subspaces().unwrap().get("personal").unwrap().0, "space-1");
+
+        store.save_group_session(&group_record("garden-1", 3)).unwrap();
+        let sessions = store.group_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].epoch, 3);
+        assert_eq!(sessions[0].ratchet_key, vec![3u8; 32]);
+    }
+
+    #[test]
+    fn store_backend_defaults_to_in_memory() {
+        assert!(matches!(StoreBackend::default(), StoreBackend::InMemory));
+    }
+
+    #[test]
+    fn in_memory_store_round_trips_peers_and_replaces_the_whole_table() {
+        let store = InMemoryStore::new();
+
+        let peer_a = PeerRecord {
+            peer_id: "peer-a".to_string(),
+            last_seen_unix_secs: 100,
+            topics: vec![Topic::new("garden/group/abc")],
+        };
+        let peer_b = PeerRecord { peer_id: "peer-b".to_string(), last_seen_unix_secs: 200, topics: vec![] };
+
+        store.save_peers(&[peer_a.clone(), peer_b.clone()]).unwrap();
+        let mut peers = store.peers().unwrap();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        assert_eq!(peers, vec![peer_a, peer_b.clone()]);
+
+        // A later save_peers call replaces the table, rather than merging into it.
+        store.save_peers(&[peer_b.clone()]).unwrap();
+        assert_eq!(store.peers().unwrap(), vec![peer_b]);
+    }
+
+    #[test]
+    fn sqlite_store_round_trips_peers() {
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        let peer = PeerRecord {
+            peer_id: "peer-a".to_string(),
+            last_seen_unix_secs: 42,
+            topics: vec![Topic::new("garden/group/abc")],
+        };
+        store.save_peers(&[peer.clone()]).unwrap();
+        assert_eq!(store.peers().unwrap(), vec![peer]);
+    }
+}
@@ -0,0 +1,154 @@
+// garden-core/src/token_store.rs
+//
+// Lets an issuer (or the user themselves) take back one issued `AuthToken`
+// by its `id` before `expires_at`, without touching the device it was
+// issued to - unlike `crate::revocation::RevocationRegistry`, which revokes
+// an entire `(user_id, device_id)` pair. Mirrors `crate::store`'s
+// storage-behind-a-trait approach: an in-memory default for tests and
+// ephemeral clients, with room for a persistent (sled/sqlite) backend later.
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::auth::AuthToken;
+
+#[derive(Error, Debug)]
+pub enum TokenStoreError {
+    #[error("token store lock was poisoned")]
+    Poisoned,
+}
+
+pub type TokenStoreResult<T> = Result<T, TokenStoreError>;
+
+/// Published on `GardenClient::create_token_revocation_topic` when a token
+/// is revoked, so peers holding a cached copy of it can drop it instead of
+/// trusting it until it expires on its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenRevocationNotice {
+    pub token_id: String,
+}
+
+/// Persists issued `AuthToken`s and which of their ids have since been
+/// revoked.
+pub trait TokenStore: Send + Sync {
+    /// Record `token` as issued. Overwrites any previous token with the
+    /// same `id`.
+    fn insert(&self, token: &AuthToken) -> TokenStoreResult<()>;
+    /// The token recorded under `id`, if any.
+    fn get(&self, id: &str) -> TokenStoreResult<Option<AuthToken>>;
+    /// Mark `id` as revoked. Idempotent; doesn't require the token to have
+    /// been `insert`ed first, since a revocation notice from a peer may
+    /// arrive before (or instead of) ever seeing the token itself.
+    fn revoke(&self, id: &str) -> TokenStoreResult<()>;
+    /// Whether `id` has been revoked.
+    fn is_revoked(&self, id: &str) -> TokenStoreResult<bool>;
+    /// Every token on record for `user_id`, e.g. for an admin auditing what
+    /// they could still revoke.
+    fn list_for_user(&self, user_id: &str) -> TokenStoreResult<Vec<AuthToken>>;
+}
+
+/// In-memory `TokenStore` - the default, and what every ephemeral or test
+/// client uses. Nothing survives the process exiting.
+#[derive(Default)]
+pub struct InMemoryTokenStore {
+    tokens: Mutex<HashMap<String, AuthToken>>,
+    revoked: Mutex<HashSet<String>>,
+}
+
+impl InMemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for InMemoryTokenStore {
+    fn insert(&self, token: &AuthToken) -> TokenStoreResult<()> {
+        let mut tokens = self.tokens.lock().map_err(|_| TokenStoreError::Poisoned)?;
+        tokens.insert(token.id.clone(), token.clone());
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> TokenStoreResult<Option<AuthToken>> {
+        let tokens = self.tokens.lock().map_err(|_| TokenStoreError::Poisoned)?;
+        Ok(tokens.get(id).cloned())
+    }
+
+    fn revoke(&self, id: &str) -> TokenStoreResult<()> {
+        let mut revoked = self.revoked.lock().map_err(|_| TokenStoreError::Poisoned)?;
+        revoked.insert(id.to_string());
+        Ok(())
+    }
+
+    fn is_revoked(&self, id: &str) -> TokenStoreResult<bool> {
+        let revoked = self.revoked.lock().map_err(|_| TokenStoreError::Poisoned)?;
+        Ok(revoked.contains(id))
+    }
+
+    fn list_for_user(&self, user_id: &str) -> TokenStoreResult<Vec<AuthToken>> {
+        let tokens = self.tokens.lock().map_err(|_| TokenStoreError::Poisoned)?;
+        Ok(tokens.values().filter(|t| t.user_id == user_id).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Capability;
+
+    fn token(id: &str, user_id: &str) -> AuthToken {
+        AuthToken {
+            id: id.to_string(),
+            user_id: user_id.to_string(),
+            device_id: "device-1".to_string(),
+            capabilities: vec![Capability::CreateInvites],
+            signature: None,
+            expires_at: u64::MAX,
+            mfa_verified_until: None,
+            mfa_verified_at: None,
+            mfa_factor: None,
+            proof: None,
+            device_key_certificate: None,
+        }
+    }
+
+    #[test]
+    fn a_token_is_retrievable_after_insert_and_not_revoked_by_default() {
+        let store = InMemoryTokenStore::new();
+        store.insert(&token("t1", "user-a")).unwrap();
+
+        assert_eq!(store.get("t1").unwrap().unwrap().user_id, "user-a");
+        assert!(!store.is_revoked("t1").unwrap());
+    }
+
+    #[test]
+    fn revoking_a_token_marks_it_revoked_without_removing_it() {
+        let store = InMemoryTokenStore::new();
+        store.insert(&token("t1", "user-a")).unwrap();
+
+        store.revoke("t1").unwrap();
+
+        assert!(store.is_revoked("t1").unwrap());
+        assert!(store.get("t1").unwrap().is_some());
+    }
+
+    #[test]
+    fn revoking_an_id_the_store_never_saw_still_takes_effect() {
+        let store = InMemoryTokenStore::new();
+        store.revoke("never-inserted").unwrap();
+        assert!(store.is_revoked("never-inserted").unwrap());
+    }
+
+    #[test]
+    fn list_for_user_only_returns_that_users_tokens() {
+        let store = InMemoryTokenStore::new();
+        store.insert(&token("t1", "user-a")).unwrap();
+        store.insert(&token("t2", "user-a")).unwrap();
+        store.insert(&token("t3", "user-b")).unwrap();
+
+        let mut ids: Vec<String> = store.list_for_user("user-a").unwrap().into_iter().map(|t| t.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["t1".to_string(), "t2".to_string()]);
+    }
+}
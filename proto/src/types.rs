@@ -3,7 +3,11 @@ use serde::{Serialize, Deserialize};
 use std::cmp::{PartialEq, Eq, PartialOrd, Ord};
 use std::hash::Hash;
 
-pub type Timestamp = i64;
+/// A Hybrid Logical Clock timestamp: a 48-bit physical-time-in-ms component and
+/// a 16-bit logical counter packed into a `u64`. See [`crate::clock::HybridLogicalClock`]
+/// for how values are produced; the packing keeps existing serde/ordering code working
+/// unchanged since `Timestamp` still compares and serializes as a plain integer.
+pub type Timestamp = u64;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MessageType {
@@ -64,7 +68,7 @@ pub struct AttachmentMetadata {
     pub thumbnail: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SubspaceId(pub String);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
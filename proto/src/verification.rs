@@ -0,0 +1,178 @@
+// garden-core/src/verification.rs
+//
+// Wires `identity::verify`'s SAS protocol onto `GardenClient`: offers are
+// exchanged over a dedicated per-peer `Topic` (see
+// `GardenClient::create_verification_topic`), and a session that both
+// sides confirm turns into a `VerifiedDevice` record - a trust signal kept
+// independent of `Capability`, since a device can hold every capability it
+// wants and still never have had its key confirmed out of band.
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use ed25519_dalek::{SigningKey, Signer};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use crate::identity::verify::{Sas, VerificationError};
+use crate::identity::verify::VerificationSession;
+use crate::types::Timestamp;
+
+#[derive(Debug, Error)]
+pub enum TrustStoreError {
+    #[error("verified-device store lock was poisoned")]
+    Poisoned,
+}
+
+/// What one side of a SAS exchange hands the other, out-of-band, to kick
+/// off (or respond to) verification - the ephemeral public key and the
+/// commitment to it, plus the transaction id both sides must agree on.
+/// Analogous to a group's `key_package`, until there's a real transport to
+/// carry it over `create_verification_topic` automatically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationOffer {
+    pub transaction_id: String,
+    pub ephemeral_public: [u8; 32],
+    pub commitment: [u8; 32],
+}
+
+/// Sent over `create_verification_topic` once a user confirms the SAS
+/// matches out of band - the MAC from `VerificationHandle::confirm` that
+/// the peer's `VerificationHandle::finish` checks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationMac {
+    pub transaction_id: String,
+    pub mac: [u8; 32],
+}
+
+/// Sent over `create_verification_topic` when a user backs out of a
+/// verification in progress (the emojis didn't match, or they simply gave
+/// up), so the peer's handle can be cancelled instead of waiting
+/// indefinitely on a confirmation that will never arrive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationCancel {
+    pub transaction_id: String,
+}
+
+/// A long-term key that has passed SAS verification, recorded independent
+/// of any `Capability` grant.
+#[derive(Debug, Clone)]
+pub struct VerifiedDevice {
+    pub public_key: Vec<u8>,
+    /// Our signature over `public_key`, attesting that we verified it.
+    pub attestation_signature: Vec<u8>,
+    pub verified_at: Timestamp,
+}
+
+/// Per-client store of devices that have passed SAS verification.
+#[derive(Default)]
+pub struct TrustStore {
+    verified: RwLock<HashMap<Vec<u8>, VerifiedDevice>>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, device: VerifiedDevice) -> Result<(), TrustStoreError> {
+        let mut verified = self.verified.write().map_err(|_| TrustStoreError::Poisoned)?;
+        verified.insert(device.public_key.clone(), device);
+        Ok(())
+    }
+
+    pub fn is_verified(&self, public_key: &[u8]) -> Result<bool, TrustStoreError> {
+        let verified = self.verified.read().map_err(|_| TrustStoreError::Poisoned)?;
+        Ok(verified.contains_key(public_key))
+    }
+
+    pub fn get(&self, public_key: &[u8]) -> Result<Option<VerifiedDevice>, TrustStoreError> {
+        let verified = self.verified.read().map_err(|_| TrustStoreError::Poisoned)?;
+        Ok(verified.get(public_key).cloned())
+    }
+}
+
+/// One in-progress SAS verification with `peer_user_id`, returned by
+/// `GardenClient::start_verification`/`accept_verification`. Carries the
+/// session between the key exchange and the user's out-of-band emoji
+/// comparison, through to the mutual-confirm step.
+pub struct VerificationHandle {
+    pub peer_user_id: String,
+    our_offer: VerificationOffer,
+    session: VerificationSession,
+    sas: Option<Sas>,
+    cancelled: bool,
+}
+
+impl VerificationHandle {
+    pub(crate) fn new(
+        peer_user_id: String,
+        session: VerificationSession,
+        our_offer: VerificationOffer,
+    ) -> Self {
+        Self { peer_user_id, our_offer, session, sas: None, cancelled: false }
+    }
+
+    /// Our half of the exchange, to hand the peer out-of-band (e.g. over
+    /// `create_verification_topic`) if they haven't already sent us theirs.
+    pub fn our_offer(&self) -> &VerificationOffer {
+        &self.our_offer
+    }
+
+    /// The SAS to compare out of band, once `accept` has consumed the
+    /// peer's offer. `None` before that.
+    pub fn sas(&self) -> Option<&Sas> {
+        self.sas.as_ref()
+    }
+
+    /// Consume the peer's offer, completing the ECDH and deriving the SAS.
+    /// The commitment check here is what stops either side from choosing
+    /// its ephemeral key after seeing the other's.
+    pub fn accept(&mut self, peer_offer: VerificationOffer) -> Result<Sas, VerificationError> {
+        if self.cancelled {
+            return Err(VerificationError::InvalidState);
+        }
+        let remote_ephemeral_public = peer_offer.ephemeral_public.into();
+        let sas = self.session.accept_remote_key(remote_ephemeral_public, peer_offer.commitment)?;
+        self.sas = Some(sas.clone());
+        Ok(sas)
+    }
+
+    /// The user confirmed the SAS matches out of band: compute our MAC
+    /// over the attested long-term keys. Send the result to the peer;
+    /// they feed it into their own `finish`.
+    pub fn confirm(&mut self) -> Result<[u8; 32], VerificationError> {
+        if self.cancelled {
+            return Err(VerificationError::InvalidState);
+        }
+        self.session.confirm_match()
+    }
+
+    /// The user reported the emojis don't match (or simply backed out).
+    /// Any later `confirm`/`finish` call on this handle fails.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    /// Check the peer's MAC (from their own `confirm`) and, on success,
+    /// sign their long-term key to produce the attestation that
+    /// `GardenClient::start_verification`'s caller should hand to its
+    /// `TrustStore`.
+    pub fn finish(
+        &mut self,
+        peer_mac: &[u8; 32],
+        signing_key: &SigningKey,
+    ) -> Result<VerifiedDevice, VerificationError> {
+        if self.cancelled {
+            return Err(VerificationError::InvalidState);
+        }
+        let trust = self.session.verify_peer_mac(peer_mac)?;
+
+        let attestation_signature = signing_key.sign(&trust.subject_public_key).to_bytes().to_vec();
+
+        Ok(VerifiedDevice {
+            public_key: trust.subject_public_key,
+            attestation_signature,
+            verified_at: trust.verified_at,
+        })
+    }
+}
@@ -157,6 +157,7 @@ fn test_direct_message_authorization() {
     
     // Create Alice's token with write capability
     let mut alice_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: alice_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -165,12 +166,18 @@ fn test_direct_message_authorization() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     alice_token.sign(&alice_signing_key);
     
     // Create Bob's token with read capability for Alice's messages
     let mut bob_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: bob_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -178,6 +185,11 @@ fn test_direct_message_authorization() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     bob_token.sign(&alice_signing_key); // In reality would be signed by its own key
@@ -208,6 +220,7 @@ fn test_direct_message_authorization() {
     // Create a token for a third user without specific permissions
     let (charlie_identity, charlie_signing_key) = Identity::generate_identity();
     let mut charlie_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: charlie_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -216,6 +229,11 @@ fn test_direct_message_authorization() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     charlie_token.sign(&charlie_signing_key);
@@ -254,6 +272,7 @@ fn test_group_message_authorization() {
     
     // Create owner's token with management capability
     let mut owner_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: owner_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -263,12 +282,18 @@ fn test_group_message_authorization() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     owner_token.sign(&owner_signing_key);
     
     // Create member's token with read/write capabilities
     let mut member_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: member_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -277,12 +302,18 @@ fn test_group_message_authorization() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     member_token.sign(&owner_signing_key); // In reality would be signed by the group admin
     
     // Create non-member's token without group capabilities
     let mut non_member_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: non_member_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -291,6 +322,11 @@ fn test_group_message_authorization() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     non_member_token.sign(&owner_signing_key);
@@ -305,6 +341,7 @@ fn test_group_message_authorization() {
         timestamp: now,
         message_type: MessageType::Text,
         attachments: vec![],
+        epoch: 0,
     };
     
     // Test if owner can create and access the message
@@ -325,6 +362,7 @@ fn test_group_message_authorization() {
         timestamp: now,
         message_type: MessageType::Text,
         attachments: vec![],
+        epoch: 0,
     };
     
     // Test if member can create and access the message
@@ -345,6 +383,7 @@ fn test_group_message_authorization() {
         timestamp: now,
         message_type: MessageType::Text,
         attachments: vec![],
+        epoch: 0,
     };
     
     let non_member_create = AccessControlService::can_create_entry(&non_member_token, &non_member_fake_message, now);
@@ -382,6 +421,7 @@ fn test_token_expiry() {
     
     // Create an expired token
     let mut expired_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -390,6 +430,11 @@ fn test_token_expiry() {
         ],
         signature: None,
         expires_at: expired_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     expired_token.sign(&signing_key);
@@ -33,11 +33,17 @@ async fn test_auth_token_lifecycle() {
     
     // Create and sign auth token
     let mut token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity.user_id.clone(),
         device_id: device_id.clone(),
         capabilities: capabilities.clone(),
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     // Sign the token
@@ -82,11 +88,17 @@ async fn test_auth_with_entries() {
     let expires_at = now + 3600;
     
     let mut token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity.user_id.clone(),
         device_id: device_id.clone(),
         capabilities: capabilities.clone(),
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     // Sign the token
@@ -104,6 +116,7 @@ async fn test_auth_with_entries() {
         timestamp: now,
         message_type: garden_core::types::MessageType::Text,
         attachments: vec![],
+        epoch: 0,
     };
     
     // Test with direct message entry - should have access
@@ -117,7 +130,7 @@ async fn test_auth_with_entries() {
         message_type: garden_core::types::MessageType::Text,
         attachments: vec![],
     };
-    
+
     // This test would typically call a function that checks if the token authorizes operations
     // on these entries. Since we don't have a direct function for this in the codebase,
     // we'll just verify the capabilities exist that would be required.
@@ -147,11 +160,17 @@ async fn test_auth_with_p2p() {
     let expires_at = now + 3600;
     
     let mut token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity.user_id.clone(),
         device_id: device_id.clone(),
         capabilities: capabilities.clone(),
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     // Sign the token
@@ -212,11 +231,17 @@ async fn test_auth_with_encrypted_group() {
     let expires_at = now + 3600;
     
     let mut token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity.user_id.clone(),
         device_id: device_id.clone(),
         capabilities: capabilities.clone(),
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     // Sign the token
@@ -259,11 +284,17 @@ async fn test_auth_with_encrypted_group() {
     ];
     
     let mut token2 = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity2.user_id.clone(),
         device_id: uuid::Uuid::new_v4().to_string(),
         capabilities: capabilities2,
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     // Sign the second token
@@ -21,6 +21,7 @@ fn test_auth_with_path_validation() {
     
     // Alice's token grants access to her own profile paths and group data
     let mut alice_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: alice_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -34,12 +35,18 @@ fn test_auth_with_path_validation() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     alice_token.sign(&alice_signing_key);
     
     // Bob's token only grants access to his own profile paths
     let mut bob_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: bob_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -49,6 +56,11 @@ fn test_auth_with_path_validation() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     bob_token.sign(&alice_signing_key); // In reality would be signed by Bob's key
@@ -131,6 +143,7 @@ fn test_auth_with_direct_message_paths() {
     
     // Alice's token grants access to messages with Bob
     let mut alice_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: alice_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -140,6 +153,11 @@ fn test_auth_with_direct_message_paths() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     alice_token.sign(&alice_signing_key);
@@ -198,6 +216,7 @@ fn test_auth_with_group_message_paths() {
     
     // Owner's token with group management capability
     let mut owner_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: owner_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -207,12 +226,18 @@ fn test_auth_with_group_message_paths() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     owner_token.sign(&owner_signing_key);
     
     // Member's token with read/write but not manage capability
     let mut member_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: member_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -221,12 +246,18 @@ fn test_auth_with_group_message_paths() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     member_token.sign(&owner_signing_key); // In reality would be signed by member's key
     
     // Non-member's token with no group capabilities
     let mut non_member_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: non_member_identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -234,6 +265,11 @@ fn test_auth_with_group_message_paths() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     non_member_token.sign(&owner_signing_key);
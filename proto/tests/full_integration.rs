@@ -29,11 +29,17 @@ async fn create_test_user(
     let expires_at = now + 3600;
     
     let mut token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities,
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     // Sign token
@@ -129,6 +135,7 @@ async fn test_group_chat_scenario() {
         timestamp: now,
         message_type: MessageType::Text,
         attachments: vec![],
+        epoch: 0,
     };
     
     // 10. Verify that member 1's token authorizes them to create this message
@@ -257,12 +264,18 @@ async fn test_token_lifecycle() {
         let expires_at = now + 5; // 5 seconds
         
         let mut token = AuthToken {
+            id: uuid::Uuid::new_v4().to_string(),
             user_id: identity.user_id.clone(),
             device_id: Uuid::new_v4().to_string(),
             capabilities,
             signature: None,
             expires_at,
-        };
+                mfa_verified_until: None,
+                mfa_verified_at: None,
+                mfa_factor: None,
+                proof: None,
+                device_key_certificate: None,
+            };
         
         // Sign token
         token.sign(&signing_key);
@@ -287,6 +300,7 @@ async fn test_token_lifecycle() {
     let expires_at = now + 3600; // 1 hour
     
     let mut refreshed_token = AuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
         user_id: identity.user_id.clone(),
         device_id: Uuid::new_v4().to_string(),
         capabilities: vec![
@@ -297,6 +311,11 @@ async fn test_token_lifecycle() {
         ],
         signature: None,
         expires_at,
+        mfa_verified_until: None,
+        mfa_verified_at: None,
+        mfa_factor: None,
+        proof: None,
+        device_key_certificate: None,
     };
     
     // Sign refreshed token
@@ -0,0 +1,96 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::data::schema::Timestamp;
+
+/// Bits reserved for the logical counter in a packed HLC timestamp.
+const COUNTER_BITS: u32 = 16;
+const COUNTER_MASK: u64 = (1 << COUNTER_BITS) - 1;
+
+/// Default bound (ms) on how far a remote physical-time component may exceed
+/// our own before it's treated as clock-skew abuse rather than legitimate drift.
+pub const DEFAULT_MAX_DRIFT_MS: u64 = 60_000;
+
+/// A Hybrid Logical Clock: a monotonically increasing `(physical, counter)` pair
+/// packed into a single `u64` so it serializes as a plain [`Timestamp`]. Mirrors
+/// `garden_core::clock::HybridLogicalClock` so entries written from the Tauri
+/// side sort consistently with entries authored over the P2P layer.
+#[derive(Debug, Clone)]
+pub struct HybridLogicalClock {
+    physical: u64,
+    counter: u16,
+    max_drift_ms: u64,
+}
+
+impl Default for HybridLogicalClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HybridLogicalClock {
+    pub fn new() -> Self {
+        Self {
+            physical: 0,
+            counter: 0,
+            max_drift_ms: DEFAULT_MAX_DRIFT_MS,
+        }
+    }
+
+    fn physical_now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis() as u64
+    }
+
+    fn pack(physical: u64, counter: u16) -> Timestamp {
+        (physical << COUNTER_BITS) | (counter as u64 & COUNTER_MASK)
+    }
+
+    pub fn unpack(ts: Timestamp) -> (u64, u16) {
+        (ts >> COUNTER_BITS, (ts & COUNTER_MASK) as u16)
+    }
+
+    /// Advance the clock for a local write and return its timestamp.
+    pub fn local_event(&mut self) -> Timestamp {
+        let pt = Self::physical_now();
+        let new_physical = self.physical.max(pt);
+        self.counter = if new_physical == self.physical {
+            self.counter + 1
+        } else {
+            0
+        };
+        self.physical = new_physical;
+        Self::pack(self.physical, self.counter)
+    }
+
+    /// One-shot timestamp for call sites that don't hold onto clock state
+    /// across writes.
+    pub fn now() -> Timestamp {
+        Self::new().local_event()
+    }
+
+    /// Merge a timestamp observed on an incoming entry, rejecting remote
+    /// physical times that outrun ours by more than `max_drift_ms`.
+    pub fn observe(&mut self, remote: Timestamp) -> Option<Timestamp> {
+        let (remote_physical, remote_counter) = Self::unpack(remote);
+        let pt = Self::physical_now();
+
+        if remote_physical > pt && remote_physical - pt > self.max_drift_ms {
+            return None;
+        }
+
+        let new_physical = self.physical.max(remote_physical).max(pt);
+        self.counter = if new_physical == self.physical && new_physical == remote_physical {
+            self.counter.max(remote_counter) + 1
+        } else if new_physical == self.physical {
+            self.counter + 1
+        } else if new_physical == remote_physical {
+            remote_counter + 1
+        } else {
+            0
+        };
+        self.physical = new_physical;
+        Some(Self::pack(self.physical, self.counter))
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::data::clock::HybridLogicalClock;
+use crate::data::schema::{
+    AttachmentRef, CommandVisibility, DirectMessage, GroupMessage, MessageType, SlashCommand,
+    Timestamp,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How far a received interaction's timestamp may drift from the handler's
+/// clock before `verify_interaction` treats it as a replay rather than a
+/// fresh interaction.
+const FRESHNESS_WINDOW_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("no command registered for /{0}")]
+    UnknownCommand(String),
+
+    #[error("/{0} is not visible in this context")]
+    NotVisible(String),
+
+    #[error("rate limit exceeded for /{0}, retry after {1:?}")]
+    RateLimited(String, Duration),
+
+    #[error("webhook request failed: {0}")]
+    Webhook(String),
+
+    #[error("interaction timestamp outside the freshness window")]
+    StaleResponse,
+}
+
+/// JSON payload POSTed to `handler_url` when a command fires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionPayload {
+    pub command: String,
+    pub arguments: Vec<String>,
+    pub user_id: String,
+    pub garden_id: Option<String>,
+    pub timestamp: Timestamp,
+}
+
+/// Structured response a handler returns for an interaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionResponse {
+    /// `true` if the reply should only be visible to the invoking user.
+    pub ephemeral: bool,
+    pub content: String,
+    #[serde(default)]
+    pub attachments: Vec<AttachmentRef>,
+}
+
+/// The outcome of dispatching a command: either a message ready to route
+/// through the normal encrypted send path, or an ephemeral reply that never
+/// becomes a persisted `GardenEntry`.
+pub enum DispatchOutcome {
+    Direct(DirectMessage),
+    Group(GroupMessage),
+    Ephemeral(String),
+}
+
+/// Minimal outbound HTTP seam so the dispatcher can be tested without a
+/// network: production wires this to a real HTTP client, tests wire it to a
+/// closure/fake.
+pub trait WebhookClient {
+    fn post_interaction(
+        &self,
+        handler_url: &str,
+        body: &[u8],
+        signature: &str,
+        timestamp: Timestamp,
+    ) -> Result<InteractionResponse, CommandError>;
+}
+
+/// Per-command token bucket: one token refills per `interval`, capped at `burst`.
+struct RateLimiter {
+    burst: u32,
+    interval: Duration,
+    buckets: HashMap<String, (u32, Timestamp)>,
+}
+
+impl RateLimiter {
+    fn new(burst: u32, interval: Duration) -> Self {
+        Self {
+            burst,
+            interval,
+            buckets: HashMap::new(),
+        }
+    }
+
+    fn check(&mut self, command: &str) -> Result<(), CommandError> {
+        let now = HybridLogicalClock::now();
+        let (physical_now, _) = crate::data::clock::HybridLogicalClock::unpack(now);
+        let entry = self.buckets.entry(command.to_string()).or_insert((self.burst, now));
+        let (physical_last, _) = crate::data::clock::HybridLogicalClock::unpack(entry.1);
+
+        let elapsed_ms = physical_now.saturating_sub(physical_last);
+        let refilled = (elapsed_ms / self.interval.as_millis().max(1) as u64) as u32;
+        entry.0 = (entry.0 + refilled).min(self.burst);
+        // Only restart the refill clock once a token has actually been
+        // credited, so calls closer together than `interval` still refill.
+        if refilled > 0 {
+            entry.1 = now;
+        }
+
+        if entry.0 == 0 {
+            return Err(CommandError::RateLimited(command.to_string(), self.interval));
+        }
+        entry.0 -= 1;
+        Ok(())
+    }
+}
+
+/// Registry of `SlashCommand`s, persisted through `ToEntry`/Willow so
+/// commands survive restarts and sync between peers.
+pub struct CommandRegistry {
+    commands: HashMap<String, SlashCommand>,
+    limiter: RateLimiter,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self {
+            commands: HashMap::new(),
+            limiter: RateLimiter::new(5, Duration::from_secs(10)),
+        }
+    }
+
+    /// Register (or update) a command. Callers are expected to also persist
+    /// `command.to_entry()` through the Willow store.
+    pub fn register(&mut self, command: SlashCommand) {
+        self.commands.insert(command.command.clone(), command);
+    }
+
+    /// Resolve user input (e.g. `/roll 2d6`) against the registry, honoring
+    /// `CommandVisibility` and `garden_id` scoping.
+    fn resolve(
+        &self,
+        name: &str,
+        invoking_garden_id: Option<&str>,
+    ) -> Result<&SlashCommand, CommandError> {
+        let command = self
+            .commands
+            .get(name)
+            .ok_or_else(|| CommandError::UnknownCommand(name.to_string()))?;
+
+        let visible = match command.visibility {
+            CommandVisibility::Public => true,
+            CommandVisibility::Private => invoking_garden_id.is_none(),
+            CommandVisibility::Garden => {
+                invoking_garden_id.is_some() && command.garden_id.as_deref() == invoking_garden_id
+            }
+        };
+
+        if !visible {
+            return Err(CommandError::NotVisible(name.to_string()));
+        }
+
+        Ok(command)
+    }
+
+    /// Parse `/name arg1 arg2`, resolve it, rate-limit it, sign and POST the
+    /// interaction, and turn the response into a message ready to send.
+    pub fn dispatch(
+        &mut self,
+        input: &str,
+        user_id: &str,
+        garden_id: Option<&str>,
+        client: &impl WebhookClient,
+    ) -> Result<DispatchOutcome, CommandError> {
+        let mut parts = input.trim_start_matches('/').split_whitespace();
+        let name = parts.next().unwrap_or_default().to_string();
+        let arguments: Vec<String> = parts.map(str::to_string).collect();
+
+        let command = self.resolve(&name, garden_id)?.clone();
+        self.limiter.check(&name)?;
+
+        let timestamp = HybridLogicalClock::now();
+        let payload = InteractionPayload {
+            command: name.clone(),
+            arguments,
+            user_id: user_id.to_string(),
+            garden_id: garden_id.map(str::to_string),
+            timestamp,
+        };
+        let body = serde_json::to_vec(&payload).map_err(|e| CommandError::Webhook(e.to_string()))?;
+        let signature = sign_interaction(&command.bot_token, &body, timestamp);
+
+        let response = client.post_interaction(&command.handler_url, &body, &signature, timestamp)?;
+
+        if response.ephemeral {
+            return Ok(DispatchOutcome::Ephemeral(response.content));
+        }
+
+        if let Some(garden_id) = garden_id {
+            Ok(DispatchOutcome::Group(GroupMessage {
+                garden_id: garden_id.to_string(),
+                sender_id: user_id.to_string(),
+                encrypted_content: response.content.into_bytes(),
+                timestamp: HybridLogicalClock::now(),
+                message_type: MessageType::Text,
+                attachments: response.attachments,
+            }))
+        } else {
+            Ok(DispatchOutcome::Direct(DirectMessage {
+                sender_id: user_id.to_string(),
+                recipient_id: user_id.to_string(),
+                thread_id: format!("command/{}", name),
+                encrypted_content: response.content.into_bytes(),
+                timestamp: HybridLogicalClock::now(),
+                message_type: MessageType::Text,
+                attachments: response.attachments,
+            }))
+        }
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// HMAC-SHA256 over `body || timestamp`, keyed from `bot_token`, so the
+/// remote handler can both authenticate the request and reject replays
+/// outside its own freshness window.
+fn sign_interaction(bot_token: &Option<String>, body: &[u8], timestamp: Timestamp) -> String {
+    let key = bot_token.clone().unwrap_or_default();
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.update(&timestamp.to_be_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Verify a received interaction's signature and freshness - the mirror
+/// image of `sign_interaction`, run on the handler side of the webhook.
+pub fn verify_interaction(
+    bot_token: &str,
+    body: &[u8],
+    signature: &str,
+    timestamp: Timestamp,
+    now: Timestamp,
+) -> Result<(), CommandError> {
+    let (ts_physical, _) = crate::data::clock::HybridLogicalClock::unpack(timestamp);
+    let (now_physical, _) = crate::data::clock::HybridLogicalClock::unpack(now);
+    if now_physical.abs_diff(ts_physical) > FRESHNESS_WINDOW_MS {
+        return Err(CommandError::StaleResponse);
+    }
+
+    let expected = sign_interaction(&Some(bot_token.to_string()), body, timestamp);
+    if expected != signature {
+        return Err(CommandError::Webhook("signature mismatch".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct FakeClient {
+        response: InteractionResponse,
+        calls: RefCell<Vec<(String, String)>>,
+    }
+
+    impl WebhookClient for FakeClient {
+        fn post_interaction(
+            &self,
+            handler_url: &str,
+            _body: &[u8],
+            signature: &str,
+            _timestamp: Timestamp,
+        ) -> Result<InteractionResponse, CommandError> {
+            self.calls
+                .borrow_mut()
+                .push((handler_url.to_string(), signature.to_string()));
+            Ok(self.response.clone())
+        }
+    }
+
+    fn sample_command(visibility: CommandVisibility, garden_id: Option<&str>) -> SlashCommand {
+        SlashCommand {
+            command: "roll".to_string(),
+            description: None,
+            handler_url: "https://example.com/roll".to_string(),
+            visibility,
+            creator_id: "creator".to_string(),
+            garden_id: garden_id.map(str::to_string),
+            timestamp: HybridLogicalClock::now(),
+            bot_token: Some("shared-secret".to_string()),
+        }
+    }
+
+    #[test]
+    fn public_command_dispatches_and_routes_as_group_message() {
+        let mut registry = CommandRegistry::new();
+        registry.register(sample_command(CommandVisibility::Public, None));
+
+        let client = FakeClient {
+            response: InteractionResponse {
+                ephemeral: false,
+                content: "you rolled a 4".to_string(),
+                attachments: vec![],
+            },
+            calls: RefCell::new(Vec::new()),
+        };
+
+        let outcome = registry
+            .dispatch("/roll 2d6", "user-1", Some("garden-1"), &client)
+            .unwrap();
+
+        match outcome {
+            DispatchOutcome::Group(msg) => {
+                assert_eq!(msg.garden_id, "garden-1");
+                assert_eq!(String::from_utf8(msg.encrypted_content).unwrap(), "you rolled a 4");
+            }
+            _ => panic!("expected a group message"),
+        }
+        assert_eq!(client.calls.borrow().len(), 1);
+    }
+
+    #[test]
+    fn garden_scoped_command_rejected_outside_its_garden() {
+        let mut registry = CommandRegistry::new();
+        registry.register(sample_command(CommandVisibility::Garden, Some("garden-1")));
+
+        let client = FakeClient {
+            response: InteractionResponse {
+                ephemeral: true,
+                content: "nope".to_string(),
+                attachments: vec![],
+            },
+            calls: RefCell::new(Vec::new()),
+        };
+
+        let result = registry.dispatch("/roll", "user-1", Some("garden-2"), &client);
+        assert!(matches!(result, Err(CommandError::NotVisible(_))));
+    }
+
+    #[test]
+    fn rate_limit_kicks_in_after_burst() {
+        let mut registry = CommandRegistry::new();
+        registry.register(sample_command(CommandVisibility::Public, None));
+        let client = FakeClient {
+            response: InteractionResponse {
+                ephemeral: true,
+                content: "ok".to_string(),
+                attachments: vec![],
+            },
+            calls: RefCell::new(Vec::new()),
+        };
+
+        for _ in 0..5 {
+            registry.dispatch("/roll", "user-1", None, &client).unwrap();
+        }
+        let result = registry.dispatch("/roll", "user-1", None, &client);
+        assert!(matches!(result, Err(CommandError::RateLimited(_, _))));
+    }
+
+    #[test]
+    fn signed_interaction_round_trips_through_verify() {
+        let body = b"{\"command\":\"roll\"}".to_vec();
+        let timestamp = HybridLogicalClock::now();
+        let signature = sign_interaction(&Some("shared-secret".to_string()), &body, timestamp);
+
+        assert!(verify_interaction("shared-secret", &body, &signature, timestamp, timestamp).is_ok());
+        assert!(verify_interaction("wrong-secret", &body, &signature, timestamp, timestamp).is_err());
+    }
+}
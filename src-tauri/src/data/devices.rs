@@ -0,0 +1,214 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::data::clock::HybridLogicalClock;
+use crate::data::schema::{DeviceKey, KeyType, Timestamp};
+use crate::data::willow::GardenWillowError;
+
+/// A short-lived pairing code the primary device displays (as text/QR) so a
+/// new device can join a one-time gossip/Willow topic and enroll itself.
+pub struct PairingCode {
+    secret: [u8; 16],
+}
+
+impl PairingCode {
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 16];
+        OsRng.fill_bytes(&mut secret);
+        Self { secret }
+    }
+
+    pub fn as_hex(&self) -> String {
+        hex::encode(self.secret)
+    }
+
+    pub fn from_hex(code: &str) -> Result<Self, GardenWillowError> {
+        let bytes = hex::decode(code).map_err(|e| GardenWillowError::InvalidKey(e.to_string()))?;
+        let secret: [u8; 16] = bytes
+            .try_into()
+            .map_err(|_| GardenWillowError::InvalidKey("pairing code must be 16 bytes".into()))?;
+        Ok(Self { secret })
+    }
+
+    /// Deterministically derive the one-time pairing topic both devices join
+    /// to exchange the new device's key and the signed `DeviceKey` it gets back.
+    pub fn topic(&self, user_id: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"garden-device-pairing-v1");
+        hasher.update(user_id.as_bytes());
+        hasher.update(self.secret);
+        format!("pairing/{}", hex::encode(hasher.finalize()))
+    }
+}
+
+/// The authenticated set of devices allowed to act on a user's identity,
+/// published as a `Profile` entry under `device_list_path()`. Peers reject
+/// messages from devices absent from (or revoked in) the current version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedDeviceList {
+    pub user_id: String,
+    /// Monotonically increasing so a replayed, older signed list is rejected
+    /// even though its signature is still valid.
+    pub version: u64,
+    pub devices: Vec<DeviceKey>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedDeviceList {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            version: 0,
+            devices: Vec::new(),
+            signature: Vec::new(),
+        }
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        unsigned.signature = Vec::new();
+        serde_json::to_vec(&unsigned).expect("SignedDeviceList always serializes")
+    }
+
+    fn sign(&mut self, identity_key: &SigningKey) {
+        let bytes = self.signing_bytes();
+        self.signature = identity_key.sign(&bytes).to_bytes().to_vec();
+    }
+
+    /// Verify the list was signed by the user's long-term identity key.
+    pub fn verify(&self, identity_public: &VerifyingKey) -> bool {
+        let Ok(signature_bytes): Result<[u8; 64], _> = self.signature.as_slice().try_into() else {
+            return false;
+        };
+        let signature = Signature::from_bytes(&signature_bytes);
+        identity_public.verify(&self.signing_bytes(), &signature).is_ok()
+    }
+
+    /// Enroll a new device: sign a `DeviceKey` binding `user_id`, `device_id`,
+    /// the new device's public key and a timestamp with the long-term
+    /// `KeyType::Identity` key, append it, bump the version, and re-sign the list.
+    pub fn enroll_device(
+        &mut self,
+        device_id: &str,
+        public_key: Vec<u8>,
+        identity_key: &SigningKey,
+    ) -> DeviceKey {
+        let timestamp: Timestamp = HybridLogicalClock::now();
+        let mut device_key = DeviceKey {
+            user_id: self.user_id.clone(),
+            device_id: device_id.to_string(),
+            key_type: KeyType::DeviceAuth,
+            public_key,
+            signature: Vec::new(),
+            timestamp,
+        };
+
+        let unsigned = serde_json::to_vec(&(
+            &device_key.user_id,
+            &device_key.device_id,
+            &device_key.public_key,
+            device_key.timestamp,
+        ))
+        .expect("device binding tuple always serializes");
+        device_key.signature = identity_key.sign(&unsigned).to_bytes().to_vec();
+
+        self.devices.push(device_key.clone());
+        self.version += 1;
+        self.sign(identity_key);
+
+        device_key
+    }
+
+    /// Revoke a compromised device: drop it and re-sign without it, bumping
+    /// the version so the revocation can't be rolled back by replaying the
+    /// old, still-validly-signed list.
+    pub fn revoke_device(
+        &mut self,
+        device_id: &str,
+        identity_key: &SigningKey,
+    ) -> Result<(), GardenWillowError> {
+        let before = self.devices.len();
+        self.devices.retain(|d| d.device_id != device_id);
+        if self.devices.len() == before {
+            return Err(GardenWillowError::InvalidKey(format!(
+                "device {} is not in the list",
+                device_id
+            )));
+        }
+
+        self.version += 1;
+        self.sign(identity_key);
+        Ok(())
+    }
+
+    /// Whether `device_id` is currently allowed to act on this identity.
+    pub fn is_active_device(&self, device_id: &str) -> bool {
+        self.devices.iter().any(|d| d.device_id == device_id)
+    }
+
+    /// Reject a candidate list that isn't newer than what we already have, so
+    /// a stale (e.g. pre-revocation) signed list can't be replayed by a peer.
+    pub fn supersedes(&self, incoming_version: u64) -> bool {
+        incoming_version > self.version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng as SigningRng;
+    use rand::RngCore as _;
+
+    fn identity_keypair() -> SigningKey {
+        let mut bytes = [0u8; 32];
+        SigningRng.fill_bytes(&mut bytes);
+        SigningKey::from_bytes(&bytes)
+    }
+
+    #[test]
+    fn enroll_and_revoke_bumps_version_and_stays_verifiable() {
+        let identity_key = identity_keypair();
+        let identity_public = identity_key.verifying_key();
+
+        let mut list = SignedDeviceList::new("user-1");
+        list.enroll_device("device-a", vec![1, 2, 3], &identity_key);
+        assert_eq!(list.version, 1);
+        assert!(list.verify(&identity_public));
+        assert!(list.is_active_device("device-a"));
+
+        list.enroll_device("device-b", vec![4, 5, 6], &identity_key);
+        assert_eq!(list.version, 2);
+        assert!(list.verify(&identity_public));
+
+        list.revoke_device("device-a", &identity_key).unwrap();
+        assert_eq!(list.version, 3);
+        assert!(!list.is_active_device("device-a"));
+        assert!(list.is_active_device("device-b"));
+        assert!(list.verify(&identity_public));
+    }
+
+    #[test]
+    fn stale_version_is_rejected() {
+        let identity_key = identity_keypair();
+        let mut list = SignedDeviceList::new("user-1");
+        list.enroll_device("device-a", vec![1], &identity_key);
+
+        assert!(!list.supersedes(list.version));
+        assert!(!list.supersedes(list.version - 1));
+        assert!(list.supersedes(list.version + 1));
+    }
+
+    #[test]
+    fn pairing_topic_is_deterministic_for_the_same_secret() {
+        let code = PairingCode::generate();
+        let topic_a = code.topic("user-1");
+        let topic_b = code.topic("user-1");
+        assert_eq!(topic_a, topic_b);
+
+        let roundtripped = PairingCode::from_hex(&code.as_hex()).unwrap();
+        assert_eq!(roundtripped.topic("user-1"), topic_a);
+    }
+}
@@ -1,16 +1,50 @@
 use std::sync::Arc;
 use std::collections::HashMap;
-use willow::store::Store;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use serde::{Deserialize, Serialize};
 
 use crate::data::willow::{GardenWillowStore, GardenWillowError};
 
+/// Sled tree `MigrationManager` keeps its own bookkeeping in, separate
+/// from any of the Willow-managed entry namespaces.
+const SCHEMA_TREE: &str = "_schema";
+const VERSION_KEY: &[u8] = b"version";
+const IN_PROGRESS_KEY: &[u8] = b"in_progress";
+
 /// Schema version information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SchemaVersion {
     pub version: u32,
     pub applied_at: u64,
     pub description: String,
+    /// Fingerprint of the migration's definition at the time it was
+    /// applied (see `Migration::checksum`). If a later run finds this
+    /// version already recorded but the migration registered for it now
+    /// has a different checksum, its meaning has silently changed
+    /// underneath an already-applied version - `run_migrations` rejects
+    /// that instead of treating the version as a no-op.
+    pub checksum: u64,
+}
+
+/// Marks a migration (or revert) as started but not yet finished - written
+/// before `Migration::apply`/`revert` runs and cleared only after the new
+/// `SchemaVersion` is durably recorded, so a crash mid-migration leaves
+/// evidence `has_unfinished_migration` can detect on the next start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct InProgressMarker {
+    version: u32,
+    description: String,
+}
+
+/// A lightweight, non-cryptographic fingerprint of a migration's
+/// definition - just enough to catch "this migration's code changed but
+/// its version number didn't", not a portable or collision-resistant hash.
+fn checksum_of(version: u32, description: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    version.hash(&mut hasher);
+    description.hash(&mut hasher);
+    hasher.finish()
 }
 
 /// Migration handler for Gardens schema
@@ -27,12 +61,39 @@ pub struct MigrationManager {
 pub trait Migration: Send + Sync {
     /// Get the target version for this migration
     fn version(&self) -> u32;
-    
+
     /// Get a description of this migration
     fn description(&self) -> String;
-    
+
     /// Apply the migration to the store
     fn apply(&self, store: &GardenWillowStore) -> Result<(), GardenWillowError>;
+
+    /// Undo this migration, bringing the store back down to the version
+    /// below it. Called by `MigrationManager::migrate_to` when stepping
+    /// down, in descending version order.
+    fn revert(&self, store: &GardenWillowStore) -> Result<(), GardenWillowError>;
+
+    /// A stable fingerprint of this migration's definition - see
+    /// `SchemaVersion::checksum`. The default hashes `version` and
+    /// `description`; override it if a migration's behavior can change
+    /// without either of those changing too.
+    fn checksum(&self) -> u64 {
+        checksum_of(self.version(), &self.description())
+    }
+}
+
+/// Stream every raw value in `tree_name` matching `predicate` through
+/// `transform` and write the result back under the same key - the
+/// data-rewriting hook a migration uses to backfill existing rows (e.g.
+/// adding a field to `GardenEntry::DirectMessage`) rather than only ever
+/// running new code against new writes. Returns how many rows were rewritten.
+pub fn rewrite_matching_entries(
+    store: &GardenWillowStore,
+    tree_name: &str,
+    predicate: &dyn Fn(&[u8]) -> bool,
+    transform: &dyn Fn(&[u8]) -> Vec<u8>,
+) -> Result<usize, GardenWillowError> {
+    store.rewrite_tree_entries(tree_name, predicate, transform)
 }
 
 impl MigrationManager {
@@ -44,54 +105,176 @@ impl MigrationManager {
             migrations: HashMap::new(),
         }
     }
-    
+
     /// Register a migration
     pub fn register_migration(&mut self, migration: Box<dyn Migration>) {
         let version = migration.version();
         self.migrations.insert(version, migration);
     }
-    
-    /// Load the current schema version
+
+    /// Load the highest applied `SchemaVersion`'s version number, or 0 if
+    /// none has ever been recorded.
     pub async fn load_version(&mut self) -> Result<u32, GardenWillowError> {
-        // TODO: Implement loading schema version from the store
-        // For now, we'll return 0 to indicate no migrations have been applied
-        Ok(0)
-    }
-    
-    /// Save the current schema version
-    async fn save_version(&self, version: u32, description: &str) -> Result<(), GardenWillowError> {
-        // TODO: Implement saving schema version to the store
+        let version = match self.applied_version_record()? {
+            Some(record) => record.version,
+            None => 0,
+        };
+        self.current_version = version;
+        Ok(version)
+    }
+
+    /// Persist `version` as the highest applied `SchemaVersion`, tagged
+    /// with the migration's checksum at the time it was applied.
+    async fn save_version(&self, version: u32, description: &str, checksum: u64) -> Result<(), GardenWillowError> {
+        let record = SchemaVersion {
+            version,
+            applied_at: crate::data::clock::HybridLogicalClock::now(),
+            description: description.to_string(),
+            checksum,
+        };
+        let tree = self.store.open_tree(SCHEMA_TREE)?;
+        let bytes = serde_json::to_vec(&record)
+            .map_err(|e| GardenWillowError::Backend(format!("failed to serialize schema version: {}", e)))?;
+        tree.insert(VERSION_KEY, bytes)?;
+        tree.flush()?;
         Ok(())
     }
-    
-    /// Run all pending migrations
+
+    /// Clear any recorded `SchemaVersion` - used when reverting all the
+    /// way back down to version 0 ("no migrations applied").
+    fn clear_version(&self) -> Result<(), GardenWillowError> {
+        let tree = self.store.open_tree(SCHEMA_TREE)?;
+        tree.remove(VERSION_KEY)?;
+        tree.flush()?;
+        Ok(())
+    }
+
+    /// Read back the full `SchemaVersion` record last applied, if any.
+    fn applied_version_record(&self) -> Result<Option<SchemaVersion>, GardenWillowError> {
+        let tree = self.store.open_tree(SCHEMA_TREE)?;
+        match tree.get(VERSION_KEY)? {
+            Some(bytes) => {
+                let record: SchemaVersion = serde_json::from_slice(&bytes)
+                    .map_err(|e| GardenWillowError::Backend(format!("corrupt schema version record: {}", e)))?;
+                Ok(Some(record))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Write the in-progress marker for a migration (or revert) about to run.
+    fn write_in_progress_marker(&self, version: u32, description: &str) -> Result<(), GardenWillowError> {
+        let marker = InProgressMarker { version, description: description.to_string() };
+        let tree = self.store.open_tree(SCHEMA_TREE)?;
+        let bytes = serde_json::to_vec(&marker)
+            .map_err(|e| GardenWillowError::Backend(format!("failed to serialize migration marker: {}", e)))?;
+        tree.insert(IN_PROGRESS_KEY, bytes)?;
+        tree.flush()?;
+        Ok(())
+    }
+
+    fn clear_in_progress_marker(&self) -> Result<(), GardenWillowError> {
+        let tree = self.store.open_tree(SCHEMA_TREE)?;
+        tree.remove(IN_PROGRESS_KEY)?;
+        tree.flush()?;
+        Ok(())
+    }
+
+    /// Whether a previous `run_migrations`/`migrate_to` call left an
+    /// in-progress marker behind - i.e. crashed between starting a
+    /// migration (or revert) and durably recording its completion.
+    pub fn has_unfinished_migration(&self) -> Result<bool, GardenWillowError> {
+        let tree = self.store.open_tree(SCHEMA_TREE)?;
+        Ok(tree.get(IN_PROGRESS_KEY)?.is_some())
+    }
+
+    /// Run all migrations pending against the highest registered version.
     pub async fn run_migrations(&mut self) -> Result<(), GardenWillowError> {
-        // Load the current version
+        let target = self.migrations.keys().copied().max().unwrap_or(0);
+        self.migrate_to(target).await
+    }
+
+    /// Step the schema to exactly `target`: applies pending migrations in
+    /// ascending order if `target` is above the current version, or
+    /// reverts applied ones in descending order if it's below. Each step
+    /// is bracketed by an in-progress marker (write marker -> run -> write
+    /// new `SchemaVersion` -> clear marker) so a crash mid-step is
+    /// detectable via `has_unfinished_migration`.
+    pub async fn migrate_to(&mut self, target: u32) -> Result<(), GardenWillowError> {
         self.current_version = self.load_version().await?;
-        
-        // Get all migrations that need to be applied
-        let mut versions: Vec<u32> = self.migrations.keys().cloned().collect();
-        versions.sort();
-        
-        // Apply each migration in order
-        for version in versions {
-            if version > self.current_version {
-                if let Some(migration) = self.migrations.get(&version) {
-                    println!("Applying migration to version {}: {}", 
-                        version, migration.description());
-                    
-                    // Apply the migration
-                    migration.apply(&self.store)?;
-                    
-                    // Save the new version
-                    self.save_version(version, &migration.description()).await?;
-                    
-                    // Update current version
-                    self.current_version = version;
+
+        if self.current_version > 0 {
+            if let Some(applied) = self.applied_version_record()? {
+                if let Some(migration) = self.migrations.get(&self.current_version) {
+                    if applied.checksum != migration.checksum() {
+                        return Err(GardenWillowError::Backend(format!(
+                            "migration {} ({}) no longer matches the definition recorded when it was applied - refusing to silently re-run a changed migration",
+                            self.current_version, migration.description()
+                        )));
+                    }
+                }
+            }
+        }
+
+        if target > self.current_version {
+            let mut versions: Vec<u32> = self.migrations.keys()
+                .copied()
+                .filter(|v| *v > self.current_version && *v <= target)
+                .collect();
+            versions.sort();
+
+            for version in versions {
+                let (description, checksum) = {
+                    let migration = self.migrations.get(&version)
+                        .expect("version was collected from this manager's own migrations map");
+                    (migration.description(), migration.checksum())
+                };
+
+                self.write_in_progress_marker(version, &description)?;
+                println!("Applying migration to version {}: {}", version, description);
+                self.migrations.get(&version)
+                    .expect("version was collected from this manager's own migrations map")
+                    .apply(&self.store)?;
+                self.save_version(version, &description, checksum).await?;
+                self.clear_in_progress_marker()?;
+
+                self.current_version = version;
+            }
+        } else if target < self.current_version {
+            let mut versions: Vec<u32> = self.migrations.keys()
+                .copied()
+                .filter(|v| *v <= self.current_version && *v > target)
+                .collect();
+            versions.sort_by(|a, b| b.cmp(a));
+
+            for version in versions {
+                let description = self.migrations.get(&version)
+                    .expect("version was collected from this manager's own migrations map")
+                    .description();
+
+                self.write_in_progress_marker(version, &format!("reverting: {}", description))?;
+                println!("Reverting migration from version {}: {}", version, description);
+                self.migrations.get(&version)
+                    .expect("version was collected from this manager's own migrations map")
+                    .revert(&self.store)?;
+
+                let new_version = version - 1;
+                if new_version == 0 {
+                    self.clear_version()?;
+                } else {
+                    let previous = self.migrations.get(&new_version).ok_or_else(|| {
+                        GardenWillowError::Backend(format!(
+                            "no migration registered for version {} to revert down to", new_version
+                        ))
+                    })?;
+                    self.save_version(new_version, &previous.description(), previous.checksum()).await?;
                 }
+                self.clear_in_progress_marker()?;
+
+                self.current_version = new_version;
             }
         }
-        
+
         Ok(())
     }
 }
@@ -103,21 +286,148 @@ impl Migration for InitialSchemaMigration {
     fn version(&self) -> u32 {
         1
     }
-    
+
     fn description(&self) -> String {
         "Initial schema setup".to_string()
     }
-    
+
     fn apply(&self, _store: &GardenWillowStore) -> Result<(), GardenWillowError> {
         // Initial schema setup is handled by the normal schema definition
         // Nothing to do here
         Ok(())
     }
+
+    fn revert(&self, _store: &GardenWillowStore) -> Result<(), GardenWillowError> {
+        // Nothing was written by `apply`, so there's nothing to undo.
+        Ok(())
+    }
 }
 
 /// Register all migrations
 pub fn register_migrations(manager: &mut MigrationManager) {
     manager.register_migration(Box::new(InitialSchemaMigration));
-    
+
     // Register additional migrations here as the schema evolves
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::willow::StorageBackend;
+
+    async fn test_store() -> Arc<GardenWillowStore> {
+        Arc::new(GardenWillowStore::new(StorageBackend::InMemory, None).await.unwrap())
+    }
+
+    struct CountingMigration {
+        target_version: u32,
+    }
+
+    impl Migration for CountingMigration {
+        fn version(&self) -> u32 {
+            self.target_version
+        }
+
+        fn description(&self) -> String {
+            format!("counting migration {}", self.target_version)
+        }
+
+        fn apply(&self, store: &GardenWillowStore) -> Result<(), GardenWillowError> {
+            let tree = store.open_tree("test_counters")?;
+            tree.insert(b"applied", self.target_version.to_be_bytes().to_vec())?;
+            Ok(())
+        }
+
+        fn revert(&self, store: &GardenWillowStore) -> Result<(), GardenWillowError> {
+            let tree = store.open_tree("test_counters")?;
+            tree.remove(b"applied")?;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn a_fresh_store_starts_at_version_zero() {
+        let mut manager = MigrationManager::new(test_store().await);
+        assert_eq!(manager.load_version().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn running_migrations_records_the_highest_version_applied() {
+        let store = test_store().await;
+        let mut manager = MigrationManager::new(store.clone());
+        manager.register_migration(Box::new(CountingMigration { target_version: 1 }));
+        manager.register_migration(Box::new(CountingMigration { target_version: 2 }));
+
+        manager.run_migrations().await.unwrap();
+
+        assert_eq!(manager.load_version().await.unwrap(), 2);
+        assert!(!manager.has_unfinished_migration().unwrap());
+
+        let tree = store.open_tree("test_counters").unwrap();
+        assert_eq!(tree.get(b"applied").unwrap().unwrap().to_vec(), 2u32.to_be_bytes().to_vec());
+    }
+
+    #[tokio::test]
+    async fn re_running_migrations_does_not_re_apply_already_applied_versions() {
+        let store = test_store().await;
+        let mut manager = MigrationManager::new(store.clone());
+        manager.register_migration(Box::new(CountingMigration { target_version: 1 }));
+        manager.run_migrations().await.unwrap();
+
+        let mut second_manager = MigrationManager::new(store);
+        second_manager.register_migration(Box::new(CountingMigration { target_version: 1 }));
+        second_manager.run_migrations().await.unwrap();
+
+        assert_eq!(second_manager.load_version().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_changed_migration_definition_is_rejected_instead_of_silently_skipped() {
+        let store = test_store().await;
+        let mut manager = MigrationManager::new(store.clone());
+        manager.register_migration(Box::new(CountingMigration { target_version: 1 }));
+        manager.run_migrations().await.unwrap();
+
+        let mut second_manager = MigrationManager::new(store);
+        second_manager.register_migration(Box::new(InitialSchemaMigration)); // version 1, different description
+        let result = second_manager.run_migrations().await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn migrate_to_can_step_back_down_by_reverting() {
+        let store = test_store().await;
+        let mut manager = MigrationManager::new(store.clone());
+        manager.register_migration(Box::new(CountingMigration { target_version: 1 }));
+        manager.register_migration(Box::new(CountingMigration { target_version: 2 }));
+        manager.run_migrations().await.unwrap();
+        assert_eq!(manager.load_version().await.unwrap(), 2);
+
+        manager.migrate_to(0).await.unwrap();
+
+        assert_eq!(manager.load_version().await.unwrap(), 0);
+        let tree = store.open_tree("test_counters").unwrap();
+        assert!(tree.get(b"applied").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn rewrite_matching_entries_backfills_rows_passing_the_predicate() {
+        let store = test_store().await;
+        let tree = store.open_tree("backfill_test").unwrap();
+        tree.insert(b"old-1", b"v1".to_vec()).unwrap();
+        tree.insert(b"old-2", b"v2".to_vec()).unwrap();
+        tree.insert(b"already-migrated", b"already:v1".to_vec()).unwrap();
+
+        let rewritten = rewrite_matching_entries(
+            &store,
+            "backfill_test",
+            &|value: &[u8]| !value.starts_with(b"already:"),
+            &|value: &[u8]| [b"already:".as_slice(), value].concat(),
+        ).unwrap();
+
+        assert_eq!(rewritten, 2);
+        assert_eq!(tree.get(b"old-1").unwrap().unwrap().to_vec(), b"already:v1".to_vec());
+        assert_eq!(tree.get(b"already-migrated").unwrap().unwrap().to_vec(), b"already:v1".to_vec());
+    }
+}
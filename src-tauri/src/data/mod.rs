@@ -1,15 +1,17 @@
 pub mod willow;
 pub mod schema;
 pub mod migrations;
+pub mod clock;
+pub mod devices;
+pub mod commands;
 
-use std::path::PathBuf;
 use std::sync::Arc;
 
 use willow;
 use serde_json;
 use thiserror;
 
-use self::willow::{GardenWillowStore, initialize_willow_store, GardenWillowError};
+use self::willow::{GardenWillowStore, initialize_willow_store, GardenWillowError, StorageBackend};
 use self::migrations::{MigrationManager, register_migrations};
 
 /// Core data manager for Gardens
@@ -19,12 +21,12 @@ pub struct GardenDataManager {
 }
 
 impl GardenDataManager {
-    /// Initialize the data manager with the given app data directory
+    /// Initialize the data manager on the given storage backend
     pub async fn initialize(
-        app_data_dir: Option<PathBuf>,
+        backend: StorageBackend,
     ) -> Result<Self, GardenWillowError> {
         // Initialize the Willow store
-        let willow_store = initialize_willow_store(app_data_dir).await?;
+        let willow_store = initialize_willow_store(backend).await?;
         
         // Run migrations
         let mut migration_manager = MigrationManager::new(willow_store.clone());
@@ -1,20 +1,19 @@
-use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use willow::entry::{Entry, EntryBuilder};
 use willow::path::Path;
 use willow::store::Store;
 
+use crate::data::clock::HybridLogicalClock;
 use crate::data::willow::GardenWillowError;
 
-/// Timestamp in milliseconds since UNIX epoch
+/// A Hybrid Logical Clock timestamp: a 48-bit physical-time-in-ms component and a
+/// 16-bit logical counter packed into a `u64`, giving causal ordering across
+/// devices instead of relying on wall-clock alone. See [`crate::data::clock`].
 pub type Timestamp = u64;
 
 /// Get the current timestamp
 pub fn current_timestamp() -> Timestamp {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_millis() as Timestamp
+    HybridLogicalClock::now()
 }
 
 /// Message types supported in Gardens
@@ -16,15 +16,62 @@ use willow::{
 pub enum GardenWillowError {
     #[error("Database error: {0}")]
     Database(#[from] sled::Error),
-    
+
     #[error("Willow error: {0}")]
     Willow(#[from] WillowError),
-    
+
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
-    
+
     #[error("Invalid key: {0}")]
     InvalidKey(String),
+
+    /// Backend-neutral failure, so callers that swap in a non-sled
+    /// `StorageBackend` aren't coupled to `sled::Error`.
+    #[error("Storage backend error: {0}")]
+    Backend(String),
+}
+
+/// Which concrete storage the `GardenWillowStore` is backed by. `InMemory`
+/// opens a temporary sled database that is deleted when dropped and never
+/// uses `$HOME/.gardens` or the app data directory, which is what lets unit
+/// tests for `ToEntry`, path builders, and the migration manager run fast
+/// and isolated. It is not purely in memory: sled keeps that temporary
+/// database in files on disk while it is open.
+#[derive(Debug, Clone)]
+pub enum StorageBackend {
+    Sled(PathBuf),
+    InMemory,
+}
+
+impl StorageBackend {
+    /// Resolve the default on-disk backend from an optional app data
+    /// directory, falling back to `$HOME/.gardens` as the legacy default did.
+    pub fn from_app_data_dir(app_data_dir: Option<PathBuf>) -> Result<Self, GardenWillowError> {
+        let db_path = match app_data_dir {
+            Some(dir) => dir.join("gardens_willow.db"),
+            None => {
+                let mut dir = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
+                dir.push(".gardens");
+                std::fs::create_dir_all(&dir)?;
+                dir.join("gardens_willow.db")
+            }
+        };
+        Ok(StorageBackend::Sled(db_path))
+    }
+
+    fn open(&self) -> Result<(sled::Db, PathBuf), GardenWillowError> {
+        match self {
+            StorageBackend::Sled(path) => Ok((sled::open(path)?, path.clone())),
+            StorageBackend::InMemory => {
+                let db = sled::Config::new()
+                    .temporary(true)
+                    .open()
+                    .map_err(|e| GardenWillowError::Backend(e.to_string()))?;
+                Ok((db, PathBuf::from(":memory:")))
+            }
+        }
+    }
 }
 
 /// The main Willow store manager for Gardens
@@ -35,17 +82,24 @@ pub struct GardenWillowStore {
     identity: Arc<KeyIdentity>,
     /// Database path
     db_path: PathBuf,
+    /// The raw sled database `store` is built on, kept around so callers
+    /// can open auxiliary named trees (`open_tree`) for bookkeeping that
+    /// doesn't belong inside the Willow entry format itself - see
+    /// `crate::data::migrations`, which keeps its `SchemaVersion` record
+    /// and in-progress marker in one of these rather than mixed into any
+    /// of the Willow-managed entry namespaces.
+    db: sled::Db,
 }
 
 impl GardenWillowStore {
-    /// Create a new Willow store with the specified database path and identity
+    /// Create a new Willow store on the given storage backend and identity
     pub async fn new(
-        db_path: PathBuf,
+        backend: StorageBackend,
         identity_keypair: Option<KeyPair>,
     ) -> Result<Self, GardenWillowError> {
-        // Open the database
-        let db = sled::open(&db_path)?;
-        
+        // Open the database, sled or in-memory depending on the backend
+        let (db, db_path) = backend.open()?;
+
         // Generate or use the provided identity
         let identity = match identity_keypair {
             Some(keypair) => Arc::new(KeyIdentity::from(keypair)),
@@ -53,12 +107,13 @@ impl GardenWillowStore {
         };
         
         // Create the Willow store
-        let store = Arc::new(SimpleStoreSled::new(db, Parameters::recommended())?);
-        
+        let store = Arc::new(SimpleStoreSled::new(db.clone(), Parameters::recommended())?);
+
         Ok(Self {
             store,
             identity,
             db_path,
+            db,
         })
     }
     
@@ -105,6 +160,13 @@ impl GardenWillowStore {
         )
     }
     
+    /// Path for a user's signed device list, published as a `Profile` entry
+    /// so every linked device and peer can fetch the authoritative set of
+    /// devices allowed to act on this identity.
+    pub fn device_list_path(&self) -> Path {
+        self.profile_path("devicelist")
+    }
+
     /// Create a path for group/garden data
     pub fn garden_path(&self, garden_id: &str, component: &str) -> Path {
         let ns_id = NamespaceId::from_string("gardens");
@@ -117,6 +179,38 @@ impl GardenWillowStore {
         )
     }
     
+    /// Open (or create) a named sled tree alongside the Willow-managed
+    /// entry store, for auxiliary bookkeeping that doesn't belong inside
+    /// the Willow entry format itself.
+    pub fn open_tree(&self, name: &str) -> Result<sled::Tree, GardenWillowError> {
+        Ok(self.db.open_tree(name)?)
+    }
+
+    /// Stream every `(key, value)` in the named tree, replacing `value`
+    /// with `transform(value)` wherever `predicate(value)` holds, and
+    /// write the result back under the same key. Returns how many rows
+    /// were rewritten. This is the data-rewriting hook a `Migration` uses
+    /// to backfill existing rows against a schema change (see
+    /// `crate::data::migrations::rewrite_matching_entries`).
+    pub fn rewrite_tree_entries(
+        &self,
+        tree_name: &str,
+        predicate: &dyn Fn(&[u8]) -> bool,
+        transform: &dyn Fn(&[u8]) -> Vec<u8>,
+    ) -> Result<usize, GardenWillowError> {
+        let tree = self.open_tree(tree_name)?;
+        let mut rewritten = 0;
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            if predicate(&value) {
+                tree.insert(key, transform(&value))?;
+                rewritten += 1;
+            }
+        }
+        tree.flush()?;
+        Ok(rewritten)
+    }
+
     /// Close the store and clean up resources
     pub async fn close(self) -> Result<(), GardenWillowError> {
         // Explicitly close sled database
@@ -125,23 +219,24 @@ impl GardenWillowStore {
     }
 }
 
-/// Helper function to initialize the Willow store
+/// Helper function to initialize the Willow store on the given backend
 pub async fn initialize_willow_store(
-    app_data_dir: Option<PathBuf>,
+    backend: StorageBackend,
 ) -> Result<Arc<GardenWillowStore>, GardenWillowError> {
-    // Define the database path
-    let db_path = match app_data_dir {
-        Some(dir) => dir.join("gardens_willow.db"),
-        None => {
-            let mut dir = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
-            dir.push(".gardens");
-            std::fs::create_dir_all(&dir)?;
-            dir.join("gardens_willow.db")
-        }
-    };
-    
-    // Create the store
-    let store = GardenWillowStore::new(db_path, None).await?;
-    
+    let store = GardenWillowStore::new(backend, None).await?;
     Ok(Arc::new(store))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_memory_backend_uses_no_data_directory() {
+        let store = GardenWillowStore::new(StorageBackend::InMemory, None)
+            .await
+            .expect("in-memory store should open without a data directory");
+
+        assert_eq!(store.db_path, PathBuf::from(":memory:"));
+    }
+}
@@ -10,6 +10,7 @@ pub mod p2p;
 pub mod crypto;
 
 use crate::data::GardenDataManager;
+use crate::data::willow::StorageBackend;
 
 // Application state
 pub struct GardenState {
@@ -36,7 +37,15 @@ fn initialize_state(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
     // Start the data manager initialization in a background task
     let app_handle_clone = app_handle.clone();
     spawn(async move {
-        match GardenDataManager::initialize(Some(app_dir)).await {
+        let backend = match StorageBackend::from_app_data_dir(Some(app_dir)) {
+            Ok(backend) => backend,
+            Err(err) => {
+                eprintln!("Failed to resolve Gardens storage backend: {:?}", err);
+                return;
+            }
+        };
+
+        match GardenDataManager::initialize(backend).await {
             Ok(manager) => {
                 // Store the manager in the state
                 let state: State<GardenState> = app_handle_clone.state();